@@ -0,0 +1,105 @@
+use std::cmp::Ordering;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use zim::{DirectoryEntry, Target, Zim};
+
+use crate::extractor::namespace_code;
+
+#[derive(Debug, Clone)]
+pub struct SourceArchive {
+    pub zim_uuid: String,
+    pub file_name: String,
+    pub size_bytes: u64,
+    pub zim_date: Option<String>,
+    pub zim_language: Option<String>,
+    pub checksum_md5: String,
+}
+
+pub fn describe_archive(path: &Path, zim: &Zim) -> Result<SourceArchive> {
+    let size_bytes = std::fs::metadata(path)
+        .with_context(|| format!("failed to stat zim file {}", path.display()))?
+        .len();
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string());
+
+    Ok(SourceArchive {
+        zim_uuid: zim.header.uuid.to_string(),
+        file_name,
+        size_bytes,
+        zim_date: read_metadata(zim, "Date"),
+        zim_language: read_metadata(zim, "Language"),
+        checksum_md5: format!("{:x}", zim.checksum),
+    })
+}
+
+/// Reads a `M/<key>` metadata entry as text.
+pub fn read_metadata(zim: &Zim, key: &str) -> Option<String> {
+    let (_, entry) = find_by_url(zim, "M", key)?;
+    let Some(Target::Cluster(cluster_idx, blob_idx)) = entry.target else {
+        return None;
+    };
+
+    let cluster = zim.get_cluster(cluster_idx).ok()?;
+    let blob = cluster.get_blob(blob_idx).ok()?;
+    let value = String::from_utf8_lossy(blob.as_ref()).trim().to_owned();
+    (!value.is_empty()).then_some(value)
+}
+
+/// Binary-searches the URL pointer list, which is ordered by namespace then URL.
+pub fn find_by_url(zim: &Zim, namespace: &str, url: &str) -> Option<(u32, DirectoryEntry)> {
+    let mut low = 0_u32;
+    let mut high = zim.header.article_count;
+
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let entry = zim.get_by_url_index(mid).ok()?;
+        let ordering = namespace_code(entry.namespace)
+            .cmp(namespace)
+            .then_with(|| entry.url.as_str().cmp(url));
+
+        match ordering {
+            Ordering::Less => low = mid + 1,
+            Ordering::Greater => high = mid,
+            Ordering::Equal => return Some((mid, entry)),
+        }
+    }
+
+    None
+}
+
+/// Looks an entry up by title through the title pointer list, returning its URL index. Entries
+/// without a title sort under their URL, as in the ZIM spec. Archives without a title list fall
+/// back to a URL lookup.
+pub fn find_by_title(zim: &Zim, namespace: &str, title: &str) -> Option<(u32, DirectoryEntry)> {
+    if !zim.has_title_index() {
+        return find_by_url(zim, namespace, title);
+    }
+
+    let mut low = 0_u32;
+    let mut high = zim.header.article_count;
+
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let url_index = zim.url_index_by_title_index(mid)?;
+        let entry = zim.get_by_url_index(url_index).ok()?;
+        let entry_title = if entry.title.is_empty() {
+            entry.url.as_str()
+        } else {
+            entry.title.as_str()
+        };
+        let ordering = namespace_code(entry.namespace)
+            .cmp(namespace)
+            .then_with(|| entry_title.cmp(title));
+
+        match ordering {
+            Ordering::Less => low = mid + 1,
+            Ordering::Greater => high = mid,
+            Ordering::Equal => return Some((url_index, entry)),
+        }
+    }
+
+    None
+}
@@ -0,0 +1,790 @@
+//! Read-only lookup artifact for offline use: `aliases.fst` maps normalized aliases to entry
+//! ids (an [`fst::Map`]) and `entries.bin` holds each entry's definitions, so lookups need
+//! neither SQLite nor Postgres, only two memory-mapped files.
+//!
+//! `aliases.fst` is a 24-byte header, magic "ZIMRSFST" | version u32 | reserved u32 | build id
+//! u64, followed by the map. `entries.bin`, little-endian, strings as `u32` length + UTF-8 bytes:
+//!
+//! ```text
+//! header:   magic "ZIMRSENT" | version u32 | entry count u32 | build id u64
+//!           | offset table position u64
+//! entries:  u32 length | edition | title | url | definition count u32 | (language, text)*
+//! postings: u32 count | entry id u32 * count
+//! offsets:  u64 position of each entry, by entry id
+//! ```
+//!
+//! An alias naming one entry maps to `id << 1`; one naming several maps to
+//! `posting position << 1 | 1`.
+//!
+//! The two files are renamed into place one after the other, so both carry the id of the build
+//! that wrote them and [`CompactIndex::open`] refuses a pair from different builds.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result, bail};
+use fst::{Map, MapBuilder};
+use memmap2::Mmap;
+use postgres::fallible_iterator::FallibleIterator;
+use postgres::types::ToSql;
+use postgres::{Client, NoTls};
+use rusqlite::{Connection, OpenFlags, params};
+use serde::Serialize;
+
+use crate::config::{Config, StorageBackend};
+use crate::db::{build_pg_config, pg_table, validate_pg_identifier};
+use crate::extractor::canonical_language_name;
+use crate::normalization::canonicalize_lemma;
+
+pub const ALIAS_MAP_FILE: &str = "aliases.fst";
+pub const ENTRIES_FILE: &str = "entries.bin";
+
+const ENTRIES_MAGIC: &[u8; 8] = b"ZIMRSENT";
+const ENTRIES_VERSION: u32 = 2;
+const ENTRIES_HEADER_LEN: u64 = 32;
+const ALIAS_MAP_MAGIC: &[u8; 8] = b"ZIMRSFST";
+const ALIAS_MAP_VERSION: u32 = 1;
+const ALIAS_MAP_HEADER_LEN: u64 = 24;
+
+#[derive(Debug, Clone)]
+pub struct BuildIndexOptions {
+    pub output_dir: PathBuf,
+    /// Language names or ISO codes to keep; empty keeps every language.
+    pub languages: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BuildIndexMetrics {
+    pub entries: u64,
+    pub definitions: u64,
+    pub aliases: u64,
+    /// Aliases naming more than one entry, stored as posting lists.
+    pub shared_aliases: u64,
+    pub alias_map_bytes: u64,
+    pub entries_bytes: u64,
+}
+
+/// Compiles live pages with a definition in the kept languages into entries, and their
+/// aliases into the alias map. Aliases in other languages, or of pages without an entry, are
+/// left out. Files are written next to the targets and renamed into place.
+pub fn build_index(config: &Config, options: &BuildIndexOptions) -> Result<BuildIndexMetrics> {
+    fs::create_dir_all(&options.output_dir)
+        .with_context(|| format!("failed to create {}", options.output_dir.display()))?;
+    let languages = options
+        .languages
+        .iter()
+        .map(|language| language.trim())
+        .filter(|language| !language.is_empty())
+        .map(canonical_language_name)
+        .collect::<Vec<_>>();
+    let mut writer = IndexWriter::create(&options.output_dir)?;
+
+    match config.backend {
+        StorageBackend::Sqlite => {
+            let db_path = &config.input.sqlite_path;
+            let conn = Connection::open_with_flags(
+                db_path,
+                OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+            )
+            .with_context(|| format!("failed to open {}", db_path.display()))?;
+            let languages =
+                (!languages.is_empty()).then(|| serde_json::Value::from(languages).to_string());
+            sqlite_definitions(&conn, languages.as_deref(), &mut writer)?;
+            writer.finish_entries()?;
+            sqlite_aliases(&conn, languages.as_deref(), &mut writer)?;
+        }
+        StorageBackend::Postgres => {
+            validate_pg_identifier(&config.postgres.schema, "postgres.schema")?;
+            let mut client = build_pg_config(&config.postgres)?
+                .connect(NoTls)
+                .context("failed to connect to postgres")?;
+            let schema = config.postgres.schema.as_str();
+            let languages = (!languages.is_empty()).then_some(languages);
+            pg_definitions(&mut client, schema, &languages, &mut writer)?;
+            writer.finish_entries()?;
+            pg_aliases(&mut client, schema, &languages, &mut writer)?;
+        }
+    }
+
+    writer.finish()
+}
+
+fn sqlite_definitions(
+    conn: &Connection,
+    languages: Option<&str>,
+    writer: &mut IndexWriter,
+) -> Result<()> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT p.id, p.edition, p.title, p.url, d.language, d.definition_text
+        FROM pages p
+        JOIN definitions d ON d.page_id = p.id
+        WHERE p.deleted_at IS NULL
+          AND (?1 IS NULL OR d.language IN (SELECT value FROM json_each(?1)))
+        ORDER BY p.id, d.language, d.def_order
+        "#,
+    )?;
+    let mut rows = stmt.query(params![languages])?;
+    while let Some(row) = rows.next()? {
+        writer.add_definition(DefinitionRow {
+            page_id: row.get(0)?,
+            edition: row.get(1)?,
+            title: row.get(2)?,
+            url: row.get(3)?,
+            language: row.get(4)?,
+            text: row.get(5)?,
+        })?;
+    }
+    Ok(())
+}
+
+fn sqlite_aliases(
+    conn: &Connection,
+    languages: Option<&str>,
+    writer: &mut IndexWriter,
+) -> Result<()> {
+    // BINARY collation orders by bytes, as the fst builder requires.
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT a.normalized_alias, a.page_id
+        FROM lemma_aliases a
+        JOIN pages p ON p.id = a.page_id AND p.deleted_at IS NULL
+        WHERE a.normalized_alias <> ''
+          AND (?1 IS NULL OR a.language IS NULL
+               OR a.language IN (SELECT value FROM json_each(?1)))
+        ORDER BY a.normalized_alias, a.page_id
+        "#,
+    )?;
+    let mut rows = stmt.query(params![languages])?;
+    while let Some(row) = rows.next()? {
+        writer.add_alias(row.get(0)?, row.get(1)?)?;
+    }
+    Ok(())
+}
+
+fn pg_definitions(
+    client: &mut Client,
+    schema: &str,
+    languages: &Option<Vec<String>>,
+    writer: &mut IndexWriter,
+) -> Result<()> {
+    let params: [&(dyn ToSql + Sync); 1] = [languages];
+    let mut rows = client.query_raw(
+        &format!(
+            r#"
+            SELECT p.id, p.edition, p.title, p.url, d.language, d.definition_text
+            FROM {} p
+            JOIN {} d ON d.page_id = p.id
+            WHERE p.deleted_at IS NULL
+              AND ($1::text[] IS NULL OR d.language = ANY($1))
+            ORDER BY p.id, d.language, d.def_order
+            "#,
+            pg_table(schema, "pages"),
+            pg_table(schema, "definitions")
+        ),
+        params,
+    )?;
+    while let Some(row) = rows.next()? {
+        writer.add_definition(DefinitionRow {
+            page_id: row.get(0),
+            edition: row.get(1),
+            title: row.get(2),
+            url: row.get(3),
+            language: row.get(4),
+            text: row.get(5),
+        })?;
+    }
+    Ok(())
+}
+
+fn pg_aliases(
+    client: &mut Client,
+    schema: &str,
+    languages: &Option<Vec<String>>,
+    writer: &mut IndexWriter,
+) -> Result<()> {
+    let params: [&(dyn ToSql + Sync); 1] = [languages];
+    let mut rows = client.query_raw(
+        &format!(
+            r#"
+            SELECT a.normalized_alias, a.page_id
+            FROM {} a
+            JOIN {} p ON p.id = a.page_id AND p.deleted_at IS NULL
+            WHERE a.normalized_alias <> ''
+              AND ($1::text[] IS NULL OR a.language IS NULL OR a.language = ANY($1))
+            ORDER BY a.normalized_alias COLLATE "C", a.page_id
+            "#,
+            pg_table(schema, "lemma_aliases"),
+            pg_table(schema, "pages")
+        ),
+        params,
+    )?;
+    while let Some(row) = rows.next()? {
+        writer.add_alias(row.get(0), row.get(1))?;
+    }
+    Ok(())
+}
+
+struct DefinitionRow {
+    page_id: i64,
+    edition: String,
+    title: String,
+    url: String,
+    language: String,
+    text: String,
+}
+
+struct PendingEntry {
+    page_id: i64,
+    edition: String,
+    title: String,
+    url: String,
+    definitions: Vec<(String, String)>,
+}
+
+/// Streams entries into `entries.bin` and aliases into the fst builder; rows arrive grouped
+/// by page, then by alias.
+struct IndexWriter {
+    output_dir: PathBuf,
+    build_id: u64,
+    entries: BufWriter<File>,
+    position: u64,
+    offsets: Vec<u64>,
+    entry_ids: HashMap<i64, u32>,
+    pending: Option<PendingEntry>,
+    alias_map: MapBuilder<BufWriter<File>>,
+    alias: Option<(String, Vec<u32>)>,
+    metrics: BuildIndexMetrics,
+}
+
+impl IndexWriter {
+    fn create(output_dir: &Path) -> Result<Self> {
+        let path = temporary_path(output_dir, ENTRIES_FILE);
+        let file =
+            File::create(&path).with_context(|| format!("failed to create {}", path.display()))?;
+        let mut entries = BufWriter::new(file);
+        entries.write_all(&[0; ENTRIES_HEADER_LEN as usize])?;
+        let alias_path = temporary_path(output_dir, ALIAS_MAP_FILE);
+        let alias_file = File::create(&alias_path)
+            .with_context(|| format!("failed to create {}", alias_path.display()))?;
+        let mut alias_map = BufWriter::new(alias_file);
+        let build_id = new_build_id();
+        alias_map.write_all(ALIAS_MAP_MAGIC)?;
+        alias_map.write_all(&ALIAS_MAP_VERSION.to_le_bytes())?;
+        alias_map.write_all(&0_u32.to_le_bytes())?;
+        alias_map.write_all(&build_id.to_le_bytes())?;
+        Ok(Self {
+            output_dir: output_dir.to_path_buf(),
+            build_id,
+            entries,
+            position: ENTRIES_HEADER_LEN,
+            offsets: Vec::new(),
+            entry_ids: HashMap::new(),
+            pending: None,
+            alias_map: MapBuilder::new(alias_map)?,
+            alias: None,
+            metrics: BuildIndexMetrics::default(),
+        })
+    }
+
+    fn add_definition(&mut self, row: DefinitionRow) -> Result<()> {
+        if self
+            .pending
+            .as_ref()
+            .is_some_and(|entry| entry.page_id != row.page_id)
+        {
+            self.write_entry()?;
+        }
+        let entry = self.pending.get_or_insert_with(|| PendingEntry {
+            page_id: row.page_id,
+            edition: row.edition,
+            title: row.title,
+            url: row.url,
+            definitions: Vec::new(),
+        });
+        entry.definitions.push((row.language, row.text));
+        self.metrics.definitions += 1;
+        Ok(())
+    }
+
+    fn finish_entries(&mut self) -> Result<()> {
+        if self.pending.is_some() {
+            self.write_entry()?;
+        }
+        Ok(())
+    }
+
+    fn write_entry(&mut self) -> Result<()> {
+        let entry = self.pending.take().expect("pending entry");
+        let mut record = Vec::new();
+        for value in [&entry.edition, &entry.title, &entry.url] {
+            push_str(&mut record, value)?;
+        }
+        record.extend_from_slice(&(entry.definitions.len() as u32).to_le_bytes());
+        for (language, text) in &entry.definitions {
+            push_str(&mut record, language)?;
+            push_str(&mut record, text)?;
+        }
+
+        let id = u32::try_from(self.offsets.len()).context("too many entries for the index")?;
+        self.offsets.push(self.position);
+        self.entry_ids.insert(entry.page_id, id);
+        self.write(&(record.len() as u32).to_le_bytes())?;
+        self.write(&record)?;
+        self.metrics.entries += 1;
+        Ok(())
+    }
+
+    fn add_alias(&mut self, alias: String, page_id: i64) -> Result<()> {
+        let Some(&id) = self.entry_ids.get(&page_id) else {
+            return Ok(());
+        };
+        match &mut self.alias {
+            Some((current, ids)) if *current == alias => {
+                if ids.last() != Some(&id) {
+                    ids.push(id);
+                }
+            }
+            _ => {
+                self.flush_alias()?;
+                self.alias = Some((alias, vec![id]));
+            }
+        }
+        Ok(())
+    }
+
+    fn flush_alias(&mut self) -> Result<()> {
+        let Some((alias, ids)) = self.alias.take() else {
+            return Ok(());
+        };
+        let value = if let [id] = ids.as_slice() {
+            u64::from(*id) << 1
+        } else {
+            let posting = self.position;
+            self.write(&(ids.len() as u32).to_le_bytes())?;
+            for id in &ids {
+                self.write(&id.to_le_bytes())?;
+            }
+            self.metrics.shared_aliases += 1;
+            (posting << 1) | 1
+        };
+        self.metrics.aliases += 1;
+        self.alias_map
+            .insert(alias.as_bytes(), value)
+            .with_context(|| format!("alias {alias:?} is out of order"))
+    }
+
+    fn finish(mut self) -> Result<BuildIndexMetrics> {
+        self.flush_alias()?;
+
+        let offsets_position = self.position;
+        let offsets = std::mem::take(&mut self.offsets);
+        for offset in &offsets {
+            self.write(&offset.to_le_bytes())?;
+        }
+        self.metrics.entries_bytes = self.position;
+        let mut file = self
+            .entries
+            .into_inner()
+            .context("failed to flush the entries file")?;
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(ENTRIES_MAGIC)?;
+        file.write_all(&ENTRIES_VERSION.to_le_bytes())?;
+        file.write_all(&(offsets.len() as u32).to_le_bytes())?;
+        file.write_all(&self.build_id.to_le_bytes())?;
+        file.write_all(&offsets_position.to_le_bytes())?;
+        file.sync_all()?;
+
+        self.metrics.alias_map_bytes = ALIAS_MAP_HEADER_LEN + self.alias_map.bytes_written();
+        self.alias_map
+            .into_inner()?
+            .into_inner()
+            .context("failed to flush the alias map")?
+            .sync_all()?;
+
+        for name in [ENTRIES_FILE, ALIAS_MAP_FILE] {
+            let target = self.output_dir.join(name);
+            fs::rename(temporary_path(&self.output_dir, name), &target)
+                .with_context(|| format!("failed to move {} into place", target.display()))?;
+        }
+        Ok(self.metrics)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        self.entries.write_all(bytes)?;
+        self.position += bytes.len() as u64;
+        Ok(())
+    }
+}
+
+/// Distinguishes the output of one `build-index` run from any other.
+fn new_build_id() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+    nanos ^ (u64::from(std::process::id()) << 32)
+}
+
+fn temporary_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{name}.tmp"))
+}
+
+fn push_str(out: &mut Vec<u8>, value: &str) -> Result<()> {
+    let len = u32::try_from(value.len()).context("string too long for the index")?;
+    out.extend_from_slice(&len.to_le_bytes());
+    out.extend_from_slice(value.as_bytes());
+    Ok(())
+}
+
+/// One entry of a [`CompactIndex`], borrowing from the mapped file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IndexEntry<'a> {
+    pub id: u32,
+    pub edition: &'a str,
+    pub title: &'a str,
+    pub url: &'a str,
+    pub definitions: Vec<IndexDefinition<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IndexDefinition<'a> {
+    pub language: &'a str,
+    pub text: &'a str,
+}
+
+/// Reader over a `build-index` output directory. Both files are memory-mapped, so opening is
+/// cheap and lookups touch only the pages they read.
+pub struct CompactIndex {
+    aliases: Map<AliasMapBytes>,
+    entries: Mmap,
+    entry_count: u32,
+    offsets_position: usize,
+}
+
+impl CompactIndex {
+    pub fn open(dir: &Path) -> Result<Self> {
+        let alias_map = map_file(&dir.join(ALIAS_MAP_FILE))?;
+        let mut header = Cursor::new(&alias_map, 0);
+        if header.take(8)? != ALIAS_MAP_MAGIC {
+            bail!("{} is not a zimrs alias map", ALIAS_MAP_FILE);
+        }
+        let version = header.u32()?;
+        if version != ALIAS_MAP_VERSION {
+            bail!("unsupported alias map version {version}, expected {ALIAS_MAP_VERSION}");
+        }
+        header.u32()?;
+        let alias_build_id = header.u64()?;
+        let aliases = Map::new(AliasMapBytes(alias_map))
+            .with_context(|| format!("invalid {}", dir.join(ALIAS_MAP_FILE).display()))?;
+        let entries = map_file(&dir.join(ENTRIES_FILE))?;
+
+        let mut header = Cursor::new(&entries, 0);
+        if header.take(8)? != ENTRIES_MAGIC {
+            bail!("{} is not a zimrs entries file", ENTRIES_FILE);
+        }
+        let version = header.u32()?;
+        if version != ENTRIES_VERSION {
+            bail!("unsupported entries version {version}, expected {ENTRIES_VERSION}");
+        }
+        let entry_count = header.u32()?;
+        if header.u64()? != alias_build_id {
+            bail!(
+                "{ALIAS_MAP_FILE} and {ENTRIES_FILE} in {} come from different builds; rerun build-index",
+                dir.display()
+            );
+        }
+        let offsets_position = usize::try_from(header.u64()?)?;
+        let offsets_end = offsets_position.checked_add(entry_count as usize * 8);
+        if offsets_end.is_none_or(|end| end > entries.len()) {
+            bail!("{} is truncated", ENTRIES_FILE);
+        }
+
+        Ok(Self {
+            aliases,
+            entries,
+            entry_count,
+            offsets_position,
+        })
+    }
+
+    /// Number of distinct normalized aliases.
+    pub fn alias_count(&self) -> u64 {
+        self.aliases.len() as u64
+    }
+
+    pub fn entry_count(&self) -> u32 {
+        self.entry_count
+    }
+
+    /// Entries named by the term's normalized alias, in page order.
+    pub fn lookup(&self, term: &str) -> Result<Vec<IndexEntry<'_>>> {
+        self.entry_ids(&canonicalize_lemma(term))?
+            .into_iter()
+            .map(|id| self.entry(id))
+            .collect()
+    }
+
+    /// Entry ids for an already normalized alias.
+    pub fn entry_ids(&self, normalized_alias: &str) -> Result<Vec<u32>> {
+        let Some(value) = self.aliases.get(normalized_alias.as_bytes()) else {
+            return Ok(Vec::new());
+        };
+        if value & 1 == 0 {
+            return Ok(vec![u32::try_from(value >> 1)?]);
+        }
+        let mut posting = Cursor::new(&self.entries, usize::try_from(value >> 1)?);
+        let count = posting.u32()?;
+        (0..count).map(|_| posting.u32()).collect()
+    }
+
+    pub fn entry(&self, id: u32) -> Result<IndexEntry<'_>> {
+        if id >= self.entry_count {
+            bail!("entry {id} is out of range ({} entries)", self.entry_count);
+        }
+        let offset = Cursor::new(&self.entries, self.offsets_position + id as usize * 8).u64()?;
+        let mut cursor = Cursor::new(&self.entries, usize::try_from(offset)?);
+        let len = cursor.u32()? as usize;
+        let mut record = Cursor::new(cursor.take(len)?, 0);
+
+        let edition = record.str()?;
+        let title = record.str()?;
+        let url = record.str()?;
+        let count = record.u32()?;
+        let definitions = (0..count)
+            .map(|_| {
+                Ok(IndexDefinition {
+                    language: record.str()?,
+                    text: record.str()?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(IndexEntry {
+            id,
+            edition,
+            title,
+            url,
+            definitions,
+        })
+    }
+}
+
+fn map_file(path: &Path) -> Result<Mmap> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    // Index files are only ever replaced by rename, never rewritten in place.
+    unsafe { Mmap::map(&file) }.with_context(|| format!("failed to map {}", path.display()))
+}
+
+/// The mapped `aliases.fst` past its header; [`CompactIndex::open`] checks the header first.
+struct AliasMapBytes(Mmap);
+
+impl AsRef<[u8]> for AliasMapBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0[ALIAS_MAP_HEADER_LEN as usize..]
+    }
+}
+
+/// Bounds-checked little-endian reads over a mapped file.
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(bytes: &'a [u8], position: usize) -> Self {
+        Self { bytes, position }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .context("index file is truncated or corrupt")?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into()?))
+    }
+
+    fn str(&mut self) -> Result<&'a str> {
+        let len = self.u32()? as usize;
+        std::str::from_utf8(self.take(len)?).context("index file holds invalid UTF-8")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::db::{Database, PageLineage, test_postgres_config};
+    use crate::test_zim::page;
+
+    fn seed(db: &Database) {
+        for page in [
+            page(
+                "cat",
+                &[
+                    ("English", "A small domesticated feline."),
+                    ("English", "A spiteful woman."),
+                ],
+                &[("English", "cat")],
+            ),
+            page(
+                "cats",
+                &[("English", "plural of cat")],
+                &[("English", "cats"), ("English", "Cat")],
+            ),
+            page(
+                "Katze",
+                &[("German", "cat")],
+                &[("German", "Katze"), ("German", "Kätzchen")],
+            ),
+            page("orphan", &[], &[("English", "orphan")]),
+        ] {
+            db.upsert_page(&page, &PageLineage::default())
+                .expect("upsert page");
+        }
+    }
+
+    fn titles(index: &CompactIndex, term: &str) -> Vec<String> {
+        index
+            .lookup(term)
+            .expect("lookup")
+            .into_iter()
+            .map(|entry| entry.title.to_owned())
+            .collect()
+    }
+
+    /// Expectations on [`seed`] data that hold for both backends.
+    fn assert_index(config: &Config, dir: &Path) {
+        let metrics = build_index(
+            config,
+            &BuildIndexOptions {
+                output_dir: dir.join("all"),
+                languages: Vec::new(),
+            },
+        )
+        .expect("build index");
+        assert_eq!(metrics.entries, 3);
+        assert_eq!(metrics.definitions, 4);
+        assert_eq!(metrics.aliases, 4);
+        assert_eq!(metrics.shared_aliases, 1);
+
+        let index = CompactIndex::open(&dir.join("all")).expect("open index");
+        assert_eq!(index.entry_count(), 3);
+        assert_eq!(index.alias_count(), 4);
+        assert_eq!(titles(&index, " CAT "), vec!["cat", "cats"]);
+        assert_eq!(titles(&index, "kätzchen"), vec!["Katze"]);
+        assert!(titles(&index, "orphan").is_empty());
+        assert!(titles(&index, "dog").is_empty());
+
+        let cat = index.lookup("cat").expect("lookup").remove(0);
+        assert_eq!(
+            cat.definitions,
+            vec![
+                IndexDefinition {
+                    language: "English",
+                    text: "A small domesticated feline.",
+                },
+                IndexDefinition {
+                    language: "English",
+                    text: "A spiteful woman.",
+                },
+            ]
+        );
+        assert_eq!((cat.edition, cat.url), ("en", "cat"));
+        assert!(index.entry(3).is_err());
+
+        build_index(
+            config,
+            &BuildIndexOptions {
+                output_dir: dir.join("de"),
+                languages: vec!["de".to_owned()],
+            },
+        )
+        .expect("build german index");
+        let german = CompactIndex::open(&dir.join("de")).expect("open index");
+        assert_eq!(german.entry_count(), 1);
+        assert_eq!(titles(&german, "katze"), vec!["Katze"]);
+        assert!(titles(&german, "cat").is_empty());
+    }
+
+    #[test]
+    fn builds_and_reads_an_index_from_sqlite() {
+        let workdir = tempdir().expect("tempdir");
+        let mut config = Config {
+            backend: StorageBackend::Sqlite,
+            ..Config::default()
+        };
+        config.input.sqlite_path = workdir.path().join("index.sqlite");
+        let db = Database::open(&config).expect("open db");
+        db.init_schema().expect("init schema");
+        seed(&db);
+
+        assert_index(&config, workdir.path());
+        assert!(!workdir.path().join("all").join("entries.bin.tmp").exists());
+    }
+
+    #[test]
+    fn refuses_files_from_different_builds() {
+        let workdir = tempdir().expect("tempdir");
+        let mut config = Config {
+            backend: StorageBackend::Sqlite,
+            ..Config::default()
+        };
+        config.input.sqlite_path = workdir.path().join("index.sqlite");
+        let db = Database::open(&config).expect("open db");
+        db.init_schema().expect("init schema");
+        seed(&db);
+
+        let build = |name: &str| {
+            let output_dir = workdir.path().join(name);
+            let options = BuildIndexOptions {
+                output_dir: output_dir.clone(),
+                languages: Vec::new(),
+            };
+            build_index(&config, &options).expect("build index");
+            output_dir
+        };
+        let first = build("first");
+        let second = build("second");
+        fs::copy(first.join(ALIAS_MAP_FILE), second.join(ALIAS_MAP_FILE)).expect("copy aliases");
+
+        CompactIndex::open(&first).expect("open the first build");
+        let error = CompactIndex::open(&second)
+            .err()
+            .expect("mixed builds are refused");
+        assert!(error.to_string().contains("different builds"), "{error:#}");
+    }
+
+    #[test]
+    #[ignore = "needs ZIMRS_TEST_PG_HOST"]
+    fn builds_the_same_index_from_postgres() {
+        let schema = format!("zimrs_compact_{}", std::process::id());
+        let config = test_postgres_config(&schema);
+        let db = Database::open(&config).expect("open db");
+        db.init_schema().expect("init schema");
+        seed(&db);
+
+        let workdir = tempdir().expect("tempdir");
+        assert_index(&config, workdir.path());
+
+        build_pg_config(&config.postgres)
+            .expect("pg config")
+            .connect(NoTls)
+            .expect("connect to test postgres")
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE;"))
+            .expect("drop schema");
+    }
+}
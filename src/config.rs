@@ -1,10 +1,14 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use clap::ValueEnum;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+
+use crate::extractor::canonical_language_name;
+use crate::shard::ShardSpec;
+use crate::sink::SinkSpec;
 
 #[derive(Debug, Clone, Copy, Deserialize, ValueEnum, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
@@ -24,14 +28,22 @@ impl Default for StorageBackend {
 pub struct Config {
     pub backend: StorageBackend,
     pub input: InputConfig,
+    pub archives: Vec<ArchiveConfig>,
     pub postgres: PostgresConfig,
     pub selection: SelectionConfig,
     pub extraction: ExtractionConfig,
     pub sqlite: SqliteConfig,
     pub logging: LoggingConfig,
+    pub metrics: MetricsConfig,
     pub checkpoint: CheckpointConfig,
+    pub incremental: IncrementalConfig,
+    pub sweep: SweepConfig,
     pub workers: WorkerConfig,
+    pub output: OutputConfig,
+    pub sample: SampleConfig,
     pub reindex: ReindexConfig,
+    pub hot_lookup: HotLookupConfig,
+    pub serve: ServeConfig,
     pub export: ExportConfig,
     pub release: ReleaseConfig,
 }
@@ -41,14 +53,22 @@ impl Default for Config {
         Self {
             backend: StorageBackend::default(),
             input: InputConfig::default(),
+            archives: Vec::new(),
             postgres: PostgresConfig::default(),
             selection: SelectionConfig::default(),
             extraction: ExtractionConfig::default(),
             sqlite: SqliteConfig::default(),
             logging: LoggingConfig::default(),
+            metrics: MetricsConfig::default(),
             checkpoint: CheckpointConfig::default(),
+            incremental: IncrementalConfig::default(),
+            sweep: SweepConfig::default(),
             workers: WorkerConfig::default(),
+            output: OutputConfig::default(),
+            sample: SampleConfig::default(),
             reindex: ReindexConfig::default(),
+            hot_lookup: HotLookupConfig::default(),
+            serve: ServeConfig::default(),
             export: ExportConfig::default(),
             release: ReleaseConfig::default(),
         }
@@ -63,12 +83,68 @@ impl Config {
             toml::from_str(&raw).with_context(|| format!("invalid TOML in {}", path.display()))?;
         Ok(parsed)
     }
+
+    /// Resolves `[[archives]]` into one effective config per archive, in declaration order.
+    /// Without any `[[archives]]` entries the config describes a single archive on its own.
+    pub fn archive_configs(&self) -> Result<Vec<Config>> {
+        if self.archives.is_empty() {
+            return Ok(vec![self.clone()]);
+        }
+
+        let mut editions = HashSet::new();
+        let mut resolved = Vec::with_capacity(self.archives.len());
+        for archive in &self.archives {
+            let edition = archive.edition.trim();
+            if edition.is_empty() {
+                anyhow::bail!(
+                    "archive {} is missing an edition tag",
+                    archive.zim_path.display()
+                );
+            }
+            if !editions.insert(edition.to_owned()) {
+                anyhow::bail!("duplicate archive edition '{edition}'");
+            }
+
+            let mut config = self.clone();
+            config.archives = Vec::new();
+            config.input.zim_path = archive.zim_path.clone();
+            config.input.edition = edition.to_owned();
+            config.checkpoint.name = archive
+                .checkpoint_name
+                .clone()
+                .unwrap_or_else(|| format!("{}:{edition}", self.checkpoint.name));
+            if let Some(selection) = &archive.selection {
+                config.selection = selection.clone();
+            }
+            if let Some(extraction) = &archive.extraction {
+                config.extraction = extraction.clone();
+            }
+            resolved.push(config);
+        }
+
+        Ok(resolved)
+    }
+
+    /// Settings for a `sample.size` run: output goes to `sample.sqlite_path` or
+    /// `sample.postgres_schema`, which is rebuilt each time, and nothing is checkpointed, compared
+    /// or swept against the main dictionary.
+    pub fn sample_run_config(&self) -> Config {
+        let mut config = self.clone();
+        config.input.sqlite_path = self.sample.sqlite_path.clone();
+        config.postgres.schema = self.sample.postgres_schema.clone();
+        config.sqlite.overwrite = true;
+        config.checkpoint.enabled = false;
+        config.incremental.skip_unchanged = false;
+        config.sweep.enabled = false;
+        config
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(default)]
 pub struct InputConfig {
     pub zim_path: PathBuf,
+    pub edition: String,
     pub sqlite_path: PathBuf,
 }
 
@@ -76,11 +152,24 @@ impl Default for InputConfig {
     fn default() -> Self {
         Self {
             zim_path: PathBuf::from("tmp/wiktionary_en_all_nopic_2026-02.zim"),
+            edition: "en".to_owned(),
             sqlite_path: PathBuf::from("out/wiktionary.sqlite"),
         }
     }
 }
 
+/// One entry of `[[archives]]`. Profiles left unset fall back to the top-level
+/// `[selection]` / `[extraction]` tables; a profile that is set replaces them wholesale.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ArchiveConfig {
+    pub edition: String,
+    pub zim_path: PathBuf,
+    pub checkpoint_name: Option<String>,
+    pub selection: Option<SelectionConfig>,
+    pub extraction: Option<ExtractionConfig>,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(default)]
 pub struct PostgresConfig {
@@ -122,8 +211,39 @@ pub struct SelectionConfig {
     pub include_mime_prefixes: Vec<String>,
     pub exclude_url_prefixes: Vec<String>,
     pub exclude_title_prefixes: Vec<String>,
+    pub include_url_patterns: Vec<String>,
+    pub exclude_url_patterns: Vec<String>,
+    pub include_title_patterns: Vec<String>,
+    pub exclude_title_patterns: Vec<String>,
+    /// One title per line; when set, only these entries are visited instead of the full range.
+    pub title_list_path: Option<PathBuf>,
     pub skip_redirects: bool,
+    pub redirect_mode: Option<RedirectMode>,
+    pub max_redirect_hops: usize,
     pub require_title: bool,
+    pub shard: Option<ShardSpec>,
+}
+
+impl SelectionConfig {
+    /// `redirect_mode` wins when set; otherwise the legacy `skip_redirects` flag decides.
+    pub fn redirect_mode(&self) -> RedirectMode {
+        self.redirect_mode.unwrap_or(if self.skip_redirects {
+            RedirectMode::Skip
+        } else {
+            RedirectMode::Page
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, ValueEnum, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RedirectMode {
+    /// Drop redirect entries.
+    Skip,
+    /// Store each redirect as its own empty `pages` row.
+    Page,
+    /// Attach the redirect name to the resolved target page as a `redirect` alias.
+    Alias,
 }
 
 impl Default for SelectionConfig {
@@ -135,8 +255,16 @@ impl Default for SelectionConfig {
             include_mime_prefixes: vec!["text/html".to_owned()],
             exclude_url_prefixes: vec!["Special:".to_owned(), "Wiktionary:".to_owned()],
             exclude_title_prefixes: vec!["Appendix:".to_owned(), "Reconstruction:".to_owned()],
+            include_url_patterns: Vec::new(),
+            exclude_url_patterns: Vec::new(),
+            include_title_patterns: Vec::new(),
+            exclude_title_patterns: Vec::new(),
+            title_list_path: None,
             skip_redirects: true,
+            redirect_mode: None,
+            max_redirect_hops: 8,
             require_title: true,
+            shard: None,
         }
     }
 }
@@ -159,6 +287,7 @@ pub struct ExtractionConfig {
     pub confidence_threshold: f64,
     pub include_title_as_alias: bool,
     pub alias_min_length: usize,
+    pub guards: PageGuardConfig,
 }
 
 impl Default for ExtractionConfig {
@@ -183,10 +312,48 @@ impl Default for ExtractionConfig {
             confidence_threshold: 0.15,
             include_title_as_alias: true,
             alias_min_length: 2,
+            guards: PageGuardConfig::default(),
         }
     }
 }
 
+/// Per-page limits that keep one pathological page from stalling extraction. A limit of 0
+/// disables that guard. The time budget is off by default: it depends on machine load, so
+/// enabling it makes stored output vary between runs.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PageGuardConfig {
+    pub max_html_bytes: usize,
+    pub max_list_items: usize,
+    pub max_headings: usize,
+    /// Soft budget: checked between sections and list items, so a page can overrun it by the
+    /// cost of one item.
+    pub time_budget_ms: u64,
+    pub on_exceed: GuardAction,
+}
+
+impl Default for PageGuardConfig {
+    fn default() -> Self {
+        Self {
+            max_html_bytes: 4 * 1024 * 1024,
+            max_list_items: 20_000,
+            max_headings: 2_000,
+            time_budget_ms: 0,
+            on_exceed: GuardAction::Truncate,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, ValueEnum, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GuardAction {
+    /// Keep whatever was extracted before the limit was reached.
+    #[default]
+    Truncate,
+    /// Drop the page and quarantine it with stage `guard`.
+    Skip,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(default)]
 pub struct SqliteConfig {
@@ -219,6 +386,11 @@ pub struct LoggingConfig {
     pub level: String,
     pub json: bool,
     pub progress_interval: u64,
+    /// JSON progress snapshot for orchestration, rewritten atomically while converting.
+    pub status_file: Option<PathBuf>,
+    /// Draw a progress bar on stderr when it is a terminal.
+    pub progress_bar: bool,
+    pub status_interval_ms: u64,
 }
 
 impl Default for LoggingConfig {
@@ -227,6 +399,30 @@ impl Default for LoggingConfig {
             level: "info".to_owned(),
             json: false,
             progress_interval: 1_000,
+            status_file: None,
+            progress_bar: false,
+            status_interval_ms: 1_000,
+        }
+    }
+}
+
+/// Prometheus exposition for `convert` and `reindex`. Both outputs are off unless set.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    /// Address for a local `/metrics` HTTP endpoint, e.g. `127.0.0.1:9464`.
+    pub listen: Option<String>,
+    /// node-exporter textfile collector file, rewritten every `textfile_interval_ms`.
+    pub textfile_path: Option<PathBuf>,
+    pub textfile_interval_ms: u64,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            listen: None,
+            textfile_path: None,
+            textfile_interval_ms: 15_000,
         }
     }
 }
@@ -251,6 +447,48 @@ impl Default for CheckpointConfig {
     }
 }
 
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct IncrementalConfig {
+    pub skip_unchanged: bool,
+    pub hash_batch_size: usize,
+}
+
+impl Default for IncrementalConfig {
+    fn default() -> Self {
+        Self {
+            skip_unchanged: false,
+            hash_batch_size: 50_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, ValueEnum, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SweepPolicy {
+    #[default]
+    Mark,
+    Delete,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SweepConfig {
+    pub enabled: bool,
+    pub policy: SweepPolicy,
+    pub touch_batch_size: usize,
+}
+
+impl Default for SweepConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            policy: SweepPolicy::Mark,
+            touch_batch_size: 1_000,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(default)]
 pub struct WorkerConfig {
@@ -268,6 +506,65 @@ impl Default for WorkerConfig {
     }
 }
 
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct OutputConfig {
+    pub sinks: Vec<SinkSpec>,
+}
+
+impl OutputConfig {
+    pub fn uses_database(&self) -> bool {
+        self.sinks.contains(&SinkSpec::Database)
+    }
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            sinks: vec![SinkSpec::Database],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SampleConfig {
+    /// Number of entries to draw; unset means a normal convert.
+    pub size: Option<usize>,
+    pub seed: u64,
+    pub stratify: SampleStratify,
+    /// Candidates drawn per sampled entry when stratifying by language, since the language is
+    /// only known after reading the entry.
+    pub language_oversample: usize,
+    pub sqlite_path: PathBuf,
+    pub postgres_schema: String,
+}
+
+impl Default for SampleConfig {
+    fn default() -> Self {
+        Self {
+            size: None,
+            seed: 42,
+            stratify: SampleStratify::None,
+            language_oversample: 8,
+            sqlite_path: PathBuf::from("out/sample.sqlite"),
+            postgres_schema: "dictionary_sample".to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, ValueEnum, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SampleStratify {
+    /// One uniform sample over all selectable entries.
+    #[default]
+    None,
+    /// Allocate the sample across ZIM namespaces in proportion to their size.
+    Namespace,
+    /// Allocate the sample across the primary language heading of each entry.
+    Language,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(default)]
 pub struct ReindexConfig {
@@ -286,6 +583,89 @@ impl Default for ReindexConfig {
     }
 }
 
+/// The Postgres `hot_lookup` projection: one row per alias in the most-queried languages, with
+/// the page and its first definition, so exact lookups skip the canonical joins.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct HotLookupConfig {
+    /// Language names or ISO codes (`en`); codes resolve to the names definitions are stored
+    /// under. The hot partial indexes are rebuilt when this set changes.
+    pub languages: Vec<String>,
+    pub maintenance: HotLookupMaintenance,
+    /// Route exact alias lookups in a hot language to the projection first, falling back to
+    /// the canonical tables when it has no hit.
+    pub hot_first: bool,
+}
+
+impl Default for HotLookupConfig {
+    fn default() -> Self {
+        Self {
+            languages: ["English", "Spanish", "German", "French"]
+                .into_iter()
+                .map(str::to_owned)
+                .collect(),
+            maintenance: HotLookupMaintenance::Incremental,
+            hot_first: true,
+        }
+    }
+}
+
+impl HotLookupConfig {
+    /// Configured languages as stored language names, sorted and deduplicated.
+    pub fn resolved_languages(&self) -> Result<Vec<String>> {
+        let mut languages = BTreeSet::new();
+        for value in &self.languages {
+            let value = value.trim();
+            if value.is_empty() || value.chars().any(char::is_control) {
+                anyhow::bail!("invalid hot_lookup.languages entry {value:?}");
+            }
+            languages.insert(canonical_language_name(value));
+        }
+        if languages.is_empty() {
+            anyhow::bail!(
+                "hot_lookup.languages is empty; list at least one language or set maintenance = \"off\""
+            );
+        }
+        Ok(languages.into_iter().collect())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HotLookupMaintenance {
+    /// Left alone; `hot-lookup rebuild` still works.
+    Off,
+    /// Rebuilt from the canonical tables after every convert and reindex.
+    Full,
+    /// Refreshed for each page as it is written or reindexed.
+    #[default]
+    Incremental,
+}
+
+/// The read-only HTTP JSON API of `zimrs serve`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ServeConfig {
+    pub listen: String,
+    /// Request threads, and the size of the read connection pool.
+    pub threads: usize,
+    /// Page size when a request has no `limit`.
+    pub default_limit: usize,
+    /// Largest accepted `limit`.
+    pub max_limit: usize,
+}
+
+impl Default for ServeConfig {
+    fn default() -> Self {
+        Self {
+            listen: "127.0.0.1:8080".to_owned(),
+            threads: 4,
+            default_limit: 20,
+            max_limit: 100,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(default)]
 pub struct ExportConfig {
@@ -321,3 +701,82 @@ impl Default for ReleaseConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_archive_profiles_and_checkpoints() {
+        let config: Config = toml::from_str(
+            r#"
+            [selection]
+            max_entries = 10
+
+            [[archives]]
+            edition = "en"
+            zim_path = "tmp/en.zim"
+
+            [[archives]]
+            edition = "fr"
+            zim_path = "tmp/fr.zim"
+            checkpoint_name = "french"
+
+            [archives.selection]
+            include_namespaces = ["A"]
+            "#,
+        )
+        .expect("parse config");
+
+        let archives = config.archive_configs().expect("resolve archives");
+        assert_eq!(archives.len(), 2);
+        assert_eq!(archives[0].input.edition, "en");
+        assert_eq!(archives[0].checkpoint.name, "default:en");
+        assert_eq!(archives[0].selection.max_entries, Some(10));
+        assert_eq!(archives[1].checkpoint.name, "french");
+        assert_eq!(archives[1].selection.include_namespaces, vec!["A"]);
+        assert_eq!(archives[1].selection.max_entries, None);
+    }
+
+    #[test]
+    fn rejects_duplicate_editions() {
+        let config: Config = toml::from_str(
+            r#"
+            [[archives]]
+            edition = "en"
+            zim_path = "a.zim"
+
+            [[archives]]
+            edition = "en"
+            zim_path = "b.zim"
+            "#,
+        )
+        .expect("parse config");
+
+        assert!(config.archive_configs().is_err());
+    }
+
+    #[test]
+    fn resolves_hot_lookup_languages() {
+        let config: Config = toml::from_str(
+            r#"
+            [hot_lookup]
+            languages = ["de", "English", " en ", "Old English"]
+            maintenance = "full"
+            "#,
+        )
+        .expect("parse config");
+
+        assert_eq!(config.hot_lookup.maintenance, HotLookupMaintenance::Full);
+        assert_eq!(
+            config.hot_lookup.resolved_languages().expect("languages"),
+            vec!["English", "German", "Old English"]
+        );
+
+        let empty = HotLookupConfig {
+            languages: vec![" ".to_owned()],
+            ..HotLookupConfig::default()
+        };
+        assert!(empty.resolved_languages().is_err());
+    }
+}
@@ -1,20 +1,24 @@
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
-use postgres::NoTls;
 use postgres::error::SqlState;
+use postgres::{GenericClient, NoTls};
 use r2d2::Pool;
 use r2d2_postgres::PostgresConnectionManager;
 use rusqlite::{Connection, OptionalExtension, Transaction, params};
 use tracing::{debug, info, warn};
 
-use crate::config::{Config, PostgresConfig, StorageBackend};
-use crate::extractor::ExtractedPage;
-
-const TARGET_SCHEMA_VERSION: i64 = 3;
+use crate::archive::SourceArchive;
+use crate::config::{Config, HotLookupMaintenance, PostgresConfig, StorageBackend, SweepPolicy};
+use crate::extractor::{ExtractedPage, TEXT_SEARCH_CONFIGS};
+use crate::hot_lookup;
+use crate::migrations::{self, MigrationStatus};
+use crate::pipeline::RunMetrics;
+use crate::telemetry;
 
 #[derive(Debug, Clone, Default)]
 pub struct CheckpointState {
@@ -22,11 +26,64 @@ pub struct CheckpointState {
     pub ingested_pages: u64,
     pub extracted_definitions: u64,
     pub extracted_relations: u64,
+    pub run_id: Option<i64>,
+}
+
+/// Where a page write came from: the ingestion run, the source archive and the ZIM entry.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PageLineage {
+    pub run_id: Option<i64>,
+    pub source_archive_id: Option<i64>,
+    pub entry_index: Option<u32>,
+}
+
+impl PageLineage {
+    pub fn at_entry(self, entry_index: u32) -> Self {
+        Self {
+            entry_index: Some(entry_index),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SweepMetrics {
+    pub swept_pages: u64,
+    pub removed_fts_rows: u64,
+    pub removed_hot_lookup_rows: u64,
+}
+
+/// A redirect name to attach to the page stored under `target_url`.
+#[derive(Debug, Clone)]
+pub struct RedirectAlias {
+    pub target_url: String,
+    pub alias: String,
+    pub normalized_alias: String,
+}
+
+/// One quarantined entry. A later failure of the same entry overwrites the row and bumps
+/// `attempts`.
+#[derive(Debug, Clone)]
+pub struct IngestionFailure {
+    pub edition: String,
+    pub entry_index: u32,
+    pub url: Option<String>,
+    pub stage: String,
+    pub error_chain: String,
+    pub run_id: Option<i64>,
+    pub attempts: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RedirectAliasMetrics {
+    pub inserted_aliases: u64,
+    pub missing_targets: u64,
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct ReindexMetrics {
     pub updated_pages: u64,
+    pub hot_lookup_rows: u64,
     pub watermark: Option<String>,
 }
 
@@ -45,6 +102,18 @@ struct PostgresBackend {
     schema: String,
     overwrite: bool,
     enable_fts: bool,
+    hot_maintenance: HotLookupMaintenance,
+    /// Resolved `hot_lookup.languages`; empty only when maintenance is off and the list is invalid.
+    hot_languages: Vec<String>,
+}
+
+impl PostgresBackend {
+    fn hot_languages(&self) -> Result<&[String]> {
+        if self.hot_languages.is_empty() {
+            anyhow::bail!("hot_lookup.languages has no valid language");
+        }
+        Ok(&self.hot_languages)
+    }
 }
 
 pub struct Database {
@@ -88,6 +157,13 @@ impl Database {
         validate_pg_identifier(&config.postgres.database, "postgres.database")?;
         validate_pg_identifier(&config.postgres.schema, "postgres.schema")?;
 
+        let hot_languages = match config.hot_lookup.maintenance {
+            HotLookupMaintenance::Off => config.hot_lookup.resolved_languages().unwrap_or_default(),
+            HotLookupMaintenance::Full | HotLookupMaintenance::Incremental => {
+                config.hot_lookup.resolved_languages()?
+            }
+        };
+
         ensure_postgres_database(&config.postgres)?;
 
         let manager = PostgresConnectionManager::new(build_pg_config(&config.postgres)?, NoTls);
@@ -102,17 +178,57 @@ impl Database {
                 schema: config.postgres.schema.clone(),
                 overwrite: config.sqlite.overwrite,
                 enable_fts: config.sqlite.enable_fts,
+                hot_maintenance: config.hot_lookup.maintenance,
+                hot_languages,
             }),
         })
     }
 
     pub fn init_schema(&self) -> Result<()> {
         match &self.inner {
-            DatabaseInner::Sqlite(sqlite) => migrate_connection(&sqlite.conn, sqlite.enable_fts),
+            DatabaseInner::Sqlite(sqlite) => migrate_connection(&sqlite.conn),
             DatabaseInner::Postgres(pg) => pg_init_schema(pg),
         }
     }
 
+    /// Applies pending schema migrations without the rest of `init_schema` (no overwrite reset,
+    /// no FTS tables) and returns them; with `dry_run` only lists what would be applied.
+    pub fn migrate(&self, dry_run: bool) -> Result<Vec<MigrationStatus>> {
+        match &self.inner {
+            DatabaseInner::Sqlite(sqlite) => {
+                sqlite.conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+                migrations::sqlite_migrate(&sqlite.conn, dry_run)
+            }
+            DatabaseInner::Postgres(pg) => {
+                let mut conn = pg
+                    .pool
+                    .get()
+                    .context("failed to checkout postgres connection")?;
+                if !dry_run {
+                    conn.batch_execute(&format!(
+                        "CREATE SCHEMA IF NOT EXISTS {};",
+                        pg_ident(&pg.schema)
+                    ))?;
+                }
+                migrations::pg_migrate(&mut conn, &pg.schema, dry_run)
+            }
+        }
+    }
+
+    /// Every known and recorded migration with its state, for `migrate status`.
+    pub fn migration_status(&self) -> Result<Vec<MigrationStatus>> {
+        match &self.inner {
+            DatabaseInner::Sqlite(sqlite) => migrations::sqlite_status(&sqlite.conn),
+            DatabaseInner::Postgres(pg) => {
+                let mut conn = pg
+                    .pool
+                    .get()
+                    .context("failed to checkout postgres connection")?;
+                migrations::pg_status(&mut *conn, &pg.schema)
+            }
+        }
+    }
+
     pub fn enable_fts(&self) -> bool {
         match &self.inner {
             DatabaseInner::Sqlite(sqlite) => sqlite.enable_fts,
@@ -130,7 +246,8 @@ impl Database {
                         last_processed_index,
                         ingested_pages,
                         extracted_definitions,
-                        extracted_relations
+                        extracted_relations,
+                        metadata_json
                     FROM ingestion_checkpoints
                     WHERE name = ?1
                     "#,
@@ -141,6 +258,7 @@ impl Database {
                             ingested_pages: row.get::<_, i64>(1)? as u64,
                             extracted_definitions: row.get::<_, i64>(2)? as u64,
                             extracted_relations: row.get::<_, i64>(3)? as u64,
+                            run_id: checkpoint_run_id(&row.get::<_, String>(4)?),
                         })
                     },
                 )
@@ -152,7 +270,7 @@ impl Database {
                     .get()
                     .context("failed to checkout postgres connection")?;
                 let sql = format!(
-                    "SELECT last_processed_index, ingested_pages, extracted_definitions, extracted_relations FROM {} WHERE name = $1",
+                    "SELECT last_processed_index, ingested_pages, extracted_definitions, extracted_relations, metadata_json FROM {} WHERE name = $1",
                     pg_table(&pg.schema, "ingestion_checkpoints")
                 );
                 let row = conn.query_opt(&sql, &[&name])?;
@@ -161,6 +279,7 @@ impl Database {
                     ingested_pages: row.get::<_, i64>(1) as u64,
                     extracted_definitions: row.get::<_, i64>(2) as u64,
                     extracted_relations: row.get::<_, i64>(3) as u64,
+                    run_id: checkpoint_run_id(&row.get::<_, String>(4)),
                 }))
             }
         }
@@ -168,6 +287,7 @@ impl Database {
 
     pub fn save_checkpoint(&self, name: &str, state: &CheckpointState) -> Result<()> {
         let now = unix_now_ms()? as i64;
+        let metadata_json = checkpoint_metadata_json(state);
         match &self.inner {
             DatabaseInner::Sqlite(sqlite) => {
                 sqlite.conn.execute(
@@ -181,13 +301,14 @@ impl Database {
                         extracted_relations,
                         metadata_json
                     )
-                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, '{}')
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
                     ON CONFLICT(name) DO UPDATE SET
                         last_processed_index = excluded.last_processed_index,
                         updated_unix_ms = excluded.updated_unix_ms,
                         ingested_pages = excluded.ingested_pages,
                         extracted_definitions = excluded.extracted_definitions,
-                        extracted_relations = excluded.extracted_relations
+                        extracted_relations = excluded.extracted_relations,
+                        metadata_json = excluded.metadata_json
                     "#,
                     params![
                         name,
@@ -196,6 +317,7 @@ impl Database {
                         state.ingested_pages as i64,
                         state.extracted_definitions as i64,
                         state.extracted_relations as i64,
+                        metadata_json,
                     ],
                 )?;
             }
@@ -215,13 +337,14 @@ impl Database {
                         extracted_relations,
                         metadata_json
                     )
-                    VALUES ($1, $2, $3, $4, $5, $6, '{{}}')
+                    VALUES ($1, $2, $3, $4, $5, $6, $7)
                     ON CONFLICT(name) DO UPDATE SET
                         last_processed_index = EXCLUDED.last_processed_index,
                         updated_unix_ms = EXCLUDED.updated_unix_ms,
                         ingested_pages = EXCLUDED.ingested_pages,
                         extracted_definitions = EXCLUDED.extracted_definitions,
-                        extracted_relations = EXCLUDED.extracted_relations
+                        extracted_relations = EXCLUDED.extracted_relations,
+                        metadata_json = EXCLUDED.metadata_json
                     "#,
                     pg_table(&pg.schema, "ingestion_checkpoints")
                 );
@@ -234,6 +357,7 @@ impl Database {
                         &(state.ingested_pages as i64),
                         &(state.extracted_definitions as i64),
                         &(state.extracted_relations as i64),
+                        &metadata_json,
                     ],
                 )?;
             }
@@ -242,21 +366,77 @@ impl Database {
         Ok(())
     }
 
-    #[allow(clippy::too_many_arguments)]
-    pub fn insert_run_metrics(
-        &self,
-        started_unix_ms: u128,
-        finished_unix_ms: u128,
-        scanned_entries: u64,
-        filtered_entries: u64,
-        ingested_pages: u64,
-        extracted_definitions: u64,
-        extracted_relations: u64,
-        extraction_errors: u64,
-    ) -> Result<()> {
+    pub fn upsert_source_archive(&self, archive: &SourceArchive) -> Result<i64> {
+        let now = unix_now_ms()? as i64;
         match &self.inner {
-            DatabaseInner::Sqlite(sqlite) => {
-                sqlite.conn.execute(
+            DatabaseInner::Sqlite(sqlite) => sqlite
+                .conn
+                .query_row(
+                    r#"
+                    INSERT INTO source_archives(
+                        zim_uuid,
+                        file_name,
+                        size_bytes,
+                        zim_date,
+                        zim_language,
+                        checksum_md5,
+                        first_seen_unix_ms,
+                        last_seen_unix_ms
+                    )
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)
+                    ON CONFLICT(zim_uuid) DO UPDATE SET
+                        file_name = excluded.file_name,
+                        size_bytes = excluded.size_bytes,
+                        zim_date = excluded.zim_date,
+                        zim_language = excluded.zim_language,
+                        checksum_md5 = excluded.checksum_md5,
+                        last_seen_unix_ms = excluded.last_seen_unix_ms
+                    RETURNING id
+                    "#,
+                    params![
+                        &archive.zim_uuid,
+                        &archive.file_name,
+                        archive.size_bytes as i64,
+                        &archive.zim_date,
+                        &archive.zim_language,
+                        &archive.checksum_md5,
+                        now,
+                    ],
+                    |row| row.get(0),
+                )
+                .map_err(Into::into),
+            DatabaseInner::Postgres(pg) => {
+                let mut conn = pg
+                    .pool
+                    .get()
+                    .context("failed to checkout postgres connection")?;
+                let sql = format!(
+                    "INSERT INTO {}(zim_uuid, file_name, size_bytes, zim_date, zim_language, checksum_md5, first_seen_unix_ms, last_seen_unix_ms) VALUES ($1,$2,$3,$4,$5,$6,$7,$7) ON CONFLICT(zim_uuid) DO UPDATE SET file_name=EXCLUDED.file_name, size_bytes=EXCLUDED.size_bytes, zim_date=EXCLUDED.zim_date, zim_language=EXCLUDED.zim_language, checksum_md5=EXCLUDED.checksum_md5, last_seen_unix_ms=EXCLUDED.last_seen_unix_ms RETURNING id",
+                    pg_table(&pg.schema, "source_archives")
+                );
+                Ok(conn
+                    .query_one(
+                        &sql,
+                        &[
+                            &archive.zim_uuid,
+                            &archive.file_name,
+                            &(archive.size_bytes as i64),
+                            &archive.zim_date,
+                            &archive.zim_language,
+                            &archive.checksum_md5,
+                            &now,
+                        ],
+                    )?
+                    .get(0))
+            }
+        }
+    }
+
+    pub fn begin_run(&self, started_unix_ms: u128, source_archive_id: Option<i64>) -> Result<i64> {
+        match &self.inner {
+            DatabaseInner::Sqlite(sqlite) => sqlite
+                .conn
+                .query_row(
                     r#"
                     INSERT INTO ingestion_runs(
                         started_unix_ms,
@@ -266,19 +446,60 @@ impl Database {
                         ingested_pages,
                         extracted_definitions,
                         extracted_relations,
-                        extraction_errors
+                        extraction_errors,
+                        source_archive_id
                     )
-                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                    VALUES (?1, 0, 0, 0, 0, 0, 0, 0, ?2)
+                    RETURNING id
+                    "#,
+                    params![started_unix_ms as i64, source_archive_id],
+                    |row| row.get(0),
+                )
+                .map_err(Into::into),
+            DatabaseInner::Postgres(pg) => {
+                let mut conn = pg
+                    .pool
+                    .get()
+                    .context("failed to checkout postgres connection")?;
+                let sql = format!(
+                    "INSERT INTO {}(started_unix_ms, finished_unix_ms, scanned_entries, filtered_entries, ingested_pages, extracted_definitions, extracted_relations, extraction_errors, source_archive_id) VALUES ($1,0,0,0,0,0,0,0,$2) RETURNING id",
+                    pg_table(&pg.schema, "ingestion_runs")
+                );
+                Ok(conn
+                    .query_one(&sql, &[&(started_unix_ms as i64), &source_archive_id])?
+                    .get(0))
+            }
+        }
+    }
+
+    pub fn finish_run(&self, run_id: i64, metrics: &RunMetrics) -> Result<()> {
+        match &self.inner {
+            DatabaseInner::Sqlite(sqlite) => {
+                sqlite.conn.execute(
+                    r#"
+                    UPDATE ingestion_runs SET
+                        finished_unix_ms = ?2,
+                        scanned_entries = ?3,
+                        filtered_entries = ?4,
+                        ingested_pages = ?5,
+                        extracted_definitions = ?6,
+                        extracted_relations = ?7,
+                        extraction_errors = ?8,
+                        unchanged_pages = ?9,
+                        swept_pages = ?10
+                    WHERE id = ?1
                     "#,
                     params![
-                        started_unix_ms as i64,
-                        finished_unix_ms as i64,
-                        scanned_entries as i64,
-                        filtered_entries as i64,
-                        ingested_pages as i64,
-                        extracted_definitions as i64,
-                        extracted_relations as i64,
-                        extraction_errors as i64,
+                        run_id,
+                        metrics.finished_unix_ms as i64,
+                        metrics.scanned_entries as i64,
+                        metrics.filtered_entries as i64,
+                        metrics.ingested_pages as i64,
+                        metrics.extracted_definitions as i64,
+                        metrics.extracted_relations as i64,
+                        metrics.extraction_errors as i64,
+                        metrics.unchanged_pages as i64,
+                        metrics.swept_pages as i64,
                     ],
                 )?;
             }
@@ -288,20 +509,22 @@ impl Database {
                     .get()
                     .context("failed to checkout postgres connection")?;
                 let sql = format!(
-                    "INSERT INTO {}(started_unix_ms, finished_unix_ms, scanned_entries, filtered_entries, ingested_pages, extracted_definitions, extracted_relations, extraction_errors) VALUES ($1,$2,$3,$4,$5,$6,$7,$8)",
+                    "UPDATE {} SET finished_unix_ms=$2, scanned_entries=$3, filtered_entries=$4, ingested_pages=$5, extracted_definitions=$6, extracted_relations=$7, extraction_errors=$8, unchanged_pages=$9, swept_pages=$10 WHERE id = $1",
                     pg_table(&pg.schema, "ingestion_runs")
                 );
                 conn.execute(
                     &sql,
                     &[
-                        &(started_unix_ms as i64),
-                        &(finished_unix_ms as i64),
-                        &(scanned_entries as i64),
-                        &(filtered_entries as i64),
-                        &(ingested_pages as i64),
-                        &(extracted_definitions as i64),
-                        &(extracted_relations as i64),
-                        &(extraction_errors as i64),
+                        &run_id,
+                        &(metrics.finished_unix_ms as i64),
+                        &(metrics.scanned_entries as i64),
+                        &(metrics.filtered_entries as i64),
+                        &(metrics.ingested_pages as i64),
+                        &(metrics.extracted_definitions as i64),
+                        &(metrics.extracted_relations as i64),
+                        &(metrics.extraction_errors as i64),
+                        &(metrics.unchanged_pages as i64),
+                        &(metrics.swept_pages as i64),
                     ],
                 )?;
             }
@@ -310,24 +533,455 @@ impl Database {
         Ok(())
     }
 
+    pub fn mark_pages_seen(&self, run_id: i64, edition: &str, urls: &[String]) -> Result<()> {
+        if urls.is_empty() {
+            return Ok(());
+        }
+
+        match &self.inner {
+            DatabaseInner::Sqlite(sqlite) => {
+                let tx = sqlite.conn.unchecked_transaction()?;
+                {
+                    let mut stmt = tx.prepare(
+                        "UPDATE pages SET last_seen_run_id = ?1, deleted_at = NULL WHERE edition = ?2 AND url = ?3",
+                    )?;
+                    for url in urls {
+                        stmt.execute(params![run_id, edition, url])?;
+                    }
+                }
+                tx.commit()?;
+            }
+            DatabaseInner::Postgres(pg) => {
+                let mut conn = pg
+                    .pool
+                    .get()
+                    .context("failed to checkout postgres connection")?;
+                let sql = format!(
+                    "UPDATE {} SET last_seen_run_id = $1, deleted_at = NULL WHERE edition = $2 AND url = ANY($3)",
+                    pg_table(&pg.schema, "pages")
+                );
+                conn.execute(&sql, &[&run_id, &edition, &urls])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sweeps pages of `edition` that the given run did not see; other editions are untouched.
+    pub fn sweep_unseen_pages(
+        &self,
+        run_id: i64,
+        edition: &str,
+        policy: SweepPolicy,
+    ) -> Result<SweepMetrics> {
+        match &self.inner {
+            DatabaseInner::Sqlite(sqlite) => {
+                sqlite_sweep_unseen_pages(sqlite, run_id, edition, policy)
+            }
+            DatabaseInner::Postgres(pg) => pg_sweep_unseen_pages(pg, run_id, edition, policy),
+        }
+    }
+
+    /// Queues redirect entries for the end-of-run resolution pass. The queue lives in the
+    /// database so a resumed run still resolves redirects scanned before the interruption.
+    pub fn queue_redirects(&self, edition: &str, entry_indexes: &[u32]) -> Result<()> {
+        if entry_indexes.is_empty() {
+            return Ok(());
+        }
+
+        match &self.inner {
+            DatabaseInner::Sqlite(sqlite) => {
+                let tx = sqlite.conn.unchecked_transaction()?;
+                {
+                    let mut stmt = tx.prepare(
+                        "INSERT OR IGNORE INTO pending_redirects(edition, entry_index) VALUES (?1, ?2)",
+                    )?;
+                    for entry_index in entry_indexes {
+                        stmt.execute(params![edition, entry_index])?;
+                    }
+                }
+                tx.commit()?;
+            }
+            DatabaseInner::Postgres(pg) => {
+                let mut conn = pg
+                    .pool
+                    .get()
+                    .context("failed to checkout postgres connection")?;
+                let sql = format!(
+                    "INSERT INTO {}(edition, entry_index) SELECT $1, unnest($2::BIGINT[]) ON CONFLICT DO NOTHING",
+                    pg_table(&pg.schema, "pending_redirects")
+                );
+                let entry_indexes = entry_indexes
+                    .iter()
+                    .map(|entry_index| i64::from(*entry_index))
+                    .collect::<Vec<_>>();
+                conn.execute(&sql, &[&edition, &entry_indexes])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn load_pending_redirects(&self, edition: &str) -> Result<Vec<u32>> {
+        match &self.inner {
+            DatabaseInner::Sqlite(sqlite) => {
+                let mut stmt = sqlite.conn.prepare(
+                    "SELECT entry_index FROM pending_redirects WHERE edition = ?1 ORDER BY entry_index ASC",
+                )?;
+                let rows = stmt.query_map(params![edition], |row| row.get(0))?;
+                rows.collect::<rusqlite::Result<Vec<u32>>>()
+                    .map_err(Into::into)
+            }
+            DatabaseInner::Postgres(pg) => {
+                let mut conn = pg
+                    .pool
+                    .get()
+                    .context("failed to checkout postgres connection")?;
+                let sql = format!(
+                    "SELECT entry_index FROM {} WHERE edition = $1 ORDER BY entry_index ASC",
+                    pg_table(&pg.schema, "pending_redirects")
+                );
+                conn.query(&sql, &[&edition])?
+                    .iter()
+                    .map(|row| {
+                        u32::try_from(row.get::<_, i64>(0))
+                            .context("pending redirect entry index out of range")
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    pub fn clear_pending_redirects(&self, edition: &str) -> Result<()> {
+        match &self.inner {
+            DatabaseInner::Sqlite(sqlite) => {
+                sqlite.conn.execute(
+                    "DELETE FROM pending_redirects WHERE edition = ?1",
+                    params![edition],
+                )?;
+            }
+            DatabaseInner::Postgres(pg) => {
+                let mut conn = pg
+                    .pool
+                    .get()
+                    .context("failed to checkout postgres connection")?;
+                let sql = format!(
+                    "DELETE FROM {} WHERE edition = $1",
+                    pg_table(&pg.schema, "pending_redirects")
+                );
+                conn.execute(&sql, &[&edition])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds `source = 'redirect'` aliases to live pages of `edition`. Aliases already present
+    /// are left alone; aliases whose target page is not stored are counted as missing.
+    pub fn insert_redirect_aliases(
+        &self,
+        edition: &str,
+        aliases: &[RedirectAlias],
+    ) -> Result<RedirectAliasMetrics> {
+        let mut metrics = RedirectAliasMetrics::default();
+
+        match &self.inner {
+            DatabaseInner::Sqlite(sqlite) => {
+                let tx = sqlite.conn.unchecked_transaction()?;
+                {
+                    let mut find_page = tx.prepare(
+                        "SELECT id FROM pages WHERE edition = ?1 AND url = ?2 AND deleted_at IS NULL",
+                    )?;
+                    let mut insert_alias = tx.prepare(
+                        r#"
+                        INSERT INTO lemma_aliases(page_id, language, alias, normalized_alias, source)
+                        SELECT ?1, NULL, ?2, ?3, 'redirect'
+                        WHERE NOT EXISTS (
+                            SELECT 1 FROM lemma_aliases
+                            WHERE page_id = ?1 AND language IS NULL AND alias = ?2 AND source = 'redirect'
+                        )
+                        "#,
+                    )?;
+
+                    for alias in aliases {
+                        let page_id: Option<i64> = find_page
+                            .query_row(params![edition, &alias.target_url], |row| row.get(0))
+                            .optional()?;
+                        let Some(page_id) = page_id else {
+                            metrics.missing_targets += 1;
+                            continue;
+                        };
+
+                        metrics.inserted_aliases += insert_alias.execute(params![
+                            page_id,
+                            &alias.alias,
+                            &alias.normalized_alias
+                        ])? as u64;
+                    }
+                }
+                tx.commit()?;
+            }
+            DatabaseInner::Postgres(pg) => {
+                let mut conn = pg
+                    .pool
+                    .get()
+                    .context("failed to checkout postgres connection")?;
+                let pages = pg_table(&pg.schema, "pages");
+                let lemma_aliases = pg_table(&pg.schema, "lemma_aliases");
+                let find_page = format!(
+                    "SELECT id FROM {pages} WHERE edition = $1 AND url = $2 AND deleted_at IS NULL"
+                );
+                let insert_alias = format!(
+                    "INSERT INTO {lemma_aliases}(page_id, language, alias, normalized_alias, source) SELECT $1, NULL, $2, $3, 'redirect' WHERE NOT EXISTS (SELECT 1 FROM {lemma_aliases} WHERE page_id = $1 AND language IS NULL AND alias = $2 AND source = 'redirect')"
+                );
+
+                let mut tx = conn.transaction()?;
+                for alias in aliases {
+                    let Some(row) = tx.query_opt(&find_page, &[&edition, &alias.target_url])?
+                    else {
+                        metrics.missing_targets += 1;
+                        continue;
+                    };
+                    let page_id: i64 = row.get(0);
+                    metrics.inserted_aliases += tx.execute(
+                        &insert_alias,
+                        &[&page_id, &alias.alias, &alias.normalized_alias],
+                    )?;
+                }
+                tx.commit()?;
+            }
+        }
+
+        Ok(metrics)
+    }
+
+    pub fn record_failure(&self, failure: &IngestionFailure) -> Result<()> {
+        let now = unix_now_ms()? as i64;
+        match &self.inner {
+            DatabaseInner::Sqlite(sqlite) => {
+                sqlite.conn.execute(
+                    r#"
+                    INSERT INTO ingestion_failures(
+                        edition,
+                        entry_index,
+                        url,
+                        stage,
+                        error_chain,
+                        run_id,
+                        attempts,
+                        failed_unix_ms
+                    )
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, 1, ?7)
+                    ON CONFLICT(edition, entry_index) DO UPDATE SET
+                        url = COALESCE(excluded.url, ingestion_failures.url),
+                        stage = excluded.stage,
+                        error_chain = excluded.error_chain,
+                        run_id = excluded.run_id,
+                        attempts = ingestion_failures.attempts + 1,
+                        failed_unix_ms = excluded.failed_unix_ms
+                    "#,
+                    params![
+                        &failure.edition,
+                        failure.entry_index,
+                        &failure.url,
+                        &failure.stage,
+                        &failure.error_chain,
+                        failure.run_id,
+                        now,
+                    ],
+                )?;
+            }
+            DatabaseInner::Postgres(pg) => {
+                let mut conn = pg
+                    .pool
+                    .get()
+                    .context("failed to checkout postgres connection")?;
+                let failures = pg_table(&pg.schema, "ingestion_failures");
+                let sql = format!(
+                    "INSERT INTO {failures}(edition, entry_index, url, stage, error_chain, run_id, attempts, failed_unix_ms) VALUES ($1,$2,$3,$4,$5,$6,1,$7) ON CONFLICT(edition, entry_index) DO UPDATE SET url=COALESCE(EXCLUDED.url, {failures}.url), stage=EXCLUDED.stage, error_chain=EXCLUDED.error_chain, run_id=EXCLUDED.run_id, attempts={failures}.attempts + 1, failed_unix_ms=EXCLUDED.failed_unix_ms"
+                );
+                conn.execute(
+                    &sql,
+                    &[
+                        &failure.edition,
+                        &i64::from(failure.entry_index),
+                        &failure.url,
+                        &failure.stage,
+                        &failure.error_chain,
+                        &failure.run_id,
+                        &now,
+                    ],
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn load_failures(&self, edition: &str) -> Result<Vec<IngestionFailure>> {
+        match &self.inner {
+            DatabaseInner::Sqlite(sqlite) => {
+                let mut stmt = sqlite.conn.prepare(
+                    r#"
+                    SELECT entry_index, url, stage, error_chain, run_id, attempts
+                    FROM ingestion_failures
+                    WHERE edition = ?1
+                    ORDER BY entry_index ASC
+                    "#,
+                )?;
+                let rows = stmt.query_map(params![edition], |row| {
+                    Ok(IngestionFailure {
+                        edition: edition.to_owned(),
+                        entry_index: row.get(0)?,
+                        url: row.get(1)?,
+                        stage: row.get(2)?,
+                        error_chain: row.get(3)?,
+                        run_id: row.get(4)?,
+                        attempts: row.get(5)?,
+                    })
+                })?;
+                rows.collect::<rusqlite::Result<Vec<_>>>()
+                    .map_err(Into::into)
+            }
+            DatabaseInner::Postgres(pg) => {
+                let mut conn = pg
+                    .pool
+                    .get()
+                    .context("failed to checkout postgres connection")?;
+                let sql = format!(
+                    "SELECT entry_index, url, stage, error_chain, run_id, attempts FROM {} WHERE edition = $1 ORDER BY entry_index ASC",
+                    pg_table(&pg.schema, "ingestion_failures")
+                );
+                conn.query(&sql, &[&edition])?
+                    .iter()
+                    .map(|row| {
+                        Ok(IngestionFailure {
+                            edition: edition.to_owned(),
+                            entry_index: u32::try_from(row.get::<_, i64>(0))
+                                .context("failure entry index out of range")?,
+                            url: row.get(1),
+                            stage: row.get(2),
+                            error_chain: row.get(3),
+                            run_id: row.get(4),
+                            attempts: row.get(5),
+                        })
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    pub fn clear_failure(&self, edition: &str, entry_index: u32) -> Result<()> {
+        match &self.inner {
+            DatabaseInner::Sqlite(sqlite) => {
+                sqlite.conn.execute(
+                    "DELETE FROM ingestion_failures WHERE edition = ?1 AND entry_index = ?2",
+                    params![edition, entry_index],
+                )?;
+            }
+            DatabaseInner::Postgres(pg) => {
+                let mut conn = pg
+                    .pool
+                    .get()
+                    .context("failed to checkout postgres connection")?;
+                let sql = format!(
+                    "DELETE FROM {} WHERE edition = $1 AND entry_index = $2",
+                    pg_table(&pg.schema, "ingestion_failures")
+                );
+                conn.execute(&sql, &[&edition, &i64::from(entry_index)])?;
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn incremental_reindex(
         &self,
         watermark_name: &str,
         chunk_size: usize,
     ) -> Result<ReindexMetrics> {
-        match &self.inner {
+        let started = Instant::now();
+        let metrics = match &self.inner {
             DatabaseInner::Sqlite(sqlite) => {
                 sqlite_incremental_reindex(sqlite, watermark_name, chunk_size)
             }
             DatabaseInner::Postgres(pg) => pg_incremental_reindex(pg, watermark_name, chunk_size),
+        }?;
+
+        let registry = telemetry::global();
+        registry.observe_db_write("reindex", started.elapsed());
+        registry.add_reindexed_pages(metrics.updated_pages);
+        Ok(metrics)
+    }
+
+    /// Loads `url -> content_sha256` for one edition.
+    pub fn load_content_hashes(
+        &self,
+        edition: &str,
+        batch_size: usize,
+    ) -> Result<HashMap<String, String>> {
+        let batch_size = batch_size.max(1) as i64;
+        let mut out = HashMap::new();
+        let mut last_id = 0_i64;
+
+        match &self.inner {
+            DatabaseInner::Sqlite(sqlite) => {
+                let mut stmt = sqlite.conn.prepare(
+                    r#"
+                    SELECT id, url, content_sha256
+                    FROM pages
+                    WHERE id > ?1 AND edition = ?2 AND content_sha256 IS NOT NULL
+                    ORDER BY id ASC
+                    LIMIT ?3
+                    "#,
+                )?;
+
+                loop {
+                    let mut rows = stmt.query(params![last_id, edition, batch_size])?;
+                    let mut batch_count = 0_u64;
+                    while let Some(row) = rows.next()? {
+                        last_id = row.get(0)?;
+                        out.insert(row.get(1)?, row.get(2)?);
+                        batch_count += 1;
+                    }
+
+                    if batch_count == 0 {
+                        break;
+                    }
+                }
+            }
+            DatabaseInner::Postgres(pg) => {
+                let mut conn = pg
+                    .pool
+                    .get()
+                    .context("failed to checkout postgres connection")?;
+                let sql = format!(
+                    "SELECT id, url, content_sha256 FROM {} WHERE id > $1 AND edition = $2 AND content_sha256 IS NOT NULL ORDER BY id ASC LIMIT $3",
+                    pg_table(&pg.schema, "pages")
+                );
+
+                loop {
+                    let rows = conn.query(&sql, &[&last_id, &edition, &batch_size])?;
+                    if rows.is_empty() {
+                        break;
+                    }
+
+                    for row in rows {
+                        last_id = row.get(0);
+                        out.insert(row.get(1), row.get(2));
+                    }
+                }
+            }
         }
+
+        Ok(out)
     }
 
-    pub fn upsert_page(&self, page: &ExtractedPage) -> Result<()> {
+    pub fn upsert_page(&self, page: &ExtractedPage, lineage: &PageLineage) -> Result<()> {
         match &self.inner {
             DatabaseInner::Sqlite(sqlite) => {
                 let tx = sqlite.conn.unchecked_transaction()?;
-                upsert_page_sqlite(&tx, page, sqlite.enable_fts)?;
+                upsert_page_sqlite(&tx, page, lineage, sqlite.enable_fts)?;
                 tx.commit()?;
             }
             DatabaseInner::Postgres(pg) => {
@@ -335,13 +989,45 @@ impl Database {
                     .pool
                     .get()
                     .context("failed to checkout postgres connection")?;
-                upsert_page_postgres(&mut conn, &pg.schema, page, pg.enable_fts)?;
+                upsert_page_postgres(&mut conn, pg, page, lineage)?;
             }
         }
 
         Ok(())
     }
 
+    /// Rebuilds the Postgres `hot_lookup` projection from the canonical tables for the
+    /// configured languages and returns the number of rows written.
+    pub fn rebuild_hot_lookup(&self) -> Result<u64> {
+        match &self.inner {
+            DatabaseInner::Sqlite(_) => {
+                anyhow::bail!("hot_lookup is only maintained on the postgres backend")
+            }
+            DatabaseInner::Postgres(pg) => {
+                let started = Instant::now();
+                let mut conn = pg
+                    .pool
+                    .get()
+                    .context("failed to checkout postgres connection")?;
+                hot_lookup::sync_partial_indexes(&mut conn, &pg.schema, pg.hot_languages()?)?;
+                let rows = hot_lookup::rebuild(&mut conn, &pg.schema, pg.hot_languages()?)?;
+                telemetry::global().observe_db_write("hot_lookup_rebuild", started.elapsed());
+                Ok(rows)
+            }
+        }
+    }
+
+    /// End-of-run maintenance for `hot_lookup.maintenance = "full"`. Returns the rebuilt row
+    /// count, or `None` when nothing was due.
+    pub fn finish_hot_lookup(&self) -> Result<Option<u64>> {
+        match &self.inner {
+            DatabaseInner::Postgres(pg) if pg.hot_maintenance == HotLookupMaintenance::Full => {
+                self.rebuild_hot_lookup().map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
     pub fn backend_name(&self) -> &'static str {
         match self.inner {
             DatabaseInner::Sqlite(_) => "sqlite",
@@ -380,6 +1066,7 @@ fn ensure_postgres_database(pg: &PostgresConfig) -> Result<()> {
                     return Err(error).context("failed to connect to target postgres database");
                 }
 
+                telemetry::global().inc_retry("postgres_connect");
                 warn!(
                     attempt,
                     retries = connect_retries,
@@ -416,7 +1103,34 @@ fn create_postgres_database(pg: &PostgresConfig) -> Result<()> {
     Ok(())
 }
 
-fn build_pg_config(pg: &PostgresConfig) -> Result<postgres::Config> {
+/// Settings for tests against a disposable Postgres named by `ZIMRS_TEST_PG_HOST` (plus optional
+/// `ZIMRS_TEST_PG_PORT`, `_USER`, `_PASSWORD` and `_DATABASE`), writing to `schema` from
+/// scratch. Tests calling this are `#[ignore = "needs ZIMRS_TEST_PG_HOST"]`, so asking for
+/// them without a host is an error rather than a silent skip.
+#[cfg(test)]
+pub(crate) fn test_postgres_config(schema: &str) -> Config {
+    let host = std::env::var("ZIMRS_TEST_PG_HOST")
+        .expect("ZIMRS_TEST_PG_HOST must name the test Postgres server");
+    let env =
+        |name: &str, default: &str| std::env::var(name).unwrap_or_else(|_| default.to_owned());
+
+    let mut config = Config {
+        backend: StorageBackend::Postgres,
+        ..Config::default()
+    };
+    config.postgres.host = host;
+    config.postgres.port = env("ZIMRS_TEST_PG_PORT", "5432")
+        .parse()
+        .expect("ZIMRS_TEST_PG_PORT must be a port number");
+    config.postgres.user = env("ZIMRS_TEST_PG_USER", "admin");
+    config.postgres.password = env("ZIMRS_TEST_PG_PASSWORD", "admin");
+    config.postgres.database = env("ZIMRS_TEST_PG_DATABASE", "postgres");
+    config.postgres.schema = schema.to_owned();
+    config.sqlite.overwrite = true;
+    config
+}
+
+pub(crate) fn build_pg_config(pg: &PostgresConfig) -> Result<postgres::Config> {
     let mut cfg = postgres::Config::new();
     cfg.host(&pg.host);
     cfg.port(pg.port);
@@ -450,167 +1164,18 @@ fn pg_init_schema(pg: &PostgresBackend) -> Result<()> {
         conn.batch_execute(&reset_sql)?;
         info!(schema = %pg.schema, "reset postgres schema due to overwrite=true");
     } else {
-        conn.batch_execute(&format!("CREATE SCHEMA IF NOT EXISTS {schema_ident};"))?;
-    }
-
-    let pages = pg_table(&pg.schema, "pages");
-    let definitions = pg_table(&pg.schema, "definitions");
-    let relations = pg_table(&pg.schema, "relations");
-    let aliases = pg_table(&pg.schema, "lemma_aliases");
-    let runs = pg_table(&pg.schema, "ingestion_runs");
-    let checkpoints = pg_table(&pg.schema, "ingestion_checkpoints");
-    let reindex_state = pg_table(&pg.schema, "reindex_state");
-    let hot_lookup = pg_table(&pg.schema, "hot_lookup");
-    let idx_definitions_norm = format!(
-        "{}.{}",
-        pg_ident(&pg.schema),
-        pg_ident("idx_definitions_norm")
-    );
-    let idx_relations_target = format!(
-        "{}.{}",
-        pg_ident(&pg.schema),
-        pg_ident("idx_relations_target")
-    );
-
-    let ddl = format!(
-        r#"
-        CREATE TABLE IF NOT EXISTS {pages} (
-            id BIGSERIAL PRIMARY KEY,
-            url TEXT NOT NULL UNIQUE,
-            title TEXT NOT NULL,
-            namespace TEXT NOT NULL,
-            mime_type TEXT NOT NULL,
-            cluster_idx BIGINT,
-            blob_idx BIGINT,
-            redirect_url TEXT,
-            content_sha256 TEXT,
-            raw_html TEXT,
-            plain_text TEXT,
-            extraction_confidence DOUBLE PRECISION NOT NULL DEFAULT 0.0,
-            updated_at TEXT NOT NULL DEFAULT to_char(timezone('UTC', now()), 'YYYY-MM-DD"T"HH24:MI:SS.MS"Z"')
-        );
-
-        CREATE TABLE IF NOT EXISTS {definitions} (
-            id BIGSERIAL PRIMARY KEY,
-            page_id BIGINT NOT NULL REFERENCES {pages}(id) ON DELETE CASCADE,
-            language TEXT NOT NULL,
-            def_order BIGINT NOT NULL,
-            definition_text TEXT NOT NULL,
-            normalized_text TEXT NOT NULL DEFAULT '',
-            confidence DOUBLE PRECISION NOT NULL DEFAULT 0.0,
-            UNIQUE(page_id, language, def_order)
-        );
-
-        CREATE TABLE IF NOT EXISTS {runs} (
-            id BIGSERIAL PRIMARY KEY,
-            started_unix_ms BIGINT NOT NULL,
-            finished_unix_ms BIGINT NOT NULL,
-            scanned_entries BIGINT NOT NULL,
-            filtered_entries BIGINT NOT NULL,
-            ingested_pages BIGINT NOT NULL,
-            extracted_definitions BIGINT NOT NULL,
-            extracted_relations BIGINT NOT NULL DEFAULT 0,
-            extraction_errors BIGINT NOT NULL
-        );
-
-        CREATE TABLE IF NOT EXISTS {relations} (
-            id BIGSERIAL PRIMARY KEY,
-            page_id BIGINT NOT NULL REFERENCES {pages}(id) ON DELETE CASCADE,
-            language TEXT NOT NULL,
-            relation_type TEXT NOT NULL,
-            rel_order BIGINT NOT NULL,
-            source_text TEXT NOT NULL,
-            target_term TEXT NOT NULL,
-            normalized_target TEXT NOT NULL,
-            confidence DOUBLE PRECISION NOT NULL DEFAULT 0.0,
-            UNIQUE(page_id, language, relation_type, rel_order, target_term)
-        );
-
-        CREATE TABLE IF NOT EXISTS {aliases} (
-            id BIGSERIAL PRIMARY KEY,
-            page_id BIGINT NOT NULL REFERENCES {pages}(id) ON DELETE CASCADE,
-            language TEXT,
-            alias TEXT NOT NULL,
-            normalized_alias TEXT NOT NULL,
-            source TEXT NOT NULL,
-            UNIQUE(page_id, language, alias, source)
-        );
-
-        CREATE TABLE IF NOT EXISTS {checkpoints} (
-            name TEXT PRIMARY KEY,
-            last_processed_index BIGINT NOT NULL,
-            updated_unix_ms BIGINT NOT NULL,
-            ingested_pages BIGINT NOT NULL,
-            extracted_definitions BIGINT NOT NULL,
-            extracted_relations BIGINT NOT NULL,
-            metadata_json TEXT NOT NULL DEFAULT '{{}}'
-        );
-
-        CREATE TABLE IF NOT EXISTS {reindex_state} (
-            name TEXT PRIMARY KEY,
-            last_updated_at TEXT NOT NULL DEFAULT ''
-        );
-
-        CREATE TABLE IF NOT EXISTS {hot_lookup} (
-            language TEXT NOT NULL,
-            normalized_alias TEXT NOT NULL,
-            alias TEXT NOT NULL,
-            page_id BIGINT NOT NULL REFERENCES {pages}(id) ON DELETE CASCADE,
-            title TEXT NOT NULL,
-            url TEXT NOT NULL,
-            primary_definition TEXT,
-            updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
-            CONSTRAINT hot_lookup_unique UNIQUE(language, normalized_alias, page_id)
-        );
-
-        CREATE INDEX IF NOT EXISTS idx_pages_title ON {pages}(title);
-        CREATE INDEX IF NOT EXISTS idx_pages_updated_at ON {pages}(updated_at);
-        CREATE INDEX IF NOT EXISTS idx_definitions_page ON {definitions}(page_id);
-        CREATE INDEX IF NOT EXISTS idx_definitions_language ON {definitions}(language);
-        CREATE INDEX IF NOT EXISTS idx_relations_page ON {relations}(page_id);
-        CREATE INDEX IF NOT EXISTS idx_relations_type ON {relations}(relation_type);
-        CREATE INDEX IF NOT EXISTS idx_aliases_page ON {aliases}(page_id);
-        CREATE INDEX IF NOT EXISTS idx_aliases_norm ON {aliases}(normalized_alias);
-        CREATE INDEX IF NOT EXISTS idx_hot_lookup_lang_norm ON {hot_lookup}(language, normalized_alias);
-        CREATE INDEX IF NOT EXISTS idx_hot_lookup_lang_page ON {hot_lookup}(language, page_id);
-        CREATE INDEX IF NOT EXISTS idx_aliases_hot_norm_page
-            ON {aliases}(normalized_alias, page_id)
-            WHERE language IN ('English', 'Spanish', 'German', 'French');
-        CREATE INDEX IF NOT EXISTS idx_definitions_hot_page_order
-            ON {definitions}(page_id, def_order)
-            WHERE language IN ('English', 'Spanish', 'German', 'French');
-        "#
-    );
-
-    conn.batch_execute(&ddl)?;
-
-    let long_text_index_sql = format!(
-        r#"
-        DROP INDEX IF EXISTS {idx_definitions_norm};
-        DROP INDEX IF EXISTS {idx_relations_target};
-        CREATE INDEX IF NOT EXISTS idx_definitions_norm ON {definitions} ((md5(normalized_text)));
-        CREATE INDEX IF NOT EXISTS idx_relations_target ON {relations} ((md5(normalized_target)));
-        "#,
-    );
-    conn.batch_execute(&long_text_index_sql)?;
-
-    if pg.enable_fts {
-        let page_fts = pg_table(&pg.schema, "page_fts");
-        let fts_sql = format!(
-            r#"
-            CREATE TABLE IF NOT EXISTS {page_fts} (
-                page_id BIGINT PRIMARY KEY REFERENCES {pages}(id) ON DELETE CASCADE,
-                title TEXT NOT NULL,
-                url TEXT NOT NULL,
-                plain_text TEXT NOT NULL,
-                search_vector tsvector GENERATED ALWAYS AS (
-                    to_tsvector('simple', COALESCE(title, '') || ' ' || COALESCE(plain_text, ''))
-                ) STORED
-            );
-            CREATE INDEX IF NOT EXISTS idx_page_fts_vector ON {page_fts} USING GIN (search_vector);
-            "#
+        conn.batch_execute(&format!("CREATE SCHEMA IF NOT EXISTS {schema_ident};"))?;
+    }
+
+    migrations::pg_migrate(&mut conn, &pg.schema, false)?;
+    if pg.hot_maintenance != HotLookupMaintenance::Off {
+        hot_lookup::sync_partial_indexes(&mut conn, &pg.schema, pg.hot_languages()?)?;
+    }
+    if !pg_sync_trigram_index(&mut *conn, &pg.schema)? {
+        warn!(
+            schema = %pg.schema,
+            "pg_trgm is not installed; fuzzy suggestions fall back to prefix matches until a privileged role runs CREATE EXTENSION pg_trgm"
         );
-        conn.batch_execute(&fts_sql)?;
     }
 
     Ok(())
@@ -643,7 +1208,7 @@ fn sqlite_incremental_reindex(
                 r#"
                 SELECT id, title, url, COALESCE(plain_text, ''), updated_at
                 FROM pages
-                WHERE updated_at > ?1
+                WHERE updated_at > ?1 AND deleted_at IS NULL
                 ORDER BY updated_at ASC
                 LIMIT ?2
                 "#,
@@ -653,6 +1218,7 @@ fn sqlite_incremental_reindex(
                 r#"
                 SELECT id, title, url, COALESCE(plain_text, ''), updated_at
                 FROM pages
+                WHERE deleted_at IS NULL
                 ORDER BY updated_at ASC
                 LIMIT ?1
                 "#,
@@ -685,6 +1251,7 @@ fn sqlite_incremental_reindex(
                     params![page_id, title, url, plain_text],
                 )?;
             }
+            sqlite_refresh_definition_fts(&sqlite.conn, Some(page_id))?;
 
             latest_seen = Some(updated_at);
             batch_count += 1;
@@ -712,6 +1279,7 @@ fn sqlite_incremental_reindex(
 
     Ok(ReindexMetrics {
         updated_pages,
+        hot_lookup_rows: 0,
         watermark: latest_seen,
     })
 }
@@ -741,20 +1309,21 @@ fn pg_incremental_reindex(
     }
 
     let mut updated_pages = 0_u64;
+    let mut hot_lookup_rows = 0_u64;
     let mut latest_seen = watermark.clone();
 
     loop {
         let rows = if let Some(current_watermark) = watermark.as_ref() {
             conn.query(
                 &format!(
-                    "SELECT id, title, url, COALESCE(plain_text, ''), updated_at FROM {pages} WHERE updated_at > $1 ORDER BY updated_at ASC LIMIT $2"
+                    "SELECT id, title, url, COALESCE(plain_text, ''), updated_at FROM {pages} WHERE updated_at > $1 AND deleted_at IS NULL ORDER BY updated_at ASC LIMIT $2"
                 ),
                 &[current_watermark, &(chunk_size as i64)],
             )?
         } else {
             conn.query(
                 &format!(
-                    "SELECT id, title, url, COALESCE(plain_text, ''), updated_at FROM {pages} ORDER BY updated_at ASC LIMIT $1"
+                    "SELECT id, title, url, COALESCE(plain_text, ''), updated_at FROM {pages} WHERE deleted_at IS NULL ORDER BY updated_at ASC LIMIT $1"
                 ),
                 &[&(chunk_size as i64)],
             )?
@@ -782,6 +1351,13 @@ fn pg_incremental_reindex(
                     ),
                     &[&page_id, &title, &url, &plain_text],
                 )?;
+                pg_refresh_language_fts(&mut *conn, &pg.schema, Some(page_id))?;
+            }
+            pg_refresh_definition_fts(&mut *conn, &pg.schema, Some(page_id))?;
+
+            if pg.hot_maintenance == HotLookupMaintenance::Incremental {
+                hot_lookup_rows +=
+                    hot_lookup::refresh_page(&mut *conn, &pg.schema, pg.hot_languages()?, page_id)?;
             }
 
             latest_seen = Some(updated_at);
@@ -802,20 +1378,140 @@ fn pg_incremental_reindex(
 
     Ok(ReindexMetrics {
         updated_pages,
+        hot_lookup_rows,
         watermark: latest_seen,
     })
 }
 
-fn upsert_page_sqlite(tx: &Transaction<'_>, page: &ExtractedPage, enable_fts: bool) -> Result<()> {
+fn sqlite_sweep_unseen_pages(
+    sqlite: &SqliteBackend,
+    run_id: i64,
+    edition: &str,
+    policy: SweepPolicy,
+) -> Result<SweepMetrics> {
+    let tx = sqlite.conn.unchecked_transaction()?;
+    let mut metrics = SweepMetrics::default();
+
+    metrics.removed_fts_rows = tx.execute(
+        r#"
+        DELETE FROM page_fts
+        WHERE page_id IN (
+            SELECT id FROM pages
+            WHERE edition = ?2
+              AND deleted_at IS NULL
+              AND (last_seen_run_id IS NULL OR last_seen_run_id <> ?1)
+        )
+        "#,
+        params![run_id, edition],
+    )? as u64;
+    tx.execute(
+        r#"
+        DELETE FROM definition_fts
+        WHERE page_id IN (
+            SELECT id FROM pages
+            WHERE edition = ?2
+              AND deleted_at IS NULL
+              AND (last_seen_run_id IS NULL OR last_seen_run_id <> ?1)
+        )
+        "#,
+        params![run_id, edition],
+    )?;
+
+    metrics.swept_pages = match policy {
+        SweepPolicy::Mark => tx.execute(
+            r#"
+            UPDATE pages
+            SET deleted_at = strftime('%Y-%m-%dT%H:%M:%fZ','now')
+            WHERE edition = ?2
+              AND deleted_at IS NULL
+              AND (last_seen_run_id IS NULL OR last_seen_run_id <> ?1)
+            "#,
+            params![run_id, edition],
+        )?,
+        SweepPolicy::Delete => tx.execute(
+            "DELETE FROM pages WHERE edition = ?2 AND (last_seen_run_id IS NULL OR last_seen_run_id <> ?1)",
+            params![run_id, edition],
+        )?,
+    } as u64;
+
+    tx.commit()?;
+    Ok(metrics)
+}
+
+fn pg_sweep_unseen_pages(
+    pg: &PostgresBackend,
+    run_id: i64,
+    edition: &str,
+    policy: SweepPolicy,
+) -> Result<SweepMetrics> {
+    let mut conn = pg
+        .pool
+        .get()
+        .context("failed to checkout postgres connection")?;
+    let pages = pg_table(&pg.schema, "pages");
+    let page_fts = pg_table(&pg.schema, "page_fts");
+    let language_fts = pg_table(&pg.schema, "page_language_fts");
+    let definition_fts = pg_table(&pg.schema, "definition_fts");
+    let hot_lookup = pg_table(&pg.schema, "hot_lookup");
+    let unseen = format!(
+        "SELECT id FROM {pages} WHERE edition = $2 AND deleted_at IS NULL AND last_seen_run_id IS DISTINCT FROM $1"
+    );
+
+    let mut tx = conn.transaction()?;
+    let mut metrics = SweepMetrics::default();
+
+    metrics.removed_fts_rows = tx.execute(
+        &format!("DELETE FROM {page_fts} WHERE page_id IN ({unseen})"),
+        &[&run_id, &edition],
+    )?;
+    tx.execute(
+        &format!("DELETE FROM {language_fts} WHERE page_id IN ({unseen})"),
+        &[&run_id, &edition],
+    )?;
+    tx.execute(
+        &format!("DELETE FROM {definition_fts} WHERE page_id IN ({unseen})"),
+        &[&run_id, &edition],
+    )?;
+    metrics.removed_hot_lookup_rows = tx.execute(
+        &format!("DELETE FROM {hot_lookup} WHERE page_id IN ({unseen})"),
+        &[&run_id, &edition],
+    )?;
+
+    metrics.swept_pages = match policy {
+        SweepPolicy::Mark => tx.execute(
+            &format!(
+                "UPDATE {pages} SET deleted_at = to_char(timezone('UTC', now()), 'YYYY-MM-DD\"T\"HH24:MI:SS.MS\"Z\"') WHERE edition = $2 AND deleted_at IS NULL AND last_seen_run_id IS DISTINCT FROM $1"
+            ),
+            &[&run_id, &edition],
+        )?,
+        SweepPolicy::Delete => tx.execute(
+            &format!(
+                "DELETE FROM {pages} WHERE edition = $2 AND last_seen_run_id IS DISTINCT FROM $1"
+            ),
+            &[&run_id, &edition],
+        )?,
+    };
+
+    tx.commit()?;
+    Ok(metrics)
+}
+
+fn upsert_page_sqlite(
+    tx: &Transaction<'_>,
+    page: &ExtractedPage,
+    lineage: &PageLineage,
+    enable_fts: bool,
+) -> Result<()> {
     let page_id: i64 = tx.query_row(
         r#"
         INSERT INTO pages (
             url, title, namespace, mime_type, cluster_idx, blob_idx,
             redirect_url, content_sha256, raw_html, plain_text,
-            extraction_confidence, updated_at
+            extraction_confidence, last_seen_run_id, written_run_id,
+            source_archive_id, zim_entry_index, edition, updated_at
         )
-        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, strftime('%Y-%m-%dT%H:%M:%fZ','now'))
-        ON CONFLICT(url) DO UPDATE SET
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?12, ?13, ?14, ?15, strftime('%Y-%m-%dT%H:%M:%fZ','now'))
+        ON CONFLICT(edition, url) DO UPDATE SET
             title = excluded.title,
             namespace = excluded.namespace,
             mime_type = excluded.mime_type,
@@ -826,6 +1522,11 @@ fn upsert_page_sqlite(tx: &Transaction<'_>, page: &ExtractedPage, enable_fts: bo
             raw_html = excluded.raw_html,
             plain_text = excluded.plain_text,
             extraction_confidence = excluded.extraction_confidence,
+            last_seen_run_id = excluded.last_seen_run_id,
+            written_run_id = excluded.written_run_id,
+            source_archive_id = excluded.source_archive_id,
+            zim_entry_index = excluded.zim_entry_index,
+            deleted_at = NULL,
             updated_at = strftime('%Y-%m-%dT%H:%M:%fZ','now')
         RETURNING id;
         "#,
@@ -841,6 +1542,10 @@ fn upsert_page_sqlite(tx: &Transaction<'_>, page: &ExtractedPage, enable_fts: bo
             &page.raw_html,
             &page.plain_text,
             page.extraction_confidence,
+            lineage.run_id,
+            lineage.source_archive_id,
+            lineage.entry_index,
+            &page.edition,
         ],
         |row| row.get(0),
     )?;
@@ -850,8 +1555,9 @@ fn upsert_page_sqlite(tx: &Transaction<'_>, page: &ExtractedPage, enable_fts: bo
         params![page_id],
     )?;
     tx.execute("DELETE FROM relations WHERE page_id = ?1", params![page_id])?;
+    // Redirect aliases are owned by the redirect resolution pass, not by the page body.
     tx.execute(
-        "DELETE FROM lemma_aliases WHERE page_id = ?1",
+        "DELETE FROM lemma_aliases WHERE page_id = ?1 AND source <> 'redirect'",
         params![page_id],
     )?;
 
@@ -926,8 +1632,9 @@ fn upsert_page_sqlite(tx: &Transaction<'_>, page: &ExtractedPage, enable_fts: bo
         )?;
     }
 
+    // page_fts always exists; with FTS off the page's old row is dropped rather than left stale.
+    tx.execute("DELETE FROM page_fts WHERE page_id = ?1", params![page_id])?;
     if enable_fts {
-        tx.execute("DELETE FROM page_fts WHERE page_id = ?1", params![page_id])?;
         tx.execute(
             r#"
             INSERT INTO page_fts(page_id, title, url, plain_text)
@@ -941,31 +1648,35 @@ fn upsert_page_sqlite(tx: &Transaction<'_>, page: &ExtractedPage, enable_fts: bo
             ],
         )?;
     }
+    sqlite_refresh_definition_fts(tx, Some(page_id))?;
 
     Ok(())
 }
 
 fn upsert_page_postgres(
     conn: &mut r2d2::PooledConnection<PostgresConnectionManager<NoTls>>,
-    schema: &str,
+    pg: &PostgresBackend,
     page: &ExtractedPage,
-    enable_fts: bool,
+    lineage: &PageLineage,
 ) -> Result<()> {
+    let schema = pg.schema.as_str();
     let pages = pg_table(schema, "pages");
     let definitions = pg_table(schema, "definitions");
     let relations = pg_table(schema, "relations");
     let aliases = pg_table(schema, "lemma_aliases");
     let page_fts = pg_table(schema, "page_fts");
+    let language_fts = pg_table(schema, "page_language_fts");
 
     let mut tx = conn.transaction()?;
 
     let cluster_idx = page.cluster_idx.map(i64::from);
     let blob_idx = page.blob_idx.map(i64::from);
+    let entry_index = lineage.entry_index.map(i64::from);
 
     let page_id: i64 = tx
         .query_one(
             &format!(
-                "INSERT INTO {pages} (url, title, namespace, mime_type, cluster_idx, blob_idx, redirect_url, content_sha256, raw_html, plain_text, extraction_confidence, updated_at) VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,to_char(timezone('UTC', now()), 'YYYY-MM-DD\"T\"HH24:MI:SS.MS\"Z\"')) ON CONFLICT(url) DO UPDATE SET title=EXCLUDED.title, namespace=EXCLUDED.namespace, mime_type=EXCLUDED.mime_type, cluster_idx=EXCLUDED.cluster_idx, blob_idx=EXCLUDED.blob_idx, redirect_url=EXCLUDED.redirect_url, content_sha256=EXCLUDED.content_sha256, raw_html=EXCLUDED.raw_html, plain_text=EXCLUDED.plain_text, extraction_confidence=EXCLUDED.extraction_confidence, updated_at=to_char(timezone('UTC', now()), 'YYYY-MM-DD\"T\"HH24:MI:SS.MS\"Z\"') RETURNING id"
+                "INSERT INTO {pages} (url, title, namespace, mime_type, cluster_idx, blob_idx, redirect_url, content_sha256, raw_html, plain_text, extraction_confidence, last_seen_run_id, written_run_id, source_archive_id, zim_entry_index, edition, updated_at) VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$12,$13,$14,$15,to_char(timezone('UTC', now()), 'YYYY-MM-DD\"T\"HH24:MI:SS.MS\"Z\"')) ON CONFLICT(edition, url) DO UPDATE SET title=EXCLUDED.title, namespace=EXCLUDED.namespace, mime_type=EXCLUDED.mime_type, cluster_idx=EXCLUDED.cluster_idx, blob_idx=EXCLUDED.blob_idx, redirect_url=EXCLUDED.redirect_url, content_sha256=EXCLUDED.content_sha256, raw_html=EXCLUDED.raw_html, plain_text=EXCLUDED.plain_text, extraction_confidence=EXCLUDED.extraction_confidence, last_seen_run_id=EXCLUDED.last_seen_run_id, written_run_id=EXCLUDED.written_run_id, source_archive_id=EXCLUDED.source_archive_id, zim_entry_index=EXCLUDED.zim_entry_index, deleted_at=NULL, updated_at=to_char(timezone('UTC', now()), 'YYYY-MM-DD\"T\"HH24:MI:SS.MS\"Z\"') RETURNING id"
             ),
             &[
                 &page.url,
@@ -979,6 +1690,10 @@ fn upsert_page_postgres(
                 &page.raw_html,
                 &page.plain_text,
                 &page.extraction_confidence,
+                &lineage.run_id,
+                &lineage.source_archive_id,
+                &entry_index,
+                &page.edition,
             ],
         )?
         .get(0);
@@ -992,7 +1707,7 @@ fn upsert_page_postgres(
         &[&page_id],
     )?;
     tx.execute(
-        &format!("DELETE FROM {aliases} WHERE page_id = $1"),
+        &format!("DELETE FROM {aliases} WHERE page_id = $1 AND source <> 'redirect'"),
         &[&page_id],
     )?;
 
@@ -1055,11 +1770,13 @@ fn upsert_page_postgres(
         )?;
     }
 
-    if enable_fts {
-        tx.execute(
-            &format!("DELETE FROM {page_fts} WHERE page_id = $1"),
-            &[&page_id],
-        )?;
+    // page_fts and page_language_fts always exist; with FTS off the page's old rows are dropped
+    // rather than left stale.
+    tx.execute(
+        &format!("DELETE FROM {page_fts} WHERE page_id = $1"),
+        &[&page_id],
+    )?;
+    if pg.enable_fts {
         tx.execute(
             &format!(
                 "INSERT INTO {page_fts}(page_id, title, url, plain_text) VALUES ($1,$2,$3,$4) ON CONFLICT(page_id) DO UPDATE SET title=EXCLUDED.title, url=EXCLUDED.url, plain_text=EXCLUDED.plain_text"
@@ -1071,21 +1788,120 @@ fn upsert_page_postgres(
                 &page.plain_text.as_deref().unwrap_or(""),
             ],
         )?;
+        pg_refresh_language_fts(&mut tx, schema, Some(page_id))?;
+    } else {
+        tx.execute(
+            &format!("DELETE FROM {language_fts} WHERE page_id = $1"),
+            &[&page_id],
+        )?;
+    }
+    pg_refresh_definition_fts(&mut tx, schema, Some(page_id))?;
+
+    if pg.hot_maintenance == HotLookupMaintenance::Incremental {
+        hot_lookup::refresh_page(&mut tx, schema, pg.hot_languages()?, page_id)?;
     }
 
     tx.commit()?;
     Ok(())
 }
 
-fn pg_table(schema: &str, table: &str) -> String {
+/// Creates the trigram index behind fuzzy suggestions when `pg_trgm` was installed after the
+/// schema was migrated. Returns whether the extension is installed.
+fn pg_sync_trigram_index(client: &mut impl GenericClient, schema: &str) -> Result<bool> {
+    let installed: bool = client
+        .query_one(
+            "SELECT EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'pg_trgm')",
+            &[],
+        )?
+        .get(0);
+    if installed {
+        client.batch_execute(&format!(
+            "CREATE INDEX IF NOT EXISTS idx_aliases_norm_trgm ON {} USING GIST (normalized_alias gist_trgm_ops);",
+            pg_table(schema, "lemma_aliases")
+        ))?;
+    }
+    Ok(installed)
+}
+
+/// Rebuilds `page_language_fts` rows, one per definition language, stemmed with the language's
+/// text search configuration (`simple` when unmapped). `None` rebuilds every live page.
+fn pg_refresh_language_fts(
+    client: &mut impl GenericClient,
+    schema: &str,
+    page_id: Option<i64>,
+) -> Result<u64> {
+    let language_fts = pg_table(schema, "page_language_fts");
+    let definitions = pg_table(schema, "definitions");
+    let pages = pg_table(schema, "pages");
+    let (languages, configs): (Vec<&str>, Vec<&str>) = TEXT_SEARCH_CONFIGS.iter().copied().unzip();
+
+    client.execute(
+        &format!("DELETE FROM {language_fts} WHERE $1::bigint IS NULL OR page_id = $1"),
+        &[&page_id],
+    )?;
+    let rows = client.execute(
+        &format!(
+            r#"
+            INSERT INTO {language_fts}(page_id, language, search_vector)
+            SELECT d.page_id, d.language,
+                   to_tsvector(COALESCE(m.config, 'simple')::regconfig,
+                               string_agg(d.definition_text, ' ' ORDER BY d.def_order))
+            FROM {definitions} d
+            JOIN {pages} p ON p.id = d.page_id AND p.deleted_at IS NULL
+            LEFT JOIN unnest($2::text[], $3::text[]) AS m(language, config)
+              ON m.language = d.language
+            WHERE $1::bigint IS NULL OR d.page_id = $1
+            GROUP BY d.page_id, d.language, m.config
+            "#
+        ),
+        &[&page_id, &languages, &configs],
+    )?;
+    Ok(rows)
+}
+
+/// Rebuilds `definition_fts` rows, one per sense, stemmed like [`pg_refresh_language_fts`].
+/// `None` rebuilds every live page.
+fn pg_refresh_definition_fts(
+    client: &mut impl GenericClient,
+    schema: &str,
+    page_id: Option<i64>,
+) -> Result<u64> {
+    let definition_fts = pg_table(schema, "definition_fts");
+    let definitions = pg_table(schema, "definitions");
+    let pages = pg_table(schema, "pages");
+    let (languages, configs): (Vec<&str>, Vec<&str>) = TEXT_SEARCH_CONFIGS.iter().copied().unzip();
+
+    client.execute(
+        &format!("DELETE FROM {definition_fts} WHERE $1::bigint IS NULL OR page_id = $1"),
+        &[&page_id],
+    )?;
+    let rows = client.execute(
+        &format!(
+            r#"
+            INSERT INTO {definition_fts}(definition_id, page_id, language, search_vector)
+            SELECT d.id, d.page_id, d.language,
+                   to_tsvector(COALESCE(m.config, 'simple')::regconfig, d.definition_text)
+            FROM {definitions} d
+            JOIN {pages} p ON p.id = d.page_id AND p.deleted_at IS NULL
+            LEFT JOIN unnest($2::text[], $3::text[]) AS m(language, config)
+              ON m.language = d.language
+            WHERE $1::bigint IS NULL OR d.page_id = $1
+            "#
+        ),
+        &[&page_id, &languages, &configs],
+    )?;
+    Ok(rows)
+}
+
+pub(crate) fn pg_table(schema: &str, table: &str) -> String {
     format!("{}.{}", pg_ident(schema), pg_ident(table))
 }
 
-fn pg_ident(name: &str) -> String {
+pub(crate) fn pg_ident(name: &str) -> String {
     format!("\"{}\"", name.replace('"', "\"\""))
 }
 
-fn validate_pg_identifier(value: &str, field: &str) -> Result<()> {
+pub(crate) fn validate_pg_identifier(value: &str, field: &str) -> Result<()> {
     let valid = !value.is_empty()
         && value
             .chars()
@@ -1105,205 +1921,41 @@ fn validate_pg_identifier(value: &str, field: &str) -> Result<()> {
     Ok(())
 }
 
-pub fn migrate_connection(conn: &Connection, enable_fts: bool) -> Result<()> {
+/// Brings a SQLite database up to the latest schema; see [`crate::migrations`].
+pub fn migrate_connection(conn: &Connection) -> Result<()> {
     conn.execute_batch("PRAGMA foreign_keys = ON;")?;
-
-    let mut version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
-
-    if version < 1 {
-        conn.execute_batch(
-            r#"
-            CREATE TABLE IF NOT EXISTS pages (
-                id INTEGER PRIMARY KEY,
-                url TEXT NOT NULL UNIQUE,
-                title TEXT NOT NULL,
-                namespace TEXT NOT NULL,
-                mime_type TEXT NOT NULL,
-                cluster_idx INTEGER,
-                blob_idx INTEGER,
-                redirect_url TEXT,
-                content_sha256 TEXT,
-                raw_html TEXT,
-                plain_text TEXT,
-                extraction_confidence REAL NOT NULL DEFAULT 0.0,
-                updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ','now'))
-            );
-
-            CREATE TABLE IF NOT EXISTS definitions (
-                id INTEGER PRIMARY KEY,
-                page_id INTEGER NOT NULL REFERENCES pages(id) ON DELETE CASCADE,
-                language TEXT NOT NULL,
-                def_order INTEGER NOT NULL,
-                definition_text TEXT NOT NULL,
-                normalized_text TEXT NOT NULL DEFAULT '',
-                confidence REAL NOT NULL DEFAULT 0.0,
-                UNIQUE(page_id, language, def_order)
-            );
-
-            CREATE TABLE IF NOT EXISTS ingestion_runs (
-                id INTEGER PRIMARY KEY,
-                started_unix_ms INTEGER NOT NULL,
-                finished_unix_ms INTEGER NOT NULL,
-                scanned_entries INTEGER NOT NULL,
-                filtered_entries INTEGER NOT NULL,
-                ingested_pages INTEGER NOT NULL,
-                extracted_definitions INTEGER NOT NULL,
-                extracted_relations INTEGER NOT NULL DEFAULT 0,
-                extraction_errors INTEGER NOT NULL
-            );
-            "#,
-        )?;
-
-        version = 1;
-        conn.pragma_update(None, "user_version", version)?;
-    }
-
-    if version < 2 {
-        conn.execute_batch(
-            r#"
-            CREATE TABLE IF NOT EXISTS ingestion_runs (
-                id INTEGER PRIMARY KEY,
-                started_unix_ms INTEGER NOT NULL,
-                finished_unix_ms INTEGER NOT NULL,
-                scanned_entries INTEGER NOT NULL,
-                filtered_entries INTEGER NOT NULL,
-                ingested_pages INTEGER NOT NULL,
-                extracted_definitions INTEGER NOT NULL,
-                extracted_relations INTEGER NOT NULL DEFAULT 0,
-                extraction_errors INTEGER NOT NULL
-            );
-            "#,
-        )?;
-
-        ensure_column(
-            conn,
-            "pages",
-            "extraction_confidence",
-            "REAL NOT NULL DEFAULT 0.0",
-        )?;
-        ensure_column(
-            conn,
-            "definitions",
-            "normalized_text",
-            "TEXT NOT NULL DEFAULT ''",
-        )?;
-        ensure_column(
-            conn,
-            "definitions",
-            "confidence",
-            "REAL NOT NULL DEFAULT 0.0",
-        )?;
-        ensure_column(
-            conn,
-            "ingestion_runs",
-            "extracted_relations",
-            "INTEGER NOT NULL DEFAULT 0",
-        )?;
-
-        conn.execute_batch(
-            r#"
-            CREATE TABLE IF NOT EXISTS relations (
-                id INTEGER PRIMARY KEY,
-                page_id INTEGER NOT NULL REFERENCES pages(id) ON DELETE CASCADE,
-                language TEXT NOT NULL,
-                relation_type TEXT NOT NULL,
-                rel_order INTEGER NOT NULL,
-                source_text TEXT NOT NULL,
-                target_term TEXT NOT NULL,
-                normalized_target TEXT NOT NULL,
-                confidence REAL NOT NULL DEFAULT 0.0,
-                UNIQUE(page_id, language, relation_type, rel_order, target_term)
-            );
-
-            CREATE TABLE IF NOT EXISTS lemma_aliases (
-                id INTEGER PRIMARY KEY,
-                page_id INTEGER NOT NULL REFERENCES pages(id) ON DELETE CASCADE,
-                language TEXT,
-                alias TEXT NOT NULL,
-                normalized_alias TEXT NOT NULL,
-                source TEXT NOT NULL,
-                UNIQUE(page_id, language, alias, source)
-            );
-
-            CREATE TABLE IF NOT EXISTS ingestion_checkpoints (
-                name TEXT PRIMARY KEY,
-                last_processed_index INTEGER NOT NULL,
-                updated_unix_ms INTEGER NOT NULL,
-                ingested_pages INTEGER NOT NULL,
-                extracted_definitions INTEGER NOT NULL,
-                extracted_relations INTEGER NOT NULL,
-                metadata_json TEXT NOT NULL DEFAULT '{}'
-            );
-
-            CREATE TABLE IF NOT EXISTS reindex_state (
-                name TEXT PRIMARY KEY,
-                last_updated_at TEXT NOT NULL DEFAULT ''
-            );
-            "#,
-        )?;
-
-        version = 2;
-        conn.pragma_update(None, "user_version", version)?;
-    }
-
-    if version < 3 {
-        conn.execute_batch(
-            r#"
-            CREATE INDEX IF NOT EXISTS idx_pages_title ON pages(title);
-            CREATE INDEX IF NOT EXISTS idx_pages_updated_at ON pages(updated_at);
-            CREATE INDEX IF NOT EXISTS idx_definitions_page ON definitions(page_id);
-            CREATE INDEX IF NOT EXISTS idx_definitions_language ON definitions(language);
-            CREATE INDEX IF NOT EXISTS idx_definitions_norm ON definitions(normalized_text);
-            CREATE INDEX IF NOT EXISTS idx_relations_page ON relations(page_id);
-            CREATE INDEX IF NOT EXISTS idx_relations_type ON relations(relation_type);
-            CREATE INDEX IF NOT EXISTS idx_relations_target ON relations(normalized_target);
-            CREATE INDEX IF NOT EXISTS idx_aliases_page ON lemma_aliases(page_id);
-            CREATE INDEX IF NOT EXISTS idx_aliases_norm ON lemma_aliases(normalized_alias);
-            "#,
-        )?;
-
-        version = 3;
-        conn.pragma_update(None, "user_version", version)?;
-    }
-
-    if enable_fts {
-        conn.execute_batch(
-            r#"
-            CREATE VIRTUAL TABLE IF NOT EXISTS page_fts
-            USING fts5(page_id UNINDEXED, title, url, plain_text);
-            "#,
-        )?;
-    }
-
-    if version != TARGET_SCHEMA_VERSION {
-        conn.pragma_update(None, "user_version", TARGET_SCHEMA_VERSION)?;
-    }
-
+    migrations::sqlite_migrate(conn, false)?;
     Ok(())
 }
 
-fn ensure_column(
-    conn: &Connection,
-    table: &str,
-    column: &str,
-    column_type_sql: &str,
-) -> Result<()> {
-    let mut stmt = conn.prepare(&format!("PRAGMA table_info({table})"))?;
-    let mut rows = stmt.query([])?;
-
-    while let Some(row) = rows.next()? {
-        let existing_name: String = row.get(1)?;
-        if existing_name == column {
-            return Ok(());
-        }
-    }
-
+/// Rebuilds the `definition_fts` rows of one page, or of every live page for `None`.
+fn sqlite_refresh_definition_fts(conn: &Connection, page_id: Option<i64>) -> Result<u64> {
     conn.execute(
-        &format!("ALTER TABLE {table} ADD COLUMN {column} {column_type_sql}"),
-        [],
+        "DELETE FROM definition_fts WHERE ?1 IS NULL OR page_id = ?1",
+        params![page_id],
+    )?;
+    let rows = conn.execute(
+        r#"
+        INSERT INTO definition_fts(page_id, language, def_order, definition_text)
+        SELECT d.page_id, d.language, d.def_order, d.definition_text
+        FROM definitions d
+        JOIN pages p ON p.id = d.page_id AND p.deleted_at IS NULL
+        WHERE ?1 IS NULL OR d.page_id = ?1
+        "#,
+        params![page_id],
     )?;
+    Ok(rows as u64)
+}
 
-    Ok(())
+fn checkpoint_metadata_json(state: &CheckpointState) -> String {
+    serde_json::json!({ "run_id": state.run_id }).to_string()
+}
+
+fn checkpoint_run_id(metadata_json: &str) -> Option<i64> {
+    serde_json::from_str::<serde_json::Value>(metadata_json)
+        .ok()?
+        .get("run_id")?
+        .as_i64()
 }
 
 fn unix_now_ms() -> Result<u128> {
@@ -1365,12 +2017,12 @@ mod tests {
         )
         .expect("seed legacy schema");
 
-        migrate_connection(&conn, true).expect("migrate");
+        migrate_connection(&conn).expect("migrate");
 
         let version: i64 = conn
             .pragma_query_value(None, "user_version", |row| row.get(0))
             .expect("query version");
-        assert_eq!(version, TARGET_SCHEMA_VERSION);
+        assert_eq!(version, migrations::SQLITE_SCHEMA_VERSION);
 
         let mut stmt = conn
             .prepare("SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'relations'")
@@ -1382,4 +2034,336 @@ mod tests {
             .is_some();
         assert!(has_relations);
     }
+
+    #[test]
+    fn edition_rebuild_keeps_page_ids_and_children() {
+        let workdir = tempdir().expect("tempdir");
+        let conn = Connection::open(workdir.path().join("rebuild.sqlite")).expect("open db");
+        migrate_connection(&conn).expect("initial migrate");
+
+        conn.execute_batch(
+            r#"
+            INSERT INTO pages(id, url, title, namespace, mime_type) VALUES (7, 'chat', 'chat', 'A', 'text/html');
+            INSERT INTO definitions(page_id, language, def_order, definition_text) VALUES (7, 'English', 0, 'informal conversation');
+            DELETE FROM schema_migrations WHERE version > 5;
+            PRAGMA user_version = 5;
+            "#,
+        )
+        .expect("seed v5 rows");
+
+        migrate_connection(&conn).expect("rebuild migrate");
+
+        let (edition, definitions): (String, i64) = conn
+            .query_row(
+                "SELECT p.edition, (SELECT COUNT(*) FROM definitions d WHERE d.page_id = p.id) FROM pages p WHERE p.id = 7",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .expect("query rebuilt page");
+        assert_eq!(edition, "en");
+        assert_eq!(definitions, 1);
+
+        conn.execute(
+            "INSERT INTO pages(edition, url, title, namespace, mime_type) VALUES ('fr', 'chat', 'chat', 'A', 'text/html')",
+            [],
+        )
+        .expect("same url in another edition");
+    }
+
+    fn sqlite_test_config(path: &Path) -> Config {
+        let mut config = Config {
+            backend: StorageBackend::Sqlite,
+            ..Config::default()
+        };
+        config.input.sqlite_path = path.to_path_buf();
+        config
+    }
+
+    fn sample_page(url: &str, content_sha256: Option<&str>) -> ExtractedPage {
+        ExtractedPage {
+            edition: "en".to_owned(),
+            url: url.to_owned(),
+            title: url.to_owned(),
+            namespace: "A".to_owned(),
+            mime_type: "text/html".to_owned(),
+            cluster_idx: Some(0),
+            blob_idx: Some(0),
+            redirect_url: None,
+            content_sha256: content_sha256.map(ToOwned::to_owned),
+            raw_html: None,
+            plain_text: Some(String::new()),
+            extraction_confidence: 0.0,
+            definitions: Vec::new(),
+            relations: Vec::new(),
+            aliases: Vec::new(),
+        }
+    }
+
+    fn run_lineage(run_id: i64) -> PageLineage {
+        PageLineage {
+            run_id: Some(run_id),
+            ..PageLineage::default()
+        }
+    }
+
+    #[test]
+    fn loads_content_hashes_across_batches() {
+        let workdir = tempdir().expect("tempdir");
+        let db = Database::open(&sqlite_test_config(&workdir.path().join("hashes.sqlite")))
+            .expect("open db");
+        db.init_schema().expect("init schema");
+
+        db.upsert_page(&sample_page("alpha", Some("aaa")), &PageLineage::default())
+            .expect("upsert alpha");
+        db.upsert_page(&sample_page("beta", Some("bbb")), &PageLineage::default())
+            .expect("upsert beta");
+        db.upsert_page(&sample_page("gamma", Some("ccc")), &PageLineage::default())
+            .expect("upsert gamma");
+        db.upsert_page(&sample_page("redirect", None), &PageLineage::default())
+            .expect("upsert redirect");
+
+        let hashes = db.load_content_hashes("en", 2).expect("load hashes");
+        assert_eq!(hashes.len(), 3);
+        assert_eq!(hashes.get("beta").map(String::as_str), Some("bbb"));
+        assert!(!hashes.contains_key("redirect"));
+    }
+
+    #[test]
+    fn sweep_marks_then_deletes_pages_missing_from_latest_run() {
+        let workdir = tempdir().expect("tempdir");
+        let db = Database::open(&sqlite_test_config(&workdir.path().join("sweep.sqlite")))
+            .expect("open db");
+        db.init_schema().expect("init schema");
+
+        let first_run = db.begin_run(1, None).expect("begin first run");
+        for url in ["alpha", "beta", "gamma"] {
+            db.upsert_page(&sample_page(url, Some(url)), &run_lineage(first_run))
+                .expect("upsert");
+        }
+
+        let second_run = db.begin_run(2, None).expect("begin second run");
+        db.upsert_page(
+            &sample_page("alpha", Some("alpha")),
+            &run_lineage(second_run),
+        )
+        .expect("upsert alpha");
+        db.mark_pages_seen(second_run, "en", &["gamma".to_owned()])
+            .expect("mark gamma seen");
+
+        let marked = db
+            .sweep_unseen_pages(second_run, "en", SweepPolicy::Mark)
+            .expect("mark sweep");
+        assert_eq!(marked.swept_pages, 1);
+        assert_eq!(marked.removed_fts_rows, 1);
+
+        let DatabaseInner::Sqlite(sqlite) = &db.inner else {
+            unreachable!("test database is sqlite");
+        };
+        let deleted: Vec<String> = sqlite
+            .conn
+            .prepare("SELECT url FROM pages WHERE deleted_at IS NOT NULL")
+            .expect("prepare")
+            .query_map([], |row| row.get(0))
+            .expect("query")
+            .collect::<rusqlite::Result<_>>()
+            .expect("collect");
+        assert_eq!(deleted, vec!["beta".to_owned()]);
+
+        let repeat = db
+            .sweep_unseen_pages(second_run, "en", SweepPolicy::Mark)
+            .expect("repeat sweep");
+        assert_eq!(repeat.swept_pages, 0);
+
+        let removed = db
+            .sweep_unseen_pages(second_run, "en", SweepPolicy::Delete)
+            .expect("delete sweep");
+        assert_eq!(removed.swept_pages, 1);
+        let remaining: i64 = sqlite
+            .conn
+            .query_row("SELECT COUNT(*) FROM pages", [], |row| row.get(0))
+            .expect("count pages");
+        assert_eq!(remaining, 2);
+    }
+
+    #[test]
+    fn records_source_archive_and_page_lineage() {
+        let workdir = tempdir().expect("tempdir");
+        let db = Database::open(&sqlite_test_config(&workdir.path().join("lineage.sqlite")))
+            .expect("open db");
+        db.init_schema().expect("init schema");
+
+        let mut archive = SourceArchive {
+            zim_uuid: "0123456789abcdef0123456789abcdef".to_owned(),
+            file_name: "wiktionary_en_all.zim".to_owned(),
+            size_bytes: 1024,
+            zim_date: Some("2025-01-01".to_owned()),
+            zim_language: Some("eng".to_owned()),
+            checksum_md5: "d41d8cd98f00b204e9800998ecf8427e".to_owned(),
+        };
+        let archive_id = db.upsert_source_archive(&archive).expect("upsert archive");
+        archive.file_name = "renamed.zim".to_owned();
+        assert_eq!(
+            db.upsert_source_archive(&archive)
+                .expect("re-upsert archive"),
+            archive_id
+        );
+
+        let run_id = db.begin_run(1, Some(archive_id)).expect("begin run");
+        let lineage = PageLineage {
+            run_id: Some(run_id),
+            source_archive_id: Some(archive_id),
+            entry_index: None,
+        };
+        db.upsert_page(&sample_page("alpha", Some("aaa")), &lineage.at_entry(42))
+            .expect("upsert alpha");
+
+        let DatabaseInner::Sqlite(sqlite) = &db.inner else {
+            unreachable!("test database is sqlite");
+        };
+        let (written_run_id, page_archive_id, entry_index, file_name): (i64, i64, i64, String) =
+            sqlite
+                .conn
+                .query_row(
+                    r#"
+                    SELECT p.written_run_id, p.source_archive_id, p.zim_entry_index, a.file_name
+                    FROM pages p
+                    JOIN ingestion_runs r ON r.id = p.written_run_id
+                    JOIN source_archives a ON a.id = r.source_archive_id
+                    WHERE p.url = 'alpha'
+                    "#,
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+                )
+                .expect("query lineage");
+        assert_eq!(written_run_id, run_id);
+        assert_eq!(page_archive_id, archive_id);
+        assert_eq!(entry_index, 42);
+        assert_eq!(file_name, "renamed.zim");
+    }
+
+    #[test]
+    fn editions_keep_separate_pages_and_sweeps() {
+        let workdir = tempdir().expect("tempdir");
+        let db = Database::open(&sqlite_test_config(&workdir.path().join("editions.sqlite")))
+            .expect("open db");
+        db.init_schema().expect("init schema");
+
+        let run_id = db.begin_run(1, None).expect("begin run");
+        let mut french = sample_page("chat", Some("fr-hash"));
+        french.edition = "fr".to_owned();
+        db.upsert_page(&french, &run_lineage(run_id))
+            .expect("upsert fr");
+        db.upsert_page(&sample_page("chat", Some("en-hash")), &run_lineage(run_id))
+            .expect("upsert en");
+
+        let hashes = db.load_content_hashes("fr", 10).expect("load fr hashes");
+        assert_eq!(hashes.get("chat").map(String::as_str), Some("fr-hash"));
+
+        let english_run = db.begin_run(2, None).expect("begin en run");
+        db.mark_pages_seen(english_run, "en", &["chat".to_owned()])
+            .expect("mark en seen");
+        let sweep = db
+            .sweep_unseen_pages(english_run, "en", SweepPolicy::Delete)
+            .expect("sweep en");
+        assert_eq!(sweep.swept_pages, 0);
+
+        let DatabaseInner::Sqlite(sqlite) = &db.inner else {
+            unreachable!("test database is sqlite");
+        };
+        let pages: i64 = sqlite
+            .conn
+            .query_row("SELECT COUNT(*) FROM pages WHERE url = 'chat'", [], |row| {
+                row.get(0)
+            })
+            .expect("count pages");
+        assert_eq!(pages, 2);
+    }
+
+    #[test]
+    fn redirect_aliases_attach_to_targets_and_survive_rewrites() {
+        let workdir = tempdir().expect("tempdir");
+        let db = Database::open(&sqlite_test_config(
+            &workdir.path().join("redirects.sqlite"),
+        ))
+        .expect("open db");
+        db.init_schema().expect("init schema");
+
+        db.upsert_page(&sample_page("colour", Some("aaa")), &PageLineage::default())
+            .expect("upsert target");
+
+        db.queue_redirects("en", &[3, 1, 3])
+            .expect("queue redirects");
+        assert_eq!(db.load_pending_redirects("en").expect("load"), vec![1, 3]);
+        assert!(db.load_pending_redirects("fr").expect("load fr").is_empty());
+        db.clear_pending_redirects("en").expect("clear");
+        assert!(db.load_pending_redirects("en").expect("reload").is_empty());
+
+        let aliases = vec![
+            RedirectAlias {
+                target_url: "colour".to_owned(),
+                alias: "color".to_owned(),
+                normalized_alias: "color".to_owned(),
+            },
+            RedirectAlias {
+                target_url: "missing".to_owned(),
+                alias: "nothing".to_owned(),
+                normalized_alias: "nothing".to_owned(),
+            },
+        ];
+        let first = db.insert_redirect_aliases("en", &aliases).expect("insert");
+        assert_eq!(first.inserted_aliases, 1);
+        assert_eq!(first.missing_targets, 1);
+        let repeat = db.insert_redirect_aliases("en", &aliases).expect("repeat");
+        assert_eq!(repeat.inserted_aliases, 0);
+
+        db.upsert_page(&sample_page("colour", Some("bbb")), &PageLineage::default())
+            .expect("rewrite target");
+
+        let DatabaseInner::Sqlite(sqlite) = &db.inner else {
+            unreachable!("test database is sqlite");
+        };
+        let redirect_aliases: i64 = sqlite
+            .conn
+            .query_row(
+                "SELECT COUNT(*) FROM lemma_aliases WHERE source = 'redirect' AND alias = 'color'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("count aliases");
+        assert_eq!(redirect_aliases, 1);
+    }
+
+    #[test]
+    fn failures_upsert_per_entry_and_clear() {
+        let workdir = tempdir().expect("tempdir");
+        let db = Database::open(&sqlite_test_config(&workdir.path().join("failures.sqlite")))
+            .expect("open db");
+        db.init_schema().expect("init schema");
+
+        let mut failure = IngestionFailure {
+            edition: "en".to_owned(),
+            entry_index: 42,
+            url: Some("chat".to_owned()),
+            stage: "blob".to_owned(),
+            error_chain: "out of bounds access".to_owned(),
+            run_id: Some(1),
+            attempts: 1,
+        };
+        db.record_failure(&failure).expect("record first");
+        failure.url = None;
+        failure.stage = "upsert".to_owned();
+        failure.run_id = Some(2);
+        db.record_failure(&failure).expect("record second");
+
+        let failures = db.load_failures("en").expect("load failures");
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].stage, "upsert");
+        assert_eq!(failures[0].url.as_deref(), Some("chat"));
+        assert_eq!(failures[0].run_id, Some(2));
+        assert_eq!(failures[0].attempts, 2);
+        assert!(db.load_failures("fr").expect("load fr").is_empty());
+
+        db.clear_failure("en", 42).expect("clear");
+        assert!(db.load_failures("en").expect("reload").is_empty());
+    }
 }
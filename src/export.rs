@@ -5,7 +5,7 @@ use std::thread;
 use std::time::Duration;
 
 use anyhow::{Context, Result};
-use postgres::NoTls;
+use postgres::{GenericClient, NoTls};
 use rusqlite::{Connection, params};
 use serde::Serialize;
 
@@ -30,49 +30,52 @@ pub struct ExportMetrics {
     pub exported_aliases: u64,
 }
 
-#[derive(Debug, Serialize)]
-struct ExportDefinition {
-    language: String,
-    order: i64,
-    text: String,
-    normalized_text: String,
-    confidence: f64,
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExportDefinition {
+    pub language: String,
+    pub order: i64,
+    pub text: String,
+    pub normalized_text: String,
+    pub confidence: f64,
 }
 
-#[derive(Debug, Serialize)]
-struct ExportRelation {
-    language: String,
-    relation_type: String,
-    order: i64,
-    source_text: String,
-    target_term: String,
-    normalized_target: String,
-    confidence: f64,
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExportRelation {
+    pub language: String,
+    pub relation_type: String,
+    pub order: i64,
+    pub source_text: String,
+    pub target_term: String,
+    pub normalized_target: String,
+    pub confidence: f64,
 }
 
-#[derive(Debug, Serialize)]
-struct ExportAlias {
-    language: Option<String>,
-    alias: String,
-    normalized_alias: String,
-    source: String,
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExportAlias {
+    pub language: Option<String>,
+    pub alias: String,
+    pub normalized_alias: String,
+    pub source: String,
 }
 
-#[derive(Debug, Serialize)]
-struct ExportPage {
-    id: i64,
-    url: String,
-    title: String,
-    namespace: String,
-    mime_type: String,
-    redirect_url: Option<String>,
-    content_sha256: Option<String>,
-    extraction_confidence: f64,
-    plain_text: Option<String>,
-    raw_html: Option<String>,
-    definitions: Vec<ExportDefinition>,
-    relations: Vec<ExportRelation>,
-    aliases: Vec<ExportAlias>,
+/// One page with its definitions, relations and aliases, as written by `export-json` and
+/// returned by [`crate::query::Dictionary`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExportPage {
+    pub id: i64,
+    pub edition: String,
+    pub url: String,
+    pub title: String,
+    pub namespace: String,
+    pub mime_type: String,
+    pub redirect_url: Option<String>,
+    pub content_sha256: Option<String>,
+    pub extraction_confidence: f64,
+    pub plain_text: Option<String>,
+    pub raw_html: Option<String>,
+    pub definitions: Vec<ExportDefinition>,
+    pub relations: Vec<ExportRelation>,
+    pub aliases: Vec<ExportAlias>,
 }
 
 pub fn export_json(options: &ExportOptions) -> Result<ExportMetrics> {
@@ -152,8 +155,10 @@ fn export_from_sqlite(
                 content_sha256,
                 extraction_confidence,
                 plain_text,
-                raw_html
+                raw_html,
+                edition
             FROM pages
+            WHERE deleted_at IS NULL
             ORDER BY id ASC
             LIMIT ?1 OFFSET ?2
             "#,
@@ -174,6 +179,7 @@ fn export_from_sqlite(
 
             let page = ExportPage {
                 id: page_id,
+                edition: row.get(10)?,
                 url: row.get(1)?,
                 title: row.get(2)?,
                 namespace: row.get(3)?,
@@ -207,7 +213,10 @@ fn export_from_sqlite(
     Ok(metrics)
 }
 
-fn fetch_definitions_sqlite(conn: &Connection, page_id: i64) -> Result<Vec<ExportDefinition>> {
+pub(crate) fn fetch_definitions_sqlite(
+    conn: &Connection,
+    page_id: i64,
+) -> Result<Vec<ExportDefinition>> {
     let mut stmt = conn.prepare(
         r#"
         SELECT language, def_order, definition_text, normalized_text, confidence
@@ -233,7 +242,10 @@ fn fetch_definitions_sqlite(conn: &Connection, page_id: i64) -> Result<Vec<Expor
     Ok(out)
 }
 
-fn fetch_relations_sqlite(conn: &Connection, page_id: i64) -> Result<Vec<ExportRelation>> {
+pub(crate) fn fetch_relations_sqlite(
+    conn: &Connection,
+    page_id: i64,
+) -> Result<Vec<ExportRelation>> {
     let mut stmt = conn.prepare(
         r#"
         SELECT
@@ -268,7 +280,7 @@ fn fetch_relations_sqlite(conn: &Connection, page_id: i64) -> Result<Vec<ExportR
     Ok(out)
 }
 
-fn fetch_aliases_sqlite(conn: &Connection, page_id: i64) -> Result<Vec<ExportAlias>> {
+pub(crate) fn fetch_aliases_sqlite(conn: &Connection, page_id: i64) -> Result<Vec<ExportAlias>> {
     let mut stmt = conn.prepare(
         r#"
         SELECT language, alias, normalized_alias, source
@@ -301,9 +313,6 @@ fn export_from_postgres(
     wrote_any_array_item: &mut bool,
 ) -> Result<ExportMetrics> {
     let pages = pg_table(schema, "pages");
-    let definitions = pg_table(schema, "definitions");
-    let relations = pg_table(schema, "relations");
-    let aliases = pg_table(schema, "lemma_aliases");
 
     let mut metrics = ExportMetrics::default();
     let mut offset = 0_u64;
@@ -320,7 +329,7 @@ fn export_from_postgres(
 
         let page_rows = client.query(
             &format!(
-                "SELECT id, url, title, namespace, mime_type, redirect_url, content_sha256, extraction_confidence, plain_text, raw_html FROM {pages} ORDER BY id ASC LIMIT $1 OFFSET $2"
+                "SELECT id, url, title, namespace, mime_type, redirect_url, content_sha256, extraction_confidence, plain_text, raw_html, edition FROM {pages} WHERE deleted_at IS NULL ORDER BY id ASC LIMIT $1 OFFSET $2"
             ),
             &[&(limit as i64), &(offset as i64)],
         )?;
@@ -331,58 +340,9 @@ fn export_from_postgres(
 
         for row in &page_rows {
             let page_id: i64 = row.get(0);
-            let definitions_rows = client.query(
-                &format!(
-                    "SELECT language, def_order, definition_text, normalized_text, confidence FROM {definitions} WHERE page_id = $1 ORDER BY language ASC, def_order ASC"
-                ),
-                &[&page_id],
-            )?;
-            let relations_rows = client.query(
-                &format!(
-                    "SELECT language, relation_type, rel_order, source_text, target_term, normalized_target, confidence FROM {relations} WHERE page_id = $1 ORDER BY relation_type ASC, rel_order ASC"
-                ),
-                &[&page_id],
-            )?;
-            let aliases_rows = client.query(
-                &format!(
-                    "SELECT language, alias, normalized_alias, source FROM {aliases} WHERE page_id = $1 ORDER BY normalized_alias ASC"
-                ),
-                &[&page_id],
-            )?;
-
-            let definitions: Vec<ExportDefinition> = definitions_rows
-                .into_iter()
-                .map(|definition_row| ExportDefinition {
-                    language: definition_row.get(0),
-                    order: definition_row.get(1),
-                    text: definition_row.get(2),
-                    normalized_text: definition_row.get(3),
-                    confidence: definition_row.get(4),
-                })
-                .collect();
-
-            let relations: Vec<ExportRelation> = relations_rows
-                .into_iter()
-                .map(|relation_row| ExportRelation {
-                    language: relation_row.get(0),
-                    relation_type: relation_row.get(1),
-                    order: relation_row.get(2),
-                    source_text: relation_row.get(3),
-                    target_term: relation_row.get(4),
-                    normalized_target: relation_row.get(5),
-                    confidence: relation_row.get(6),
-                })
-                .collect();
-
-            let aliases: Vec<ExportAlias> = aliases_rows
-                .into_iter()
-                .map(|alias_row| ExportAlias {
-                    language: alias_row.get(0),
-                    alias: alias_row.get(1),
-                    normalized_alias: alias_row.get(2),
-                    source: alias_row.get(3),
-                })
-                .collect();
+            let definitions = fetch_definitions_postgres(client, schema, page_id)?;
+            let relations = fetch_relations_postgres(client, schema, page_id)?;
+            let aliases = fetch_aliases_postgres(client, schema, page_id)?;
 
             metrics.exported_definitions += definitions.len() as u64;
             metrics.exported_relations += relations.len() as u64;
@@ -390,6 +350,7 @@ fn export_from_postgres(
 
             let page = ExportPage {
                 id: page_id,
+                edition: row.get(10),
                 url: row.get(1),
                 title: row.get(2),
                 namespace: row.get(3),
@@ -418,6 +379,82 @@ fn export_from_postgres(
     Ok(metrics)
 }
 
+pub(crate) fn fetch_definitions_postgres(
+    client: &mut impl GenericClient,
+    schema: &str,
+    page_id: i64,
+) -> Result<Vec<ExportDefinition>> {
+    let rows = client.query(
+        &format!(
+            "SELECT language, def_order, definition_text, normalized_text, confidence FROM {} WHERE page_id = $1 ORDER BY language ASC, def_order ASC",
+            pg_table(schema, "definitions")
+        ),
+        &[&page_id],
+    )?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| ExportDefinition {
+            language: row.get(0),
+            order: row.get(1),
+            text: row.get(2),
+            normalized_text: row.get(3),
+            confidence: row.get(4),
+        })
+        .collect())
+}
+
+pub(crate) fn fetch_relations_postgres(
+    client: &mut impl GenericClient,
+    schema: &str,
+    page_id: i64,
+) -> Result<Vec<ExportRelation>> {
+    let rows = client.query(
+        &format!(
+            "SELECT language, relation_type, rel_order, source_text, target_term, normalized_target, confidence FROM {} WHERE page_id = $1 ORDER BY relation_type ASC, rel_order ASC",
+            pg_table(schema, "relations")
+        ),
+        &[&page_id],
+    )?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| ExportRelation {
+            language: row.get(0),
+            relation_type: row.get(1),
+            order: row.get(2),
+            source_text: row.get(3),
+            target_term: row.get(4),
+            normalized_target: row.get(5),
+            confidence: row.get(6),
+        })
+        .collect())
+}
+
+pub(crate) fn fetch_aliases_postgres(
+    client: &mut impl GenericClient,
+    schema: &str,
+    page_id: i64,
+) -> Result<Vec<ExportAlias>> {
+    let rows = client.query(
+        &format!(
+            "SELECT language, alias, normalized_alias, source FROM {} WHERE page_id = $1 ORDER BY normalized_alias ASC",
+            pg_table(schema, "lemma_aliases")
+        ),
+        &[&page_id],
+    )?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| ExportAlias {
+            language: row.get(0),
+            alias: row.get(1),
+            normalized_alias: row.get(2),
+            source: row.get(3),
+        })
+        .collect())
+}
+
 fn write_export_page(
     options: &ExportOptions,
     writer: &mut BufWriter<File>,
@@ -1,4 +1,6 @@
 use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::time::{Duration, Instant};
 
 use html_escape::decode_html_entities;
 use once_cell::sync::Lazy;
@@ -7,7 +9,7 @@ use sha2::{Digest, Sha256};
 use tracing::{debug, trace};
 use zim::{MimeType, Namespace};
 
-use crate::config::ExtractionConfig;
+use crate::config::{ExtractionConfig, PageGuardConfig};
 use crate::normalization::{canonicalize_lemma, generate_aliases, normalize_for_language};
 
 static HEADING_RE: Lazy<Regex> = Lazy::new(|| {
@@ -78,6 +80,7 @@ pub struct ExtractedAlias {
 
 #[derive(Debug, Clone)]
 pub struct ExtractedPage {
+    pub edition: String,
     pub url: String,
     pub title: String,
     pub namespace: String,
@@ -101,6 +104,120 @@ pub struct HtmlExtraction {
     pub definitions: Vec<ExtractedDefinition>,
     pub relations: Vec<ExtractedRelation>,
     pub aliases: Vec<ExtractedAlias>,
+    /// Guards from `extraction.guards` that cut this extraction short.
+    pub guard_trips: Vec<GuardTrip>,
+}
+
+/// A per-page limit that was reached during extraction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuardTrip {
+    HtmlBytes { limit: usize, actual: usize },
+    Headings { limit: usize },
+    ListItems { limit: usize },
+    TimeBudget { budget_ms: u64 },
+}
+
+impl fmt::Display for GuardTrip {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::HtmlBytes { limit, actual } => {
+                write!(f, "html is {actual} bytes, over the {limit} byte limit")
+            }
+            Self::Headings { limit } => write!(f, "more than {limit} headings"),
+            Self::ListItems { limit } => write!(f, "more than {limit} list items"),
+            Self::TimeBudget { budget_ms } => {
+                write!(f, "extraction ran past its {budget_ms} ms budget")
+            }
+        }
+    }
+}
+
+/// Tracks one page's budgets while it is extracted.
+struct PageGuard<'a> {
+    config: &'a PageGuardConfig,
+    started: Instant,
+    headings_left: usize,
+    list_items_left: usize,
+    trips: Vec<GuardTrip>,
+}
+
+impl<'a> PageGuard<'a> {
+    fn new(config: &'a PageGuardConfig) -> Self {
+        Self {
+            config,
+            started: Instant::now(),
+            headings_left: limit_or_unbounded(config.max_headings),
+            list_items_left: limit_or_unbounded(config.max_list_items),
+            trips: Vec::new(),
+        }
+    }
+
+    fn trip(&mut self, trip: GuardTrip) {
+        if !self.trips.contains(&trip) {
+            debug!(%trip, "page guard tripped");
+            self.trips.push(trip);
+        }
+    }
+
+    /// Cuts `html` to `max_html_bytes`, backing off to a UTF-8 boundary.
+    fn clamp_html<'h>(&mut self, html: &'h str) -> &'h str {
+        let limit = self.config.max_html_bytes;
+        if limit == 0 || html.len() <= limit {
+            return html;
+        }
+
+        self.trip(GuardTrip::HtmlBytes {
+            limit,
+            actual: html.len(),
+        });
+        let mut end = limit;
+        while !html.is_char_boundary(end) {
+            end -= 1;
+        }
+        &html[..end]
+    }
+
+    /// Headings of `html`, drawn from the page-wide `max_headings` budget.
+    fn headings(&mut self, html: &str, min_level: u8, max_level: u8) -> Vec<Heading> {
+        let limit = self.headings_left;
+        let mut headings = extract_headings(html, min_level, max_level, limit.saturating_add(1));
+        if headings.len() > limit {
+            headings.truncate(limit);
+            self.trip(GuardTrip::Headings {
+                limit: self.config.max_headings,
+            });
+        }
+        self.headings_left -= headings.len();
+        headings
+    }
+
+    /// List items of `html`, drawn from the page-wide `max_list_items` budget.
+    fn list_items(&mut self, html: &str, depth_limit: usize) -> Vec<ListItemFragment> {
+        let limit = self.list_items_left;
+        let mut items = extract_list_items(html, depth_limit, limit.saturating_add(1));
+        if items.len() > limit {
+            items.truncate(limit);
+            self.trip(GuardTrip::ListItems {
+                limit: self.config.max_list_items,
+            });
+        }
+        self.list_items_left -= items.len();
+        items
+    }
+
+    fn out_of_time(&mut self) -> bool {
+        let budget_ms = self.config.time_budget_ms;
+        if budget_ms == 0 || self.started.elapsed() < Duration::from_millis(budget_ms) {
+            return false;
+        }
+
+        self.trip(GuardTrip::TimeBudget { budget_ms });
+        true
+    }
+}
+
+fn limit_or_unbounded(limit: usize) -> usize {
+    if limit == 0 { usize::MAX } else { limit }
 }
 
 #[derive(Debug, Clone)]
@@ -165,7 +282,11 @@ pub fn sha256_hex(value: &str) -> String {
     format!("{output:x}")
 }
 
+/// Extracts definitions, relations and aliases from one page, within the limits of
+/// `config.guards`. Tripped guards truncate the result and are listed in `guard_trips`.
 pub fn extract_from_html(title: &str, html: &str, config: &ExtractionConfig) -> HtmlExtraction {
+    let mut guard = PageGuard::new(&config.guards);
+    let html = guard.clamp_html(html);
     let plain_text = config.store_plain_text.then(|| html_to_plain_text(html));
 
     if !config.parse_language_sections {
@@ -181,10 +302,11 @@ pub fn extract_from_html(title: &str, html: &str, config: &ExtractionConfig) ->
             definitions: Vec::new(),
             relations: Vec::new(),
             aliases,
+            guard_trips: guard.trips,
         };
     }
 
-    let headings = extract_headings(html, 2, 5);
+    let headings = guard.headings(html, 2, 5);
     let language_headings: Vec<&Heading> = headings.iter().filter(|h| h.level == 2).collect();
 
     if language_headings.is_empty() {
@@ -200,6 +322,7 @@ pub fn extract_from_html(title: &str, html: &str, config: &ExtractionConfig) ->
             definitions: Vec::new(),
             relations: Vec::new(),
             aliases,
+            guard_trips: guard.trips,
         };
     }
 
@@ -230,7 +353,11 @@ pub fn extract_from_html(title: &str, html: &str, config: &ExtractionConfig) ->
     let mut confidence_count = 0_u64;
     let mut language_set = BTreeSet::new();
 
-    for (idx, heading) in language_headings.iter().enumerate() {
+    'sections: for (idx, heading) in language_headings.iter().enumerate() {
+        if guard.out_of_time() {
+            break;
+        }
+
         let language = normalize_text(&heading.title);
         if language.is_empty() {
             continue;
@@ -255,8 +382,18 @@ pub fn extract_from_html(title: &str, html: &str, config: &ExtractionConfig) ->
             continue;
         }
 
+        // Section headings come from the page-level pass, which already paid for them.
         let section_html = &html[heading.end..section_end];
-        let section_headings = extract_headings(section_html, 3, 5);
+        let section_headings = headings
+            .iter()
+            .filter(|h| h.level >= 3 && h.start >= heading.end && h.end <= section_end)
+            .map(|h| Heading {
+                start: h.start - heading.end,
+                end: h.end - heading.end,
+                level: h.level,
+                title: h.title.clone(),
+            })
+            .collect::<Vec<_>>();
 
         let mut relation_ranges: Vec<(usize, usize, String)> = Vec::new();
         if config.parse_relations {
@@ -279,13 +416,16 @@ pub fn extract_from_html(title: &str, html: &str, config: &ExtractionConfig) ->
             }
         }
 
-        let list_items = extract_list_items(section_html, config.nested_list_depth_limit);
+        let list_items = guard.list_items(section_html, config.nested_list_depth_limit);
 
         let mut def_order = 0_i64;
         for fragment in &list_items {
             if def_order as usize >= config.max_definitions_per_language {
                 break;
             }
+            if guard.out_of_time() {
+                break 'sections;
+            }
 
             let inside_relation = relation_ranges
                 .iter()
@@ -325,7 +465,7 @@ pub fn extract_from_html(title: &str, html: &str, config: &ExtractionConfig) ->
         if config.parse_relations {
             for (range_start, range_end, relation_type) in relation_ranges {
                 let subsection = &section_html[range_start..range_end];
-                let relation_items = extract_list_items(subsection, config.nested_list_depth_limit);
+                let relation_items = guard.list_items(subsection, config.nested_list_depth_limit);
                 let state_key = (language.clone(), relation_type.clone());
                 let relation_order = relation_order_state.entry(state_key).or_insert(0_i64);
 
@@ -333,6 +473,9 @@ pub fn extract_from_html(title: &str, html: &str, config: &ExtractionConfig) ->
                     if *relation_order as usize >= config.max_relations_per_type {
                         break;
                     }
+                    if guard.out_of_time() {
+                        break 'sections;
+                    }
 
                     let source_text = normalize_text(&item.raw_html);
                     if source_text.len() < config.min_definition_chars / 2 {
@@ -386,6 +529,7 @@ pub fn extract_from_html(title: &str, html: &str, config: &ExtractionConfig) ->
         definitions,
         relations,
         aliases,
+        guard_trips: guard.trips,
     }
 }
 
@@ -418,6 +562,12 @@ fn expand_language_tokens(value: &str) -> BTreeSet<String> {
     out
 }
 
+/// Resolves an ISO-like code (`en`) to the language name definitions are stored under; other
+/// values are returned unchanged.
+pub fn canonical_language_name(value: &str) -> String {
+    language_code_to_name(&value.to_lowercase()).map_or_else(|| value.to_owned(), str::to_owned)
+}
+
 fn language_code_to_name(code: &str) -> Option<&'static str> {
     match code {
         "en" => Some("English"),
@@ -450,6 +600,33 @@ fn language_code_to_name(code: &str) -> Option<&'static str> {
     }
 }
 
+/// Postgres text search configurations by stored language name, limited to the stemmers
+/// every supported server ships; other languages are indexed with `simple`.
+pub const TEXT_SEARCH_CONFIGS: &[(&str, &str)] = &[
+    ("English", "english"),
+    ("French", "french"),
+    ("Spanish", "spanish"),
+    ("German", "german"),
+    ("Italian", "italian"),
+    ("Portuguese", "portuguese"),
+    ("Dutch", "dutch"),
+    ("Swedish", "swedish"),
+    ("Norwegian", "norwegian"),
+    ("Danish", "danish"),
+    ("Finnish", "finnish"),
+    ("Russian", "russian"),
+    ("Turkish", "turkish"),
+];
+
+/// Text search configuration for a language name or ISO code, `simple` when unmapped.
+pub fn text_search_config(language: &str) -> &'static str {
+    let name = canonical_language_name(language);
+    TEXT_SEARCH_CONFIGS
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(&name))
+        .map_or("simple", |(_, config)| config)
+}
+
 fn language_name_to_code(name: &str) -> Option<&'static str> {
     match name {
         "english" => Some("en"),
@@ -507,10 +684,14 @@ fn build_aliases(
     out
 }
 
-fn extract_headings(html: &str, min_level: u8, max_level: u8) -> Vec<Heading> {
+fn extract_headings(html: &str, min_level: u8, max_level: u8, limit: usize) -> Vec<Heading> {
     let mut out = Vec::new();
 
     for captures in HEADING_RE.captures_iter(html) {
+        if out.len() >= limit {
+            break;
+        }
+
         let Some(matched) = captures.get(0) else {
             continue;
         };
@@ -542,7 +723,7 @@ fn extract_headings(html: &str, min_level: u8, max_level: u8) -> Vec<Heading> {
     out
 }
 
-fn extract_list_items(html: &str, depth_limit: usize) -> Vec<ListItemFragment> {
+fn extract_list_items(html: &str, depth_limit: usize, limit: usize) -> Vec<ListItemFragment> {
     let mut out = Vec::new();
     let mut list_depth = 0_usize;
     let mut li_depth = 0_usize;
@@ -550,6 +731,10 @@ fn extract_list_items(html: &str, depth_limit: usize) -> Vec<ListItemFragment> {
     let mut current_list_depth = 0_usize;
 
     for captures in TAG_TOKEN_RE.captures_iter(html) {
+        if out.len() >= limit {
+            break;
+        }
+
         let Some(matched) = captures.get(0) else {
             continue;
         };
@@ -842,4 +1027,74 @@ mod tests {
         orders.sort_unstable();
         assert_eq!(orders, vec![0, 1]);
     }
+
+    #[test]
+    fn page_guards_truncate_oversized_pages() {
+        let items = (0..10)
+            .map(|i| format!("<li>Definition number {i} is long enough.</li>"))
+            .collect::<String>();
+        let html = format!(
+            "<h2>English</h2><ol>{items}</ol><h2>French</h2><ol><li>Définition française assez longue.</li></ol>"
+        );
+
+        let mut cfg = ExtractionConfig {
+            min_definition_chars: 5,
+            ..ExtractionConfig::default()
+        };
+        cfg.guards.max_list_items = 4;
+        let extracted = extract_from_html("test", &html, &cfg);
+        assert_eq!(extracted.definitions.len(), 4);
+        assert_eq!(
+            extracted.guard_trips,
+            vec![GuardTrip::ListItems { limit: 4 }]
+        );
+
+        cfg.guards.max_list_items = 0;
+        cfg.guards.max_headings = 1;
+        let extracted = extract_from_html("test", &html, &cfg);
+        assert!(
+            extracted
+                .definitions
+                .iter()
+                .all(|d| d.language == "English")
+        );
+        assert_eq!(
+            extracted.guard_trips,
+            vec![GuardTrip::Headings { limit: 1 }]
+        );
+
+        // One budget for the whole page: section headings are not counted again per section.
+        let sections = "<h2>English</h2><h3>Synonyms</h3><ul><li>moggy</li></ul>\
+                        <h2>French</h2><h3>Synonyms</h3><ul><li>minou</li></ul>";
+        cfg.guards.max_headings = 3;
+        let extracted = extract_from_html("test", sections, &cfg);
+        assert!(extracted.relations.iter().any(|r| r.target_term == "moggy"));
+        assert!(extracted.relations.iter().all(|r| r.language == "English"));
+        assert_eq!(
+            extracted.guard_trips,
+            vec![GuardTrip::Headings { limit: 3 }]
+        );
+
+        cfg.guards.max_headings = 0;
+        let cut = html.find("<h2>French").expect("french heading") + 3;
+        cfg.guards.max_html_bytes = cut;
+        let extracted = extract_from_html("test", &html, &cfg);
+        assert_eq!(extracted.definitions.len(), 10);
+        assert_eq!(
+            extracted.guard_trips,
+            vec![GuardTrip::HtmlBytes {
+                limit: cut,
+                actual: html.len(),
+            }]
+        );
+    }
+
+    #[test]
+    fn maps_languages_to_text_search_configs() {
+        assert_eq!(text_search_config("English"), "english");
+        assert_eq!(text_search_config("de"), "german");
+        assert_eq!(text_search_config("russian"), "russian");
+        assert_eq!(text_search_config("Japanese"), "simple");
+        assert_eq!(text_search_config("Klingon"), "simple");
+    }
 }
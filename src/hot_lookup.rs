@@ -0,0 +1,270 @@
+use anyhow::Result;
+use postgres::GenericClient;
+use tracing::info;
+
+use crate::db::{pg_ident, pg_table};
+
+/// Partial indexes on the canonical tables that cover the hot languages only.
+const HOT_INDEXES: &[(&str, &str, &str)] = &[
+    (
+        "idx_aliases_hot_norm_page",
+        "lemma_aliases",
+        "normalized_alias, page_id",
+    ),
+    (
+        "idx_definitions_hot_page_order",
+        "definitions",
+        "page_id, def_order",
+    ),
+];
+
+/// Languages the partial indexes were created for by the initial Postgres migration, which
+/// predates the index comments recording them.
+const MIGRATION_LANGUAGES: &[&str] = &["English", "French", "German", "Spanish"];
+
+/// Rebuilds the hot partial indexes when they were built for a different language set than
+/// `languages`. The set an index covers is kept in its comment.
+pub(crate) fn sync_partial_indexes(
+    client: &mut postgres::Client,
+    schema: &str,
+    languages: &[String],
+) -> Result<()> {
+    let marker = languages_marker(languages);
+    let in_list = languages
+        .iter()
+        .map(|language| sql_literal(language))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut tx = client.transaction()?;
+    tx.execute("SELECT pg_advisory_xact_lock(hashtext($1))", &[&schema])?;
+    for (index, table, columns) in HOT_INDEXES {
+        let qualified = format!("{}.{}", pg_ident(schema), pg_ident(index));
+        let row = tx.query_one(
+            "SELECT to_regclass($1) IS NOT NULL, obj_description(to_regclass($1), 'pg_class')",
+            &[&qualified],
+        )?;
+        let exists: bool = row.get(0);
+        let current = row.get::<_, Option<String>>(1).or_else(|| {
+            exists.then(|| {
+                languages_marker(
+                    &MIGRATION_LANGUAGES
+                        .iter()
+                        .map(|language| (*language).to_owned())
+                        .collect::<Vec<_>>(),
+                )
+            })
+        });
+        if current.as_deref() == Some(marker.as_str()) {
+            continue;
+        }
+
+        tx.batch_execute(&format!(
+            "DROP INDEX IF EXISTS {qualified}; CREATE INDEX {} ON {}({columns}) WHERE language IN ({in_list}); COMMENT ON INDEX {qualified} IS {};",
+            pg_ident(index),
+            pg_table(schema, table),
+            sql_literal(&marker)
+        ))?;
+        info!(
+            schema,
+            index = *index,
+            languages = %languages.join(","),
+            "rebuilt hot-language partial index"
+        );
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Replaces the whole projection with rows built from the canonical tables and returns the
+/// number of rows written.
+pub(crate) fn rebuild(
+    client: &mut postgres::Client,
+    schema: &str,
+    languages: &[String],
+) -> Result<u64> {
+    let hot_lookup = pg_table(schema, "hot_lookup");
+    let mut tx = client.transaction()?;
+    tx.execute("SELECT pg_advisory_xact_lock(hashtext($1))", &[&schema])?;
+    tx.execute(&format!("DELETE FROM {hot_lookup}"), &[])?;
+    let rows = tx.execute(&projection_sql(schema, false), &[&languages])?;
+    tx.commit()?;
+
+    client.batch_execute(&format!("ANALYZE {hot_lookup};"))?;
+    Ok(rows)
+}
+
+/// Replaces the projection rows of one page, e.g. inside the transaction that rewrote it.
+pub(crate) fn refresh_page(
+    client: &mut impl GenericClient,
+    schema: &str,
+    languages: &[String],
+    page_id: i64,
+) -> Result<u64> {
+    client.execute(
+        &format!(
+            "DELETE FROM {} WHERE page_id = $1",
+            pg_table(schema, "hot_lookup")
+        ),
+        &[&page_id],
+    )?;
+    Ok(client.execute(&projection_sql(schema, true), &[&languages, &page_id])?)
+}
+
+/// One row per `(language, normalized_alias, page_id)` of live pages, keeping the smallest
+/// alias spelling and the first definition in that language. `$1` is the language list and,
+/// with `single_page`, `$2` the page id.
+fn projection_sql(schema: &str, single_page: bool) -> String {
+    let pages = pg_table(schema, "pages");
+    let definitions = pg_table(schema, "definitions");
+    let aliases = pg_table(schema, "lemma_aliases");
+    let hot_lookup = pg_table(schema, "hot_lookup");
+    let (alias_filter, definition_filter) = if single_page {
+        ("AND a.page_id = $2", "AND d.page_id = $2")
+    } else {
+        ("", "")
+    };
+
+    format!(
+        r#"
+        WITH hot_aliases AS (
+            SELECT a.language, a.normalized_alias, MIN(a.alias) AS alias, a.page_id
+            FROM {aliases} a
+            WHERE a.language = ANY($1) {alias_filter}
+            GROUP BY a.language, a.normalized_alias, a.page_id
+        ),
+        first_defs AS (
+            SELECT DISTINCT ON (d.page_id, d.language) d.page_id, d.language, d.definition_text
+            FROM {definitions} d
+            WHERE d.language = ANY($1) {definition_filter}
+            ORDER BY d.page_id, d.language, d.def_order
+        )
+        INSERT INTO {hot_lookup} (
+            language, normalized_alias, alias, page_id, title, url, primary_definition, updated_at
+        )
+        SELECT h.language, h.normalized_alias, h.alias, h.page_id, p.title, p.url, f.definition_text, now()
+        FROM hot_aliases h
+        JOIN {pages} p ON p.id = h.page_id AND p.deleted_at IS NULL
+        LEFT JOIN first_defs f ON f.page_id = h.page_id AND f.language = h.language
+        ON CONFLICT (language, normalized_alias, page_id) DO UPDATE SET
+            alias = EXCLUDED.alias,
+            title = EXCLUDED.title,
+            url = EXCLUDED.url,
+            primary_definition = EXCLUDED.primary_definition,
+            updated_at = now()
+        "#
+    )
+}
+
+fn languages_marker(languages: &[String]) -> String {
+    format!("hot_languages={}", languages.join(","))
+}
+
+fn sql_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::{Database, PageLineage, build_pg_config, test_postgres_config};
+    use crate::test_zim::page;
+
+    #[test]
+    #[ignore = "needs ZIMRS_TEST_PG_HOST"]
+    fn projection_follows_page_writes_and_language_changes() {
+        let schema = format!("zimrs_hot_lookup_{}", std::process::id());
+        let mut config = test_postgres_config(&schema);
+        config.hot_lookup.languages = vec!["en".to_owned(), " German ".to_owned()];
+        let mut client = build_pg_config(&config.postgres)
+            .expect("pg config")
+            .connect(postgres::NoTls)
+            .expect("connect to test postgres");
+        let hot_rows = |client: &mut postgres::Client| -> Vec<(String, String, Option<String>)> {
+            client
+                .query(
+                    &format!(
+                        "SELECT language, alias, primary_definition FROM {schema}.hot_lookup ORDER BY language"
+                    ),
+                    &[],
+                )
+                .expect("query hot_lookup")
+                .into_iter()
+                .map(|row| (row.get(0), row.get(1), row.get(2)))
+                .collect()
+        };
+        let index_comment = |client: &mut postgres::Client| -> Option<String> {
+            client
+                .query_one(
+                    "SELECT obj_description(to_regclass($1), 'pg_class')",
+                    &[&format!("{schema}.idx_aliases_hot_norm_page")],
+                )
+                .expect("query index comment")
+                .get(0)
+        };
+
+        let db = Database::open(&config).expect("open db");
+        db.init_schema().expect("init schema");
+        assert_eq!(
+            index_comment(&mut client).as_deref(),
+            Some("hot_languages=English,German")
+        );
+
+        let lineage = PageLineage::default();
+        db.upsert_page(
+            &page(
+                "salve",
+                &[
+                    ("Latin", "hello"),
+                    ("English", "an ointment"),
+                    ("English", "a remedy"),
+                ],
+                &[
+                    ("Latin", "Salve"),
+                    ("English", "salve"),
+                    ("English", "Salve"),
+                ],
+            ),
+            &lineage,
+        )
+        .expect("upsert page");
+        assert_eq!(
+            hot_rows(&mut client),
+            vec![(
+                "English".to_owned(),
+                "Salve".to_owned(),
+                Some("an ointment".to_owned())
+            )]
+        );
+        assert_eq!(db.finish_hot_lookup().expect("finish"), None);
+
+        db.upsert_page(
+            &page("salve", &[("Latin", "hello")], &[("Latin", "Salve")]),
+            &lineage,
+        )
+        .expect("rewrite page");
+        assert!(hot_rows(&mut client).is_empty());
+
+        config.sqlite.overwrite = false;
+        config.hot_lookup.languages = vec!["Latin".to_owned()];
+        config.hot_lookup.maintenance = crate::config::HotLookupMaintenance::Full;
+        let db = Database::open(&config).expect("reopen db");
+        db.init_schema().expect("init schema");
+        assert_eq!(
+            index_comment(&mut client).as_deref(),
+            Some("hot_languages=Latin")
+        );
+        assert_eq!(db.finish_hot_lookup().expect("full rebuild"), Some(1));
+        assert_eq!(
+            hot_rows(&mut client),
+            vec![(
+                "Latin".to_owned(),
+                "Salve".to_owned(),
+                Some("hello".to_owned())
+            )]
+        );
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE;"))
+            .expect("drop schema");
+    }
+}
@@ -0,0 +1,174 @@
+//! The small HTTP/1.1 server behind the metrics endpoint and the dictionary API: one request
+//! per connection, headers only, `Connection: close`.
+
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use tracing::warn;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// Accepts connections on a non-blocking `listener` until `stop` is set and hands each one to
+/// `answer`. `endpoint` names the server in warnings.
+pub(crate) fn serve(
+    listener: &TcpListener,
+    stop: &AtomicBool,
+    endpoint: &str,
+    mut answer: impl FnMut(TcpStream) -> io::Result<()>,
+) {
+    while !stop.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                if let Err(error) = answer(stream) {
+                    warn!(%peer, endpoint, error = %error, "failed to answer HTTP request");
+                }
+            }
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                thread::sleep(POLL_INTERVAL);
+            }
+            Err(error) => {
+                warn!(endpoint, error = %error, "HTTP accept failed");
+                thread::sleep(POLL_INTERVAL);
+            }
+        }
+    }
+}
+
+/// Request line and headers of one request; bodies are never read.
+pub(crate) struct RequestHead {
+    pub method: String,
+    pub target: String,
+    headers: Vec<(String, String)>,
+}
+
+impl RequestHead {
+    /// Reads up to the blank line ending the headers, or [`MAX_REQUEST_BYTES`]. An empty or
+    /// malformed request line leaves `method` empty and `target` at `/`.
+    pub fn read(stream: &mut TcpStream, timeout: Duration) -> io::Result<Self> {
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(timeout))?;
+
+        let mut request = Vec::new();
+        let mut chunk = [0_u8; 1024];
+        while !request.windows(4).any(|window| window == b"\r\n\r\n")
+            && request.len() < MAX_REQUEST_BYTES
+        {
+            let read = stream.read(&mut chunk)?;
+            if read == 0 {
+                break;
+            }
+            request.extend_from_slice(&chunk[..read]);
+        }
+
+        let head = String::from_utf8_lossy(&request);
+        let mut lines = head.lines();
+        let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+        let method = request_line.next().unwrap_or_default().to_owned();
+        let target = request_line.next().unwrap_or("/").to_owned();
+        let headers = lines
+            .take_while(|line| !line.is_empty())
+            .filter_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                Some((name.trim().to_owned(), value.trim().to_owned()))
+            })
+            .collect();
+        Ok(Self {
+            method,
+            target,
+            headers,
+        })
+    }
+
+    /// The first header named `name`, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Writes a complete response and flushes it.
+pub(crate) fn write_response(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    headers: &[(&str, String)],
+    body: &str,
+) -> io::Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        body.len()
+    );
+    for (name, value) in headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(body.as_bytes())?;
+    stream.flush()
+}
+
+/// Whether an `If-None-Match` field value matches `etag` (RFC 9110 section 13.1.2): `*` matches
+/// any representation, otherwise the field is a comma-separated list of entity tags compared
+/// weakly, so `W/"x"` matches `"x"`. Parsing stops at the first malformed tag.
+pub(crate) fn if_none_match(field: &str, etag: &str) -> bool {
+    let field = field.trim();
+    if field == "*" {
+        return true;
+    }
+    let etag = etag.strip_prefix("W/").unwrap_or(etag);
+
+    // Entity tags may contain commas, so the list is scanned tag by tag rather than split.
+    let mut rest = field;
+    loop {
+        rest = rest.trim_start_matches([' ', '\t', ',']);
+        if rest.is_empty() {
+            return false;
+        }
+        let tag_start = rest.strip_prefix("W/").unwrap_or(rest);
+        let Some(body) = tag_start.strip_prefix('"') else {
+            return false;
+        };
+        let Some(end) = body.find('"') else {
+            return false;
+        };
+        if &tag_start[..end + 2] == etag {
+            return true;
+        }
+        rest = &body[end + 1..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::if_none_match;
+
+    #[test]
+    fn if_none_match_scans_comma_separated_lists() {
+        assert!(if_none_match(r#""a", "b""#, r#""b""#));
+        assert!(if_none_match(r#""x,y", "b""#, r#""x,y""#));
+        assert!(!if_none_match(r#""a", "b""#, r#""c""#));
+        assert!(!if_none_match(r#""a" junk, "b""#, r#""b""#));
+        assert!(!if_none_match("", r#""a""#));
+    }
+
+    #[test]
+    fn if_none_match_compares_weak_tags_weakly() {
+        assert!(if_none_match(r#"W/"a""#, r#""a""#));
+        assert!(if_none_match(r#""a""#, r#"W/"a""#));
+        assert!(if_none_match(r#""z", W/"a""#, r#""a""#));
+        assert!(!if_none_match(r#"W/"ab""#, r#""a""#));
+    }
+
+    #[test]
+    fn if_none_match_star_matches_any_tag() {
+        assert!(if_none_match("*", r#""a""#));
+        assert!(if_none_match(" * ", r#"W/"a""#));
+        assert!(!if_none_match(r#"*, "b""#, r#""a""#));
+    }
+}
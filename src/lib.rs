@@ -1,11 +1,32 @@
+pub mod archive;
+pub mod compact;
 pub mod config;
 pub mod db;
 pub mod export;
 pub mod extractor;
+pub mod hot_lookup;
+mod http;
+pub mod migrations;
 pub mod normalization;
 pub mod pipeline;
+pub mod plan;
+pub mod progress;
+pub mod query;
 pub mod release;
+pub mod sample;
+pub mod search;
+pub mod selection;
+pub mod serve;
+pub mod shard;
+pub mod sink;
+pub mod suggest;
+pub mod telemetry;
+#[cfg(test)]
+mod test_zim;
 pub mod verify;
 
 pub use config::Config;
-pub use pipeline::{RunMetrics, run_conversion};
+pub use pipeline::{
+    PageStream, PageStreamError, PageStreamItem, RetryMetrics, RunMetrics, StreamedPage,
+    retry_failed, run_conversion, run_conversion_with_sink,
+};
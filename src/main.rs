@@ -1,6 +1,9 @@
-use std::path::PathBuf;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
@@ -8,12 +11,24 @@ use tracing::{info, warn};
 use tracing_appender::non_blocking::WorkerGuard;
 use tracing_subscriber::EnvFilter;
 use tracing_subscriber::fmt::writer::MakeWriterExt;
-use zimrs::config::{Config, StorageBackend};
+use zimrs::compact::{BuildIndexOptions, build_index};
+use zimrs::config::{
+    Config, RedirectMode, SampleStratify, SelectionConfig, StorageBackend, SweepPolicy,
+};
 use zimrs::db::Database;
 use zimrs::export::{ExportOptions, export_json};
+use zimrs::extractor::canonical_language_name;
+use zimrs::migrations::{MigrationState, render_status_table};
+use zimrs::plan::{plan_selection, render_plan_table};
+use zimrs::query::{Dictionary, LookupBy, LookupQuery, render_entry};
 use zimrs::release::{build_release_artifacts, create_sample_database};
-use zimrs::run_conversion;
+use zimrs::search::{SearchQuery, render_reverse_hits, render_search_hits};
+use zimrs::serve::ApiServer;
+use zimrs::shard::{ShardSpec, ShardStatus, separate_shard_outputs, shard_statuses};
+use zimrs::sink::SinkSpec;
+use zimrs::telemetry::MetricsExporter;
 use zimrs::verify::{VerifyOptions, verify_zim_file};
+use zimrs::{retry_failed, run_conversion};
 
 #[derive(Debug, Parser)]
 #[command(
@@ -72,9 +87,19 @@ struct Cli {
 #[derive(Debug, Subcommand)]
 enum Commands {
     Convert(ConvertArgs),
+    Plan(PlanArgs),
+    Migrate(MigrateArgs),
+    RetryFailed(RetryFailedArgs),
+    ShardsStatus(ShardsStatusArgs),
     VerifyZim(VerifyZimArgs),
     Reindex(ReindexArgs),
+    HotLookup(HotLookupArgs),
+    Lookup(LookupArgs),
+    Search(SearchArgs),
+    ReverseLookup(ReverseLookupArgs),
+    Serve(ServeArgs),
     ExportJson(ExportJsonArgs),
+    BuildIndex(BuildIndexArgs),
     SampleDb(SampleDbArgs),
     BuildArtifacts(BuildArtifactsArgs),
 }
@@ -98,6 +123,163 @@ struct ConvertArgs {
 
     #[arg(long)]
     extraction_threads: Option<usize>,
+
+    #[arg(long, help = "Skip pages whose content hash matches the stored page")]
+    incremental: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Mark or delete pages missing from the archive after a full-range run"
+    )]
+    sweep: Option<SweepPolicy>,
+
+    #[arg(
+        long,
+        help = "Only convert the configured archive with this edition tag"
+    )]
+    edition: Option<String>,
+
+    #[arg(long, value_enum, help = "How redirect entries are ingested")]
+    redirects: Option<RedirectMode>,
+
+    #[arg(
+        long,
+        value_name = "INDEX/COUNT",
+        conflicts_with_all = ["start_index", "max_entries", "overwrite"],
+        help = "Convert only this slice of the entry index, e.g. 3/8, under its own checkpoint"
+    )]
+    shard: Option<ShardSpec>,
+
+    #[arg(
+        long = "sink",
+        value_name = "SINK",
+        help = "Page output: db, null or jsonl:<path>; repeat to write to several at once"
+    )]
+    sinks: Vec<SinkSpec>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Only convert the titles listed in this file, one per line"
+    )]
+    title_list: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        conflicts_with_all = ["shard", "title_list"],
+        help = "Convert a seeded random sample of N entries into the sample database"
+    )]
+    sample: Option<usize>,
+
+    #[arg(long, value_name = "SEED", help = "Seed for --sample")]
+    sample_seed: Option<u64>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Spread the --sample across namespaces or languages"
+    )]
+    stratify: Option<SampleStratify>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Keep a JSON progress snapshot with throughput and ETA at this path"
+    )]
+    status_file: Option<PathBuf>,
+
+    #[arg(long, help = "Draw a progress bar on stderr")]
+    progress: bool,
+
+    #[command(flatten)]
+    metrics: MetricsArgs,
+}
+
+#[derive(Debug, Default, clap::Args)]
+struct MetricsArgs {
+    #[arg(
+        long,
+        value_name = "ADDR",
+        help = "Serve Prometheus metrics on http://ADDR/metrics while running"
+    )]
+    metrics_listen: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Rewrite a node-exporter textfile with Prometheus metrics while running"
+    )]
+    metrics_textfile: Option<PathBuf>,
+}
+
+impl MetricsArgs {
+    fn apply(self, config: &mut Config) {
+        if let Some(listen) = self.metrics_listen {
+            config.metrics.listen = Some(listen);
+        }
+        if let Some(path) = self.metrics_textfile {
+            config.metrics.textfile_path = Some(path);
+        }
+    }
+}
+
+#[derive(Debug, clap::Args)]
+struct PlanArgs {
+    #[arg(long, help = "Only plan the configured archive with this edition tag")]
+    edition: Option<String>,
+
+    #[arg(long, value_enum, default_value = "table")]
+    format: ReportFormat,
+
+    #[arg(long, help = "Write the report to this file instead of stdout")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum ReportFormat {
+    Table,
+    Json,
+}
+
+#[derive(Debug, clap::Args)]
+struct MigrateArgs {
+    #[command(subcommand)]
+    action: Option<MigrateAction>,
+
+    #[arg(long, help = "List pending migrations without applying them")]
+    dry_run: bool,
+}
+
+#[derive(Debug, Subcommand)]
+enum MigrateAction {
+    /// Show every migration and whether it is applied, pending or modified
+    Status {
+        #[arg(long, value_enum, default_value = "table")]
+        format: ReportFormat,
+    },
+}
+
+#[derive(Debug, clap::Args)]
+struct ShardsStatusArgs {
+    #[arg(long, help = "Number of shards the conversion was split into")]
+    shards: u32,
+
+    #[arg(
+        long,
+        help = "Only report shards of the configured archive with this edition tag"
+    )]
+    edition: Option<String>,
+}
+
+#[derive(Debug, clap::Args)]
+struct RetryFailedArgs {
+    #[arg(
+        long,
+        help = "Only retry failures of the configured archive with this edition tag"
+    )]
+    edition: Option<String>,
 }
 
 #[derive(Debug, clap::Args)]
@@ -107,6 +289,94 @@ struct ReindexArgs {
 
     #[arg(long)]
     chunk_size: Option<usize>,
+
+    #[command(flatten)]
+    metrics: MetricsArgs,
+}
+
+#[derive(Debug, clap::Args)]
+struct HotLookupArgs {
+    #[command(subcommand)]
+    action: HotLookupAction,
+}
+
+#[derive(Debug, Subcommand)]
+enum HotLookupAction {
+    /// Rebuild the hot_lookup projection and its partial indexes for [hot_lookup].languages
+    Rebuild,
+}
+
+#[derive(Debug, clap::Args)]
+struct LookupArgs {
+    word: String,
+
+    #[arg(long, help = "Only match aliases in this language (name or ISO code)")]
+    lang: Option<String>,
+
+    #[arg(long, help = "Only match pages of this edition")]
+    edition: Option<String>,
+
+    #[arg(
+        long,
+        help = "Match the exact page title instead of normalized aliases"
+    )]
+    headword: bool,
+
+    #[arg(long, help = "Include synonyms, translations and other relations")]
+    relations: bool,
+
+    #[arg(long, help = "Print the matching pages as JSON")]
+    json: bool,
+
+    #[arg(long, default_value_t = 20)]
+    limit: usize,
+}
+
+#[derive(Debug, clap::Args)]
+struct SearchArgs {
+    /// Words, "quoted phrases" and prefix* terms; all must match
+    #[arg(required = true, num_args = 1..)]
+    query: Vec<String>,
+
+    #[arg(long, help = "Only return pages with a definition in this language")]
+    lang: Option<String>,
+
+    #[arg(long, default_value_t = 0)]
+    offset: usize,
+
+    #[arg(long, default_value_t = 20)]
+    limit: usize,
+
+    #[arg(long, help = "Print the hits as JSON")]
+    json: bool,
+}
+
+#[derive(Debug, clap::Args)]
+struct ReverseLookupArgs {
+    /// Description of the word; headwords whose senses match more of it rank first
+    #[arg(required = true, num_args = 1..)]
+    query: Vec<String>,
+
+    #[arg(long, help = "Only match senses in this language")]
+    lang: Option<String>,
+
+    #[arg(long, default_value_t = 0)]
+    offset: usize,
+
+    #[arg(long, default_value_t = 20)]
+    limit: usize,
+
+    #[arg(long, help = "Print the hits as JSON")]
+    json: bool,
+}
+
+#[derive(Debug, clap::Args)]
+struct ServeArgs {
+    #[arg(long, help = "Address to listen on, e.g. 127.0.0.1:8080")]
+    listen: Option<String>,
+
+    #[arg(long, help = "Request threads and read connections")]
+    threads: Option<usize>,
 }
 
 #[derive(Debug, clap::Args)]
@@ -142,6 +412,16 @@ struct ExportJsonArgs {
     batch_size: Option<usize>,
 }
 
+#[derive(Debug, clap::Args)]
+struct BuildIndexArgs {
+    /// Directory receiving aliases.fst and entries.bin
+    #[arg(long)]
+    output: PathBuf,
+
+    #[arg(long, help = "Only index definitions in this language (repeatable)")]
+    lang: Vec<String>,
+}
+
 #[derive(Debug, clap::Args)]
 struct SampleDbArgs {
     #[arg(long)]
@@ -212,24 +492,75 @@ fn main() -> Result<()> {
         no_resume: false,
         checkpoint_name: None,
         extraction_threads: None,
+        incremental: false,
+        sweep: None,
+        edition: None,
+        redirects: None,
+        shard: None,
+        sinks: Vec::new(),
+        title_list: None,
+        sample: None,
+        sample_seed: None,
+        stratify: None,
+        status_file: None,
+        progress: false,
+        metrics: MetricsArgs::default(),
     })) {
         Commands::Convert(args) => run_convert(args, config, &cli.config),
+        Commands::Plan(args) => run_plan(args, config),
+        Commands::Migrate(args) => run_migrate(args, config),
+        Commands::RetryFailed(args) => run_retry_failed(args, config),
+        Commands::ShardsStatus(args) => run_shards_status(args, config),
         Commands::VerifyZim(args) => run_verify_zim(args, config),
         Commands::Reindex(args) => run_reindex(args, config),
+        Commands::HotLookup(args) => run_hot_lookup(args, config),
+        Commands::Lookup(args) => run_lookup(args, config),
+        Commands::Search(args) => run_search(args, config),
+        Commands::ReverseLookup(args) => run_reverse_lookup(args, config),
+        Commands::Serve(args) => run_serve(args, config),
         Commands::ExportJson(args) => run_export_json(args, config),
+        Commands::BuildIndex(args) => run_build_index(args, config),
         Commands::SampleDb(args) => run_sample_db(args),
         Commands::BuildArtifacts(args) => run_build_artifacts(args, config, &cli.config),
     }
 }
 
-fn run_convert(args: ConvertArgs, mut config: Config, config_path: &PathBuf) -> Result<()> {
-    if let Some(max_entries) = args.max_entries {
-        config.selection.max_entries = Some(max_entries);
+fn run_convert(args: ConvertArgs, mut config: Config, config_path: &Path) -> Result<()> {
+    apply_selection_overrides(&mut config.selection, &args);
+    for selection in config
+        .archives
+        .iter_mut()
+        .filter_map(|archive| archive.selection.as_mut())
+    {
+        apply_selection_overrides(selection, &args);
     }
 
-    if let Some(start_index) = args.start_index {
-        config.selection.start_index = start_index;
+    if let Some(edition) = args.edition {
+        select_edition(&mut config, edition)?;
+    }
+
+    if !args.sinks.is_empty() {
+        config.output.sinks = args.sinks;
+    }
+
+    if let Some(size) = args.sample {
+        config.sample.size = Some(size);
+    }
+    if let Some(seed) = args.sample_seed {
+        config.sample.seed = seed;
+    }
+    if let Some(stratify) = args.stratify {
+        config.sample.stratify = stratify;
+    }
+
+    if let Some(path) = args.status_file {
+        config.logging.status_file = Some(path);
+    }
+    if args.progress {
+        config.logging.progress_bar = true;
     }
+    args.metrics.apply(&mut config);
+    separate_shard_outputs(&mut config)?;
 
     if args.overwrite {
         // Overwrite is backend-agnostic; SQLite removes the DB file, Postgres resets target schema.
@@ -241,6 +572,17 @@ fn run_convert(args: ConvertArgs, mut config: Config, config_path: &PathBuf) ->
     }
 
     if let Some(name) = args.checkpoint_name {
+        // The CLI name is the base for every archive, including those that name their own.
+        for archive in &mut config.archives {
+            if let Some(archive_name) = archive.checkpoint_name.take() {
+                warn!(
+                    edition = %archive.edition,
+                    archive_checkpoint_name = %archive_name,
+                    checkpoint_name = %name,
+                    "--checkpoint-name overrides the archive's checkpoint_name"
+                );
+            }
+        }
         config.checkpoint.name = name;
     }
 
@@ -248,9 +590,25 @@ fn run_convert(args: ConvertArgs, mut config: Config, config_path: &PathBuf) ->
         config.workers.extraction_threads = threads.max(1);
     }
 
+    if args.incremental {
+        config.incremental.skip_unchanged = true;
+    }
+
+    if let Some(policy) = args.sweep {
+        config.sweep.enabled = true;
+        config.sweep.policy = policy;
+    }
+
     info!(
         config_path = %config_path.display(),
         zim_path = %config.input.zim_path.display(),
+        archives = config.archives.len(),
+        sinks = ?config
+            .output
+            .sinks
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
         backend = ?config.backend,
         pg_host = %config.postgres.host,
         pg_port = config.postgres.port,
@@ -259,25 +617,268 @@ fn run_convert(args: ConvertArgs, mut config: Config, config_path: &PathBuf) ->
         sqlite_path = %config.input.sqlite_path.display(),
         "starting conversion"
     );
+    if let Some(size) = config.sample.size {
+        info!(
+            sample_size = size,
+            seed = config.sample.seed,
+            stratify = ?config.sample.stratify,
+            sqlite_path = %config.sample.sqlite_path.display(),
+            pg_schema = %config.sample.postgres_schema,
+            "sample run; writing to the sample database"
+        );
+    }
 
-    let metrics = run_conversion(&config)?;
+    let exporter = MetricsExporter::start(&config.metrics)?;
+    for metrics in run_conversion(&config)? {
+        if metrics.ingested_pages == 0 {
+            warn!(edition = %metrics.edition, "conversion finished with zero ingested pages");
+        }
 
-    if metrics.ingested_pages == 0 {
-        warn!("conversion finished with zero ingested pages");
+        info!(
+            edition = %metrics.edition,
+            shard = ?metrics.shard.map(|shard| shard.to_string()),
+            elapsed_ms = metrics.elapsed_ms(),
+            scanned_entries = metrics.scanned_entries,
+            filtered_entries = metrics.filtered_entries,
+            ingested_pages = metrics.ingested_pages,
+            unchanged_pages = metrics.unchanged_pages,
+            extracted_definitions = metrics.extracted_definitions,
+            extracted_relations = metrics.extracted_relations,
+            extraction_errors = metrics.extraction_errors,
+            checkpoint_updates = metrics.checkpoint_updates,
+            resumed_from_checkpoint = metrics.resumed_from_checkpoint,
+            run_id = ?metrics.run_id,
+            source_archive_id = ?metrics.source_archive_id,
+            swept_pages = metrics.swept_pages,
+            swept_fts_rows = metrics.swept_fts_rows,
+            swept_hot_lookup_rows = metrics.swept_hot_lookup_rows,
+            queued_redirects = metrics.queued_redirects,
+            redirect_aliases = metrics.redirect_aliases,
+            unresolved_redirects = metrics.unresolved_redirects,
+            missing_list_titles = metrics.missing_list_titles,
+            guard_truncated_pages = metrics.guard_truncated_pages,
+            guard_skipped_pages = metrics.guard_skipped_pages,
+            extraction_panics = metrics.extraction_panics,
+            entries_per_sec = metrics.entries_per_sec().round(),
+            decompressed_mb_per_sec = metrics.decompressed_mb_per_sec(),
+            directory_ms = metrics.stage_timings.directory.as_millis(),
+            decompress_ms = metrics.stage_timings.decompress.as_millis(),
+            extract_ms = metrics.stage_timings.extract.as_millis(),
+            persist_ms = metrics.stage_timings.persist.as_millis(),
+            "run summary"
+        );
     }
 
-    info!(
-        elapsed_ms = metrics.elapsed_ms(),
-        scanned_entries = metrics.scanned_entries,
-        filtered_entries = metrics.filtered_entries,
-        ingested_pages = metrics.ingested_pages,
-        extracted_definitions = metrics.extracted_definitions,
-        extracted_relations = metrics.extracted_relations,
-        extraction_errors = metrics.extraction_errors,
-        checkpoint_updates = metrics.checkpoint_updates,
-        resumed_from_checkpoint = metrics.resumed_from_checkpoint,
-        "run summary"
-    );
+    if let Some(exporter) = exporter {
+        exporter.shutdown()?;
+    }
+
+    Ok(())
+}
+
+fn run_plan(args: PlanArgs, mut config: Config) -> Result<()> {
+    if let Some(edition) = args.edition {
+        select_edition(&mut config, edition)?;
+    }
+
+    let plans = plan_selection(&config)?;
+    let report = match args.format {
+        ReportFormat::Table => render_plan_table(&plans),
+        ReportFormat::Json => {
+            let mut json = serde_json::to_string_pretty(&plans)?;
+            json.push('\n');
+            json
+        }
+    };
+
+    match &args.output {
+        Some(path) => fs::write(path, &report)
+            .with_context(|| format!("failed to write plan to {}", path.display()))?,
+        None => io::stdout()
+            .lock()
+            .write_all(report.as_bytes())
+            .context("failed to write plan to stdout")?,
+    }
+
+    for plan in &plans {
+        info!(
+            edition = %plan.edition,
+            scanned_entries = plan.scanned_entries,
+            selected_entries = plan.selected_entries,
+            clusters_to_decompress = plan.clusters_to_decompress,
+            compressed_cluster_bytes = plan.compressed_cluster_bytes,
+            "selection plan complete"
+        );
+    }
+
+    Ok(())
+}
+
+fn run_migrate(args: MigrateArgs, mut config: Config) -> Result<()> {
+    config.sqlite.overwrite = false;
+    let db = Database::open(&config)?;
+
+    let report = match args.action {
+        Some(MigrateAction::Status { format }) => {
+            let statuses = db.migration_status()?;
+            info!(
+                applied = statuses
+                    .iter()
+                    .filter(|status| status.state == MigrationState::Applied)
+                    .count(),
+                pending = statuses
+                    .iter()
+                    .filter(|status| status.state == MigrationState::Pending)
+                    .count(),
+                "schema migration status"
+            );
+            match format {
+                ReportFormat::Table => render_status_table(&statuses),
+                ReportFormat::Json => {
+                    let mut json = serde_json::to_string_pretty(&statuses)?;
+                    json.push('\n');
+                    json
+                }
+            }
+        }
+        None => {
+            let migrations = db.migrate(args.dry_run)?;
+            info!(
+                dry_run = args.dry_run,
+                migrations = migrations.len(),
+                "schema migration complete"
+            );
+            render_status_table(&migrations)
+        }
+    };
+
+    io::stdout()
+        .lock()
+        .write_all(report.as_bytes())
+        .context("failed to write migration report to stdout")
+}
+
+fn run_retry_failed(args: RetryFailedArgs, mut config: Config) -> Result<()> {
+    config.sqlite.overwrite = false;
+
+    if let Some(edition) = args.edition {
+        select_edition(&mut config, edition)?;
+    }
+
+    for metrics in retry_failed(&config)? {
+        info!(
+            edition = %metrics.edition,
+            run_id = ?metrics.run_id,
+            attempted = metrics.attempted,
+            recovered = metrics.recovered,
+            dropped = metrics.dropped,
+            still_failing = metrics.still_failing,
+            "retry summary"
+        );
+    }
+
+    Ok(())
+}
+
+fn run_shards_status(args: ShardsStatusArgs, mut config: Config) -> Result<()> {
+    config.sqlite.overwrite = false;
+    if args.shards == 0 {
+        anyhow::bail!("--shards must be at least 1");
+    }
+
+    if let Some(edition) = args.edition {
+        select_edition(&mut config, edition)?;
+    }
+
+    let db = Database::open(&config)?;
+    db.init_schema()?;
+
+    for archive_config in config.archive_configs()? {
+        let statuses = shard_statuses(&archive_config, &db, args.shards)?;
+        for status in &statuses {
+            info!(
+                edition = %status.edition,
+                shard = %status.shard,
+                checkpoint_name = %status.checkpoint_name,
+                start_index = status.start_index,
+                end_index = status.end_index,
+                processed_entries = status.processed_entries,
+                total_entries = status.total_entries(),
+                ingested_pages = status.ingested_pages,
+                run_id = ?status.run_id,
+                state = shard_state(status),
+                "shard status"
+            );
+        }
+
+        let total_entries: u64 = statuses
+            .iter()
+            .map(|status| u64::from(status.total_entries()))
+            .sum();
+        let processed_entries: u64 = statuses
+            .iter()
+            .map(|status| u64::from(status.processed_entries))
+            .sum();
+        let percent_done = if total_entries == 0 {
+            100.0
+        } else {
+            processed_entries as f64 * 100.0 / total_entries as f64
+        };
+        info!(
+            edition = %archive_config.input.edition,
+            shards = args.shards,
+            completed_shards = statuses.iter().filter(|status| status.is_complete()).count(),
+            started_shards = statuses.iter().filter(|status| status.started).count(),
+            processed_entries,
+            total_entries,
+            ingested_pages = statuses.iter().map(|status| status.ingested_pages).sum::<u64>(),
+            percent_done = format!("{percent_done:.1}"),
+            "shards summary"
+        );
+    }
+
+    Ok(())
+}
+
+fn shard_state(status: &ShardStatus) -> &'static str {
+    if status.is_complete() {
+        "complete"
+    } else if status.started {
+        "in_progress"
+    } else {
+        "pending"
+    }
+}
+
+/// Applies the convert flags that narrow the entry window to one selection profile.
+fn apply_selection_overrides(selection: &mut SelectionConfig, args: &ConvertArgs) {
+    if let Some(max_entries) = args.max_entries {
+        selection.max_entries = Some(max_entries);
+    }
+    if let Some(start_index) = args.start_index {
+        selection.start_index = start_index;
+    }
+    if let Some(mode) = args.redirects {
+        selection.redirect_mode = Some(mode);
+    }
+    if let Some(shard) = args.shard {
+        selection.shard = Some(shard);
+    }
+    if let Some(path) = &args.title_list {
+        selection.title_list_path = Some(path.clone());
+    }
+}
+
+fn select_edition(config: &mut Config, edition: String) -> Result<()> {
+    if config.archives.is_empty() {
+        config.input.edition = edition;
+        return Ok(());
+    }
+
+    config.archives.retain(|archive| archive.edition == edition);
+    if config.archives.is_empty() {
+        anyhow::bail!("no configured archive has edition '{edition}'");
+    }
 
     Ok(())
 }
@@ -292,7 +893,9 @@ fn run_reindex(args: ReindexArgs, mut config: Config) -> Result<()> {
     if let Some(chunk_size) = args.chunk_size {
         config.reindex.chunk_size = chunk_size.max(1);
     }
+    args.metrics.apply(&mut config);
 
+    let exporter = MetricsExporter::start(&config.metrics)?;
     let db = Database::open(&config)?;
     db.init_schema()?;
     let metrics =
@@ -300,13 +903,150 @@ fn run_reindex(args: ReindexArgs, mut config: Config) -> Result<()> {
 
     info!(
         updated_pages = metrics.updated_pages,
+        hot_lookup_rows = metrics.hot_lookup_rows,
         watermark = ?metrics.watermark,
         "incremental reindex complete"
     );
+    if let Some(rows) = db.finish_hot_lookup()? {
+        info!(hot_lookup_rows = rows, "rebuilt hot_lookup projection");
+    }
+
+    if let Some(exporter) = exporter {
+        exporter.shutdown()?;
+    }
 
     Ok(())
 }
 
+fn run_hot_lookup(args: HotLookupArgs, mut config: Config) -> Result<()> {
+    config.sqlite.overwrite = false;
+
+    let db = Database::open(&config)?;
+    db.init_schema()?;
+    match args.action {
+        HotLookupAction::Rebuild => {
+            let started = Instant::now();
+            let rows = db.rebuild_hot_lookup()?;
+            info!(
+                hot_lookup_rows = rows,
+                elapsed_ms = started.elapsed().as_millis(),
+                "rebuilt hot_lookup projection"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn run_lookup(args: LookupArgs, config: Config) -> Result<()> {
+    let dictionary = Dictionary::open(&config)?;
+    let query = LookupQuery {
+        by: if args.headword {
+            LookupBy::Headword
+        } else {
+            LookupBy::Alias
+        },
+        language: args.lang,
+        edition: args.edition,
+        limit: args.limit,
+        ..LookupQuery::new(args.word.as_str())
+    };
+
+    let mut pages = dictionary.lookup(&query)?.pages;
+    if pages.is_empty() {
+        anyhow::bail!("no entry for {:?}", args.word);
+    }
+    if !args.relations {
+        for page in &mut pages {
+            page.relations.clear();
+        }
+    }
+
+    let report = if args.json {
+        let mut json = serde_json::to_string_pretty(&pages)?;
+        json.push('\n');
+        json
+    } else {
+        let language = query
+            .language
+            .as_deref()
+            .map(|language| canonical_language_name(language.trim()));
+        pages
+            .iter()
+            .map(|page| render_entry(page, language.as_deref(), args.relations))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    io::stdout()
+        .lock()
+        .write_all(report.as_bytes())
+        .context("failed to write lookup to stdout")
+}
+
+fn run_search(args: SearchArgs, config: Config) -> Result<()> {
+    let dictionary = Dictionary::open(&config)?;
+    let hits = dictionary.search(&SearchQuery {
+        text: args.query.join(" "),
+        language: args.lang,
+        offset: args.offset,
+        limit: args.limit,
+    })?;
+
+    let report = if args.json {
+        let mut json = serde_json::to_string_pretty(&hits)?;
+        json.push('\n');
+        json
+    } else if hits.is_empty() {
+        "no matches\n".to_owned()
+    } else {
+        render_search_hits(&hits, args.offset)
+    };
+
+    io::stdout()
+        .lock()
+        .write_all(report.as_bytes())
+        .context("failed to write search results to stdout")
+}
+
+fn run_reverse_lookup(args: ReverseLookupArgs, config: Config) -> Result<()> {
+    let dictionary = Dictionary::open(&config)?;
+    let hits = dictionary.reverse_lookup(&SearchQuery {
+        text: args.query.join(" "),
+        language: args.lang,
+        offset: args.offset,
+        limit: args.limit,
+    })?;
+
+    let report = if args.json {
+        let mut json = serde_json::to_string_pretty(&hits)?;
+        json.push('\n');
+        json
+    } else if hits.is_empty() {
+        "no matches\n".to_owned()
+    } else {
+        render_reverse_hits(&hits, args.offset)
+    };
+
+    io::stdout()
+        .lock()
+        .write_all(report.as_bytes())
+        .context("failed to write reverse lookup results to stdout")
+}
+
+fn run_serve(args: ServeArgs, mut config: Config) -> Result<()> {
+    if let Some(listen) = args.listen {
+        config.serve.listen = listen;
+    }
+    if let Some(threads) = args.threads {
+        config.serve.threads = threads;
+    }
+
+    let dictionary = Arc::new(Dictionary::open(&config)?);
+    ApiServer::start(&config.serve, dictionary)?.wait();
+    Ok(())
+}
+
 fn run_verify_zim(args: VerifyZimArgs, config: Config) -> Result<()> {
     let path = args.path.unwrap_or(config.input.zim_path);
     let options = VerifyOptions {
@@ -361,17 +1101,34 @@ fn run_export_json(args: ExportJsonArgs, mut config: Config) -> Result<()> {
     Ok(())
 }
 
+fn run_build_index(args: BuildIndexArgs, config: Config) -> Result<()> {
+    let options = BuildIndexOptions {
+        output_dir: args.output,
+        languages: args.lang,
+    };
+    let metrics = build_index(&config, &options)?;
+
+    info!(
+        output_dir = %options.output_dir.display(),
+        entries = metrics.entries,
+        definitions = metrics.definitions,
+        aliases = metrics.aliases,
+        shared_aliases = metrics.shared_aliases,
+        alias_map_bytes = metrics.alias_map_bytes,
+        entries_bytes = metrics.entries_bytes,
+        "compact index built"
+    );
+
+    Ok(())
+}
+
 fn run_sample_db(args: SampleDbArgs) -> Result<()> {
     create_sample_database(&args.output)?;
     info!(output = %args.output.display(), "sample database created");
     Ok(())
 }
 
-fn run_build_artifacts(
-    args: BuildArtifactsArgs,
-    config: Config,
-    config_path: &PathBuf,
-) -> Result<()> {
+fn run_build_artifacts(args: BuildArtifactsArgs, config: Config, config_path: &Path) -> Result<()> {
     let binary_path = if args.build_release {
         let status = Command::new("cargo")
             .arg("build")
@@ -0,0 +1,1578 @@
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use postgres::GenericClient;
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tracing::info;
+
+use crate::db::pg_ident;
+
+/// One schema change, applied once per database and recorded in `schema_migrations`. Never edit
+/// a released migration: its checksum is compared against the recorded one on every run, so
+/// changes go into a new, higher-numbered migration instead.
+#[derive(Debug)]
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub steps: &'static [Step],
+    /// SQLite only: run with foreign keys off, for table rebuilds that would otherwise cascade
+    /// deletes into child tables.
+    pub foreign_keys_off: bool,
+}
+
+#[derive(Debug)]
+pub enum Step {
+    /// SQL run as one batch. Postgres SQL names tables as `{schema}.table`.
+    Sql(&'static str),
+    /// Adds a column unless an older release already added it.
+    AddColumn {
+        table: &'static str,
+        column: &'static str,
+        definition: &'static str,
+    },
+}
+
+impl Migration {
+    /// SHA-256 over the migration's steps, so a changed migration is caught even if its name
+    /// and version stay the same.
+    pub fn checksum(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(format!("foreign_keys_off={}\n", self.foreign_keys_off));
+        for step in self.steps {
+            match step {
+                Step::Sql(sql) => hasher.update(format!("sql\n{sql}\n")),
+                Step::AddColumn {
+                    table,
+                    column,
+                    definition,
+                } => hasher.update(format!("add_column {table} {column} {definition}\n")),
+            }
+        }
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// Recorded with a different checksum than the migration in this build.
+    Modified,
+    /// Recorded in the database but unknown to this build, which is older than the schema.
+    Unknown,
+}
+
+impl MigrationState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Applied => "applied",
+            Self::Pending => "pending",
+            Self::Modified => "modified",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub name: String,
+    pub checksum: String,
+    pub state: MigrationState,
+    pub applied_unix_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+struct AppliedMigration {
+    version: i64,
+    name: String,
+    checksum: String,
+    applied_unix_ms: i64,
+}
+
+pub const SQLITE_MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "create_pages_definitions_runs",
+        steps: &[Step::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS pages (
+                id INTEGER PRIMARY KEY,
+                url TEXT NOT NULL UNIQUE,
+                title TEXT NOT NULL,
+                namespace TEXT NOT NULL,
+                mime_type TEXT NOT NULL,
+                cluster_idx INTEGER,
+                blob_idx INTEGER,
+                redirect_url TEXT,
+                content_sha256 TEXT,
+                raw_html TEXT,
+                plain_text TEXT,
+                extraction_confidence REAL NOT NULL DEFAULT 0.0,
+                updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ','now'))
+            );
+
+            CREATE TABLE IF NOT EXISTS definitions (
+                id INTEGER PRIMARY KEY,
+                page_id INTEGER NOT NULL REFERENCES pages(id) ON DELETE CASCADE,
+                language TEXT NOT NULL,
+                def_order INTEGER NOT NULL,
+                definition_text TEXT NOT NULL,
+                normalized_text TEXT NOT NULL DEFAULT '',
+                confidence REAL NOT NULL DEFAULT 0.0,
+                UNIQUE(page_id, language, def_order)
+            );
+
+            CREATE TABLE IF NOT EXISTS ingestion_runs (
+                id INTEGER PRIMARY KEY,
+                started_unix_ms INTEGER NOT NULL,
+                finished_unix_ms INTEGER NOT NULL,
+                scanned_entries INTEGER NOT NULL,
+                filtered_entries INTEGER NOT NULL,
+                ingested_pages INTEGER NOT NULL,
+                extracted_definitions INTEGER NOT NULL,
+                extracted_relations INTEGER NOT NULL DEFAULT 0,
+                extraction_errors INTEGER NOT NULL
+            );
+            "#,
+        )],
+        foreign_keys_off: false,
+    },
+    Migration {
+        version: 2,
+        name: "add_relations_aliases_checkpoints",
+        steps: &[
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS ingestion_runs (
+                    id INTEGER PRIMARY KEY,
+                    started_unix_ms INTEGER NOT NULL,
+                    finished_unix_ms INTEGER NOT NULL,
+                    scanned_entries INTEGER NOT NULL,
+                    filtered_entries INTEGER NOT NULL,
+                    ingested_pages INTEGER NOT NULL,
+                    extracted_definitions INTEGER NOT NULL,
+                    extracted_relations INTEGER NOT NULL DEFAULT 0,
+                    extraction_errors INTEGER NOT NULL
+                );
+                "#,
+            ),
+            Step::AddColumn {
+                table: "pages",
+                column: "extraction_confidence",
+                definition: "REAL NOT NULL DEFAULT 0.0",
+            },
+            Step::AddColumn {
+                table: "definitions",
+                column: "normalized_text",
+                definition: "TEXT NOT NULL DEFAULT ''",
+            },
+            Step::AddColumn {
+                table: "definitions",
+                column: "confidence",
+                definition: "REAL NOT NULL DEFAULT 0.0",
+            },
+            Step::AddColumn {
+                table: "ingestion_runs",
+                column: "extracted_relations",
+                definition: "INTEGER NOT NULL DEFAULT 0",
+            },
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS relations (
+                    id INTEGER PRIMARY KEY,
+                    page_id INTEGER NOT NULL REFERENCES pages(id) ON DELETE CASCADE,
+                    language TEXT NOT NULL,
+                    relation_type TEXT NOT NULL,
+                    rel_order INTEGER NOT NULL,
+                    source_text TEXT NOT NULL,
+                    target_term TEXT NOT NULL,
+                    normalized_target TEXT NOT NULL,
+                    confidence REAL NOT NULL DEFAULT 0.0,
+                    UNIQUE(page_id, language, relation_type, rel_order, target_term)
+                );
+
+                CREATE TABLE IF NOT EXISTS lemma_aliases (
+                    id INTEGER PRIMARY KEY,
+                    page_id INTEGER NOT NULL REFERENCES pages(id) ON DELETE CASCADE,
+                    language TEXT,
+                    alias TEXT NOT NULL,
+                    normalized_alias TEXT NOT NULL,
+                    source TEXT NOT NULL,
+                    UNIQUE(page_id, language, alias, source)
+                );
+
+                CREATE TABLE IF NOT EXISTS ingestion_checkpoints (
+                    name TEXT PRIMARY KEY,
+                    last_processed_index INTEGER NOT NULL,
+                    updated_unix_ms INTEGER NOT NULL,
+                    ingested_pages INTEGER NOT NULL,
+                    extracted_definitions INTEGER NOT NULL,
+                    extracted_relations INTEGER NOT NULL,
+                    metadata_json TEXT NOT NULL DEFAULT '{}'
+                );
+
+                CREATE TABLE IF NOT EXISTS reindex_state (
+                    name TEXT PRIMARY KEY,
+                    last_updated_at TEXT NOT NULL DEFAULT ''
+                );
+                "#,
+            ),
+        ],
+        foreign_keys_off: false,
+    },
+    Migration {
+        version: 3,
+        name: "add_lookup_indexes",
+        steps: &[Step::Sql(
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_pages_title ON pages(title);
+            CREATE INDEX IF NOT EXISTS idx_pages_updated_at ON pages(updated_at);
+            CREATE INDEX IF NOT EXISTS idx_definitions_page ON definitions(page_id);
+            CREATE INDEX IF NOT EXISTS idx_definitions_language ON definitions(language);
+            CREATE INDEX IF NOT EXISTS idx_definitions_norm ON definitions(normalized_text);
+            CREATE INDEX IF NOT EXISTS idx_relations_page ON relations(page_id);
+            CREATE INDEX IF NOT EXISTS idx_relations_type ON relations(relation_type);
+            CREATE INDEX IF NOT EXISTS idx_relations_target ON relations(normalized_target);
+            CREATE INDEX IF NOT EXISTS idx_aliases_page ON lemma_aliases(page_id);
+            CREATE INDEX IF NOT EXISTS idx_aliases_norm ON lemma_aliases(normalized_alias);
+            "#,
+        )],
+        foreign_keys_off: false,
+    },
+    Migration {
+        version: 4,
+        name: "add_sweep_columns",
+        steps: &[
+            Step::AddColumn {
+                table: "pages",
+                column: "last_seen_run_id",
+                definition: "INTEGER",
+            },
+            Step::AddColumn {
+                table: "pages",
+                column: "deleted_at",
+                definition: "TEXT",
+            },
+            Step::AddColumn {
+                table: "ingestion_runs",
+                column: "unchanged_pages",
+                definition: "INTEGER NOT NULL DEFAULT 0",
+            },
+            Step::AddColumn {
+                table: "ingestion_runs",
+                column: "swept_pages",
+                definition: "INTEGER NOT NULL DEFAULT 0",
+            },
+            Step::Sql(
+                "CREATE INDEX IF NOT EXISTS idx_pages_last_seen_run ON pages(last_seen_run_id);",
+            ),
+        ],
+        foreign_keys_off: false,
+    },
+    Migration {
+        version: 5,
+        name: "add_source_archives_and_lineage",
+        steps: &[
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS source_archives (
+                    id INTEGER PRIMARY KEY,
+                    zim_uuid TEXT NOT NULL UNIQUE,
+                    file_name TEXT NOT NULL,
+                    size_bytes INTEGER NOT NULL,
+                    zim_date TEXT,
+                    zim_language TEXT,
+                    checksum_md5 TEXT NOT NULL,
+                    first_seen_unix_ms INTEGER NOT NULL,
+                    last_seen_unix_ms INTEGER NOT NULL
+                );
+                "#,
+            ),
+            Step::AddColumn {
+                table: "ingestion_runs",
+                column: "source_archive_id",
+                definition: "INTEGER REFERENCES source_archives(id)",
+            },
+            Step::AddColumn {
+                table: "pages",
+                column: "written_run_id",
+                definition: "INTEGER",
+            },
+            Step::AddColumn {
+                table: "pages",
+                column: "source_archive_id",
+                definition: "INTEGER",
+            },
+            Step::AddColumn {
+                table: "pages",
+                column: "zim_entry_index",
+                definition: "INTEGER",
+            },
+            Step::Sql(
+                "CREATE INDEX IF NOT EXISTS idx_pages_source_archive ON pages(source_archive_id);",
+            ),
+        ],
+        foreign_keys_off: false,
+    },
+    // SQLite cannot drop the column-level UNIQUE(url), so pages is rebuilt keyed by
+    // (edition, url). Foreign keys stay off while the old table is dropped so child rows are not
+    // cascaded away; ids are copied verbatim. Databases written by builds that added `edition`
+    // before this migration existed keep their values: the column is ensured first and copied.
+    Migration {
+        version: 6,
+        name: "key_pages_by_edition",
+        steps: &[
+            Step::AddColumn {
+                table: "pages",
+                column: "edition",
+                definition: "TEXT NOT NULL DEFAULT 'en'",
+            },
+            Step::Sql(
+                r#"
+            CREATE TABLE pages_v6 (
+                id INTEGER PRIMARY KEY,
+                edition TEXT NOT NULL DEFAULT 'en',
+                url TEXT NOT NULL,
+                title TEXT NOT NULL,
+                namespace TEXT NOT NULL,
+                mime_type TEXT NOT NULL,
+                cluster_idx INTEGER,
+                blob_idx INTEGER,
+                redirect_url TEXT,
+                content_sha256 TEXT,
+                raw_html TEXT,
+                plain_text TEXT,
+                extraction_confidence REAL NOT NULL DEFAULT 0.0,
+                updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ','now')),
+                last_seen_run_id INTEGER,
+                deleted_at TEXT,
+                written_run_id INTEGER,
+                source_archive_id INTEGER,
+                zim_entry_index INTEGER,
+                UNIQUE(edition, url)
+            );
+            INSERT INTO pages_v6 (
+                id, edition, url, title, namespace, mime_type, cluster_idx, blob_idx,
+                redirect_url, content_sha256, raw_html, plain_text,
+                extraction_confidence, updated_at, last_seen_run_id, deleted_at,
+                written_run_id, source_archive_id, zim_entry_index
+            )
+            SELECT
+                id, edition, url, title, namespace, mime_type, cluster_idx, blob_idx,
+                redirect_url, content_sha256, raw_html, plain_text,
+                extraction_confidence, updated_at, last_seen_run_id, deleted_at,
+                written_run_id, source_archive_id, zim_entry_index
+            FROM pages;
+            DROP TABLE pages;
+            ALTER TABLE pages_v6 RENAME TO pages;
+            CREATE INDEX IF NOT EXISTS idx_pages_title ON pages(title);
+            CREATE INDEX IF NOT EXISTS idx_pages_updated_at ON pages(updated_at);
+            CREATE INDEX IF NOT EXISTS idx_pages_last_seen_run ON pages(last_seen_run_id);
+            CREATE INDEX IF NOT EXISTS idx_pages_source_archive ON pages(source_archive_id);
+            "#,
+            ),
+        ],
+        foreign_keys_off: true,
+    },
+    Migration {
+        version: 7,
+        name: "add_pending_redirects",
+        steps: &[Step::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS pending_redirects (
+                edition TEXT NOT NULL,
+                entry_index INTEGER NOT NULL,
+                PRIMARY KEY(edition, entry_index)
+            );
+            "#,
+        )],
+        foreign_keys_off: false,
+    },
+    Migration {
+        version: 8,
+        name: "add_ingestion_failures",
+        steps: &[Step::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS ingestion_failures (
+                id INTEGER PRIMARY KEY,
+                edition TEXT NOT NULL,
+                entry_index INTEGER NOT NULL,
+                url TEXT,
+                stage TEXT NOT NULL,
+                error_chain TEXT NOT NULL,
+                run_id INTEGER,
+                attempts INTEGER NOT NULL DEFAULT 1,
+                failed_unix_ms INTEGER NOT NULL,
+                UNIQUE(edition, entry_index)
+            );
+            "#,
+        )],
+        foreign_keys_off: false,
+    },
+    Migration {
+        version: 9,
+        name: "add_alias_trigrams",
+        // External-content FTS5 index over `lemma_aliases`, kept in step by triggers so every
+        // writer (page upserts, redirect resolution, cascades) maintains it.
+        steps: &[Step::Sql(
+            r#"
+            CREATE VIRTUAL TABLE IF NOT EXISTS alias_trigrams USING fts5(
+                normalized_alias,
+                content = 'lemma_aliases',
+                content_rowid = 'id',
+                tokenize = 'trigram'
+            );
+            INSERT INTO alias_trigrams(alias_trigrams) VALUES ('rebuild');
+            CREATE TRIGGER IF NOT EXISTS lemma_aliases_trigrams_insert
+            AFTER INSERT ON lemma_aliases BEGIN
+                INSERT INTO alias_trigrams(rowid, normalized_alias)
+                VALUES (new.id, new.normalized_alias);
+            END;
+            CREATE TRIGGER IF NOT EXISTS lemma_aliases_trigrams_delete
+            AFTER DELETE ON lemma_aliases BEGIN
+                INSERT INTO alias_trigrams(alias_trigrams, rowid, normalized_alias)
+                VALUES ('delete', old.id, old.normalized_alias);
+            END;
+            CREATE TRIGGER IF NOT EXISTS lemma_aliases_trigrams_update
+            AFTER UPDATE OF normalized_alias ON lemma_aliases BEGIN
+                INSERT INTO alias_trigrams(alias_trigrams, rowid, normalized_alias)
+                VALUES ('delete', old.id, old.normalized_alias);
+                INSERT INTO alias_trigrams(rowid, normalized_alias)
+                VALUES (new.id, new.normalized_alias);
+            END;
+            "#,
+        )],
+        foreign_keys_off: false,
+    },
+    Migration {
+        version: 10,
+        name: "add_definition_fts",
+        // One row per sense for reverse lookups, backfilled from the definitions already stored.
+        steps: &[Step::Sql(
+            r#"
+            CREATE VIRTUAL TABLE IF NOT EXISTS definition_fts
+            USING fts5(page_id UNINDEXED, language UNINDEXED, def_order UNINDEXED, definition_text);
+            DELETE FROM definition_fts;
+            INSERT INTO definition_fts(page_id, language, def_order, definition_text)
+            SELECT d.page_id, d.language, d.def_order, d.definition_text
+            FROM definitions d
+            JOIN pages p ON p.id = d.page_id AND p.deleted_at IS NULL;
+            "#,
+        )],
+        foreign_keys_off: false,
+    },
+    Migration {
+        version: 11,
+        name: "add_page_fts",
+        // Filled by writes only while `sqlite.enable_fts` is set; not backfilled, as before.
+        steps: &[Step::Sql(
+            r#"
+            CREATE VIRTUAL TABLE IF NOT EXISTS page_fts
+            USING fts5(page_id UNINDEXED, title, url, plain_text);
+            "#,
+        )],
+        foreign_keys_off: false,
+    },
+];
+
+// Postgres schemas created before migrations were tracked already hold some or all of this, so
+// these first migrations stay idempotent (`IF NOT EXISTS`) and are simply replayed on them.
+pub const POSTGRES_MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "create_core_tables",
+        steps: &[Step::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS {schema}.source_archives (
+                id BIGSERIAL PRIMARY KEY,
+                zim_uuid TEXT NOT NULL UNIQUE,
+                file_name TEXT NOT NULL,
+                size_bytes BIGINT NOT NULL,
+                zim_date TEXT,
+                zim_language TEXT,
+                checksum_md5 TEXT NOT NULL,
+                first_seen_unix_ms BIGINT NOT NULL,
+                last_seen_unix_ms BIGINT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS {schema}.pages (
+                id BIGSERIAL PRIMARY KEY,
+                edition TEXT NOT NULL DEFAULT 'en',
+                url TEXT NOT NULL,
+                title TEXT NOT NULL,
+                namespace TEXT NOT NULL,
+                mime_type TEXT NOT NULL,
+                cluster_idx BIGINT,
+                blob_idx BIGINT,
+                redirect_url TEXT,
+                content_sha256 TEXT,
+                raw_html TEXT,
+                plain_text TEXT,
+                extraction_confidence DOUBLE PRECISION NOT NULL DEFAULT 0.0,
+                last_seen_run_id BIGINT,
+                written_run_id BIGINT,
+                source_archive_id BIGINT,
+                zim_entry_index BIGINT,
+                deleted_at TEXT,
+                updated_at TEXT NOT NULL DEFAULT to_char(timezone('UTC', now()), 'YYYY-MM-DD"T"HH24:MI:SS.MS"Z"')
+            );
+
+            CREATE TABLE IF NOT EXISTS {schema}.definitions (
+                id BIGSERIAL PRIMARY KEY,
+                page_id BIGINT NOT NULL REFERENCES {schema}.pages(id) ON DELETE CASCADE,
+                language TEXT NOT NULL,
+                def_order BIGINT NOT NULL,
+                definition_text TEXT NOT NULL,
+                normalized_text TEXT NOT NULL DEFAULT '',
+                confidence DOUBLE PRECISION NOT NULL DEFAULT 0.0,
+                UNIQUE(page_id, language, def_order)
+            );
+
+            CREATE TABLE IF NOT EXISTS {schema}.ingestion_runs (
+                id BIGSERIAL PRIMARY KEY,
+                started_unix_ms BIGINT NOT NULL,
+                finished_unix_ms BIGINT NOT NULL,
+                scanned_entries BIGINT NOT NULL,
+                filtered_entries BIGINT NOT NULL,
+                ingested_pages BIGINT NOT NULL,
+                extracted_definitions BIGINT NOT NULL,
+                extracted_relations BIGINT NOT NULL DEFAULT 0,
+                extraction_errors BIGINT NOT NULL,
+                unchanged_pages BIGINT NOT NULL DEFAULT 0,
+                swept_pages BIGINT NOT NULL DEFAULT 0,
+                source_archive_id BIGINT REFERENCES {schema}.source_archives(id)
+            );
+
+            CREATE TABLE IF NOT EXISTS {schema}.relations (
+                id BIGSERIAL PRIMARY KEY,
+                page_id BIGINT NOT NULL REFERENCES {schema}.pages(id) ON DELETE CASCADE,
+                language TEXT NOT NULL,
+                relation_type TEXT NOT NULL,
+                rel_order BIGINT NOT NULL,
+                source_text TEXT NOT NULL,
+                target_term TEXT NOT NULL,
+                normalized_target TEXT NOT NULL,
+                confidence DOUBLE PRECISION NOT NULL DEFAULT 0.0,
+                UNIQUE(page_id, language, relation_type, rel_order, target_term)
+            );
+
+            CREATE TABLE IF NOT EXISTS {schema}.lemma_aliases (
+                id BIGSERIAL PRIMARY KEY,
+                page_id BIGINT NOT NULL REFERENCES {schema}.pages(id) ON DELETE CASCADE,
+                language TEXT,
+                alias TEXT NOT NULL,
+                normalized_alias TEXT NOT NULL,
+                source TEXT NOT NULL,
+                UNIQUE(page_id, language, alias, source)
+            );
+
+            CREATE TABLE IF NOT EXISTS {schema}.pending_redirects (
+                edition TEXT NOT NULL,
+                entry_index BIGINT NOT NULL,
+                PRIMARY KEY(edition, entry_index)
+            );
+
+            CREATE TABLE IF NOT EXISTS {schema}.ingestion_failures (
+                id BIGSERIAL PRIMARY KEY,
+                edition TEXT NOT NULL,
+                entry_index BIGINT NOT NULL,
+                url TEXT,
+                stage TEXT NOT NULL,
+                error_chain TEXT NOT NULL,
+                run_id BIGINT,
+                attempts BIGINT NOT NULL DEFAULT 1,
+                failed_unix_ms BIGINT NOT NULL,
+                UNIQUE(edition, entry_index)
+            );
+
+            CREATE TABLE IF NOT EXISTS {schema}.ingestion_checkpoints (
+                name TEXT PRIMARY KEY,
+                last_processed_index BIGINT NOT NULL,
+                updated_unix_ms BIGINT NOT NULL,
+                ingested_pages BIGINT NOT NULL,
+                extracted_definitions BIGINT NOT NULL,
+                extracted_relations BIGINT NOT NULL,
+                metadata_json TEXT NOT NULL DEFAULT '{}'
+            );
+
+            CREATE TABLE IF NOT EXISTS {schema}.reindex_state (
+                name TEXT PRIMARY KEY,
+                last_updated_at TEXT NOT NULL DEFAULT ''
+            );
+
+            CREATE TABLE IF NOT EXISTS {schema}.hot_lookup (
+                language TEXT NOT NULL,
+                normalized_alias TEXT NOT NULL,
+                alias TEXT NOT NULL,
+                page_id BIGINT NOT NULL REFERENCES {schema}.pages(id) ON DELETE CASCADE,
+                title TEXT NOT NULL,
+                url TEXT NOT NULL,
+                primary_definition TEXT,
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+                CONSTRAINT hot_lookup_unique UNIQUE(language, normalized_alias, page_id)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_pages_title ON {schema}.pages(title);
+            CREATE INDEX IF NOT EXISTS idx_pages_updated_at ON {schema}.pages(updated_at);
+            CREATE INDEX IF NOT EXISTS idx_definitions_page ON {schema}.definitions(page_id);
+            CREATE INDEX IF NOT EXISTS idx_definitions_language ON {schema}.definitions(language);
+            CREATE INDEX IF NOT EXISTS idx_relations_page ON {schema}.relations(page_id);
+            CREATE INDEX IF NOT EXISTS idx_relations_type ON {schema}.relations(relation_type);
+            CREATE INDEX IF NOT EXISTS idx_aliases_page ON {schema}.lemma_aliases(page_id);
+            CREATE INDEX IF NOT EXISTS idx_aliases_norm ON {schema}.lemma_aliases(normalized_alias);
+            CREATE INDEX IF NOT EXISTS idx_hot_lookup_lang_norm ON {schema}.hot_lookup(language, normalized_alias);
+            CREATE INDEX IF NOT EXISTS idx_hot_lookup_lang_page ON {schema}.hot_lookup(language, page_id);
+            CREATE INDEX IF NOT EXISTS idx_aliases_hot_norm_page
+                ON {schema}.lemma_aliases(normalized_alias, page_id)
+                WHERE language IN ('English', 'Spanish', 'German', 'French');
+            CREATE INDEX IF NOT EXISTS idx_definitions_hot_page_order
+                ON {schema}.definitions(page_id, def_order)
+                WHERE language IN ('English', 'Spanish', 'German', 'French');
+            "#,
+        )],
+        foreign_keys_off: false,
+    },
+    Migration {
+        version: 2,
+        name: "add_lineage_and_edition_columns",
+        steps: &[
+            Step::AddColumn {
+                table: "pages",
+                column: "last_seen_run_id",
+                definition: "BIGINT",
+            },
+            Step::AddColumn {
+                table: "pages",
+                column: "deleted_at",
+                definition: "TEXT",
+            },
+            Step::AddColumn {
+                table: "ingestion_runs",
+                column: "unchanged_pages",
+                definition: "BIGINT NOT NULL DEFAULT 0",
+            },
+            Step::AddColumn {
+                table: "ingestion_runs",
+                column: "swept_pages",
+                definition: "BIGINT NOT NULL DEFAULT 0",
+            },
+            Step::AddColumn {
+                table: "ingestion_runs",
+                column: "source_archive_id",
+                definition: "BIGINT REFERENCES {schema}.source_archives(id)",
+            },
+            Step::AddColumn {
+                table: "pages",
+                column: "written_run_id",
+                definition: "BIGINT",
+            },
+            Step::AddColumn {
+                table: "pages",
+                column: "source_archive_id",
+                definition: "BIGINT",
+            },
+            Step::AddColumn {
+                table: "pages",
+                column: "zim_entry_index",
+                definition: "BIGINT",
+            },
+            Step::AddColumn {
+                table: "pages",
+                column: "edition",
+                definition: "TEXT NOT NULL DEFAULT 'en'",
+            },
+            Step::Sql(
+                r#"
+                CREATE INDEX IF NOT EXISTS idx_pages_last_seen_run ON {schema}.pages(last_seen_run_id);
+                CREATE INDEX IF NOT EXISTS idx_pages_source_archive ON {schema}.pages(source_archive_id);
+                ALTER TABLE {schema}.pages DROP CONSTRAINT IF EXISTS pages_url_key;
+                CREATE UNIQUE INDEX IF NOT EXISTS idx_pages_edition_url ON {schema}.pages(edition, url);
+                "#,
+            ),
+        ],
+        foreign_keys_off: false,
+    },
+    Migration {
+        version: 3,
+        name: "hash_long_text_indexes",
+        steps: &[Step::Sql(
+            r#"
+            DROP INDEX IF EXISTS {schema}.idx_definitions_norm;
+            DROP INDEX IF EXISTS {schema}.idx_relations_target;
+            CREATE INDEX IF NOT EXISTS idx_definitions_norm ON {schema}.definitions ((md5(normalized_text)));
+            CREATE INDEX IF NOT EXISTS idx_relations_target ON {schema}.relations ((md5(normalized_target)));
+            "#,
+        )],
+        foreign_keys_off: false,
+    },
+    Migration {
+        version: 4,
+        name: "add_alias_trigram_index",
+        // GiST rather than GIN so fuzzy lookups can order candidates by trigram distance. The
+        // extension is optional: roles that may not create it (or servers without contrib) get
+        // no index, and fuzzy suggestions fall back to prefix matches.
+        steps: &[Step::Sql(
+            r#"
+            DO $$
+            BEGIN
+                IF NOT EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'pg_trgm')
+                   AND EXISTS (SELECT 1 FROM pg_available_extensions WHERE name = 'pg_trgm') THEN
+                    BEGIN
+                        CREATE EXTENSION IF NOT EXISTS pg_trgm;
+                    EXCEPTION WHEN insufficient_privilege OR undefined_file THEN
+                        RAISE WARNING 'pg_trgm could not be created: %', SQLERRM;
+                    END;
+                END IF;
+                IF EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'pg_trgm') THEN
+                    CREATE INDEX IF NOT EXISTS idx_aliases_norm_trgm
+                        ON {schema}.lemma_aliases USING GIST (normalized_alias gist_trgm_ops);
+                END IF;
+            END
+            $$;
+            "#,
+        )],
+        foreign_keys_off: false,
+    },
+    Migration {
+        version: 5,
+        name: "add_page_language_fts",
+        // One stemmed vector per page and definition language. The backfill spells out the
+        // language-to-configuration map as `extractor::TEXT_SEARCH_CONFIGS` stood when this
+        // migration was written; page upserts keep rows current from then on.
+        steps: &[Step::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS {schema}.page_language_fts (
+                page_id BIGINT NOT NULL REFERENCES {schema}.pages(id) ON DELETE CASCADE,
+                language TEXT NOT NULL,
+                search_vector tsvector NOT NULL,
+                PRIMARY KEY (page_id, language)
+            );
+            CREATE INDEX IF NOT EXISTS idx_page_language_fts_vector
+                ON {schema}.page_language_fts USING GIN (search_vector);
+            DELETE FROM {schema}.page_language_fts;
+            INSERT INTO {schema}.page_language_fts(page_id, language, search_vector)
+            SELECT d.page_id, d.language,
+                   to_tsvector(COALESCE(m.config, 'simple')::regconfig,
+                               string_agg(d.definition_text, ' ' ORDER BY d.def_order))
+            FROM {schema}.definitions d
+            JOIN {schema}.pages p ON p.id = d.page_id AND p.deleted_at IS NULL
+            LEFT JOIN (VALUES
+                ('English', 'english'), ('French', 'french'), ('Spanish', 'spanish'),
+                ('German', 'german'), ('Italian', 'italian'), ('Portuguese', 'portuguese'),
+                ('Dutch', 'dutch'), ('Swedish', 'swedish'), ('Norwegian', 'norwegian'),
+                ('Danish', 'danish'), ('Finnish', 'finnish'), ('Russian', 'russian'),
+                ('Turkish', 'turkish')
+            ) AS m(language, config) ON m.language = d.language
+            GROUP BY d.page_id, d.language, m.config;
+            "#,
+        )],
+        foreign_keys_off: false,
+    },
+    Migration {
+        version: 6,
+        name: "add_definition_fts",
+        // One stemmed vector per sense for reverse lookups, backfilled with the same language
+        // map as version 5.
+        steps: &[Step::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS {schema}.definition_fts (
+                definition_id BIGINT PRIMARY KEY REFERENCES {schema}.definitions(id) ON DELETE CASCADE,
+                page_id BIGINT NOT NULL REFERENCES {schema}.pages(id) ON DELETE CASCADE,
+                language TEXT NOT NULL,
+                search_vector tsvector NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_definition_fts_vector
+                ON {schema}.definition_fts USING GIN (search_vector);
+            CREATE INDEX IF NOT EXISTS idx_definition_fts_page ON {schema}.definition_fts(page_id);
+            DELETE FROM {schema}.definition_fts;
+            INSERT INTO {schema}.definition_fts(definition_id, page_id, language, search_vector)
+            SELECT d.id, d.page_id, d.language,
+                   to_tsvector(COALESCE(m.config, 'simple')::regconfig, d.definition_text)
+            FROM {schema}.definitions d
+            JOIN {schema}.pages p ON p.id = d.page_id AND p.deleted_at IS NULL
+            LEFT JOIN (VALUES
+                ('English', 'english'), ('French', 'french'), ('Spanish', 'spanish'),
+                ('German', 'german'), ('Italian', 'italian'), ('Portuguese', 'portuguese'),
+                ('Dutch', 'dutch'), ('Swedish', 'swedish'), ('Norwegian', 'norwegian'),
+                ('Danish', 'danish'), ('Finnish', 'finnish'), ('Russian', 'russian'),
+                ('Turkish', 'turkish')
+            ) AS m(language, config) ON m.language = d.language;
+            "#,
+        )],
+        foreign_keys_off: false,
+    },
+    Migration {
+        version: 7,
+        name: "add_page_fts",
+        // Filled by writes only while `postgres.enable_fts` is set; not backfilled, as before.
+        steps: &[Step::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS {schema}.page_fts (
+                page_id BIGINT PRIMARY KEY REFERENCES {schema}.pages(id) ON DELETE CASCADE,
+                title TEXT NOT NULL,
+                url TEXT NOT NULL,
+                plain_text TEXT NOT NULL,
+                search_vector tsvector GENERATED ALWAYS AS (
+                    to_tsvector('simple', COALESCE(title, '') || ' ' || COALESCE(plain_text, ''))
+                ) STORED
+            );
+            CREATE INDEX IF NOT EXISTS idx_page_fts_vector
+                ON {schema}.page_fts USING GIN (search_vector);
+            "#,
+        )],
+        foreign_keys_off: false,
+    },
+    Migration {
+        version: 8,
+        name: "add_alias_prefix_index",
+        // Prefix ranges compare `normalized_alias COLLATE "C"` so they follow byte order as on
+        // SQLite; `idx_aliases_norm` uses the database collation and cannot serve them.
+        steps: &[Step::Sql(
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_aliases_norm_prefix
+                ON {schema}.lemma_aliases ((normalized_alias COLLATE "C"));
+            "#,
+        )],
+        foreign_keys_off: false,
+    },
+];
+
+/// Version a fully migrated SQLite database reports in `PRAGMA user_version`.
+pub const SQLITE_SCHEMA_VERSION: i64 = SQLITE_MIGRATIONS[SQLITE_MIGRATIONS.len() - 1].version;
+
+const SQLITE_MIGRATIONS_TABLE: &str = r#"
+    CREATE TABLE IF NOT EXISTS schema_migrations (
+        version INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        checksum TEXT NOT NULL,
+        applied_unix_ms INTEGER NOT NULL,
+        execution_ms INTEGER NOT NULL
+    );
+"#;
+
+const POSTGRES_MIGRATIONS_TABLE: &str = r#"
+    CREATE TABLE IF NOT EXISTS {schema}.schema_migrations (
+        version BIGINT PRIMARY KEY,
+        name TEXT NOT NULL,
+        checksum TEXT NOT NULL,
+        applied_unix_ms BIGINT NOT NULL,
+        execution_ms BIGINT NOT NULL
+    );
+"#;
+
+/// Compares the migrations in this build with those recorded in the database.
+fn migration_statuses(
+    migrations: &[Migration],
+    applied: &[AppliedMigration],
+) -> Vec<MigrationStatus> {
+    let mut statuses = migrations
+        .iter()
+        .map(|migration| {
+            let checksum = migration.checksum();
+            let recorded = applied
+                .iter()
+                .find(|record| record.version == migration.version);
+            let state = match recorded {
+                None => MigrationState::Pending,
+                Some(record) if record.checksum == checksum => MigrationState::Applied,
+                Some(_) => MigrationState::Modified,
+            };
+            MigrationStatus {
+                version: migration.version,
+                name: migration.name.to_owned(),
+                checksum,
+                state,
+                applied_unix_ms: recorded.map(|record| record.applied_unix_ms),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    statuses.extend(
+        applied
+            .iter()
+            .filter(|record| {
+                !migrations
+                    .iter()
+                    .any(|migration| migration.version == record.version)
+            })
+            .map(|record| MigrationStatus {
+                version: record.version,
+                name: record.name.clone(),
+                checksum: record.checksum.clone(),
+                state: MigrationState::Unknown,
+                applied_unix_ms: Some(record.applied_unix_ms),
+            }),
+    );
+    statuses.sort_by_key(|status| status.version);
+    statuses
+}
+
+fn ensure_consistent(statuses: &[MigrationStatus]) -> Result<()> {
+    for status in statuses {
+        match status.state {
+            MigrationState::Modified => anyhow::bail!(
+                "schema migration {} ({}) changed after it was applied; add a new migration instead",
+                status.version,
+                status.name
+            ),
+            MigrationState::Unknown => anyhow::bail!(
+                "database has schema migration {} ({}) that this build does not know; upgrade zimrs",
+                status.version,
+                status.name
+            ),
+            MigrationState::Applied | MigrationState::Pending => {}
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn sqlite_status(conn: &Connection) -> Result<Vec<MigrationStatus>> {
+    let (applied, _) = sqlite_applied(conn)?;
+    Ok(migration_statuses(SQLITE_MIGRATIONS, &applied))
+}
+
+/// Applies pending SQLite migrations in order, each in its own transaction, and returns them.
+/// With `dry_run` nothing is written and the returned migrations are the ones that would run.
+///
+/// Databases from before `schema_migrations` existed are adopted: every migration up to their
+/// `PRAGMA user_version` is recorded as applied without running it again.
+pub(crate) fn sqlite_migrate(conn: &Connection, dry_run: bool) -> Result<Vec<MigrationStatus>> {
+    let (applied, adopted) = sqlite_applied(conn)?;
+    let statuses = migration_statuses(SQLITE_MIGRATIONS, &applied);
+    ensure_consistent(&statuses)?;
+    let mut pending = statuses
+        .into_iter()
+        .filter(|status| status.state == MigrationState::Pending)
+        .collect::<Vec<_>>();
+    if dry_run {
+        return Ok(pending);
+    }
+
+    conn.execute_batch(SQLITE_MIGRATIONS_TABLE)?;
+    if adopted {
+        for record in &applied {
+            conn.execute(
+                "INSERT OR IGNORE INTO schema_migrations(version, name, checksum, applied_unix_ms, execution_ms) VALUES (?1, ?2, ?3, ?4, 0)",
+                params![record.version, record.name, record.checksum, record.applied_unix_ms],
+            )?;
+        }
+        info!(
+            adopted_version = applied.len(),
+            "recorded existing sqlite schema in schema_migrations"
+        );
+    }
+
+    for status in &mut pending {
+        let migration = SQLITE_MIGRATIONS
+            .iter()
+            .find(|migration| migration.version == status.version)
+            .expect("pending status comes from SQLITE_MIGRATIONS");
+        let applied_unix_ms = apply_sqlite_migration(conn, migration).with_context(|| {
+            format!(
+                "sqlite migration {} ({}) failed",
+                migration.version, migration.name
+            )
+        })?;
+        status.state = MigrationState::Applied;
+        status.applied_unix_ms = Some(applied_unix_ms);
+    }
+
+    Ok(pending)
+}
+
+fn sqlite_applied(conn: &Connection) -> Result<(Vec<AppliedMigration>, bool)> {
+    let has_table = conn
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'schema_migrations'",
+            [],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some();
+
+    if has_table {
+        let mut stmt = conn.prepare(
+            "SELECT version, name, checksum, applied_unix_ms FROM schema_migrations ORDER BY version",
+        )?;
+        let applied = stmt
+            .query_map([], |row| {
+                Ok(AppliedMigration {
+                    version: row.get(0)?,
+                    name: row.get(1)?,
+                    checksum: row.get(2)?,
+                    applied_unix_ms: row.get(3)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        if !applied.is_empty() {
+            return Ok((applied, false));
+        }
+    }
+
+    let user_version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if user_version > SQLITE_SCHEMA_VERSION {
+        anyhow::bail!(
+            "sqlite schema version {user_version} is newer than this build supports ({SQLITE_SCHEMA_VERSION})"
+        );
+    }
+    let now = unix_now_ms();
+    let adopted = SQLITE_MIGRATIONS
+        .iter()
+        .take_while(|migration| migration.version <= user_version)
+        .map(|migration| AppliedMigration {
+            version: migration.version,
+            name: migration.name.to_owned(),
+            checksum: migration.checksum(),
+            applied_unix_ms: now,
+        })
+        .collect::<Vec<_>>();
+    let is_adopted = !adopted.is_empty();
+    Ok((adopted, is_adopted))
+}
+
+/// Returns when the migration was recorded as applied.
+fn apply_sqlite_migration(conn: &Connection, migration: &Migration) -> Result<i64> {
+    let started = Instant::now();
+    let applied_unix_ms = unix_now_ms();
+    if migration.foreign_keys_off {
+        conn.execute_batch("PRAGMA foreign_keys = OFF;")?;
+    }
+
+    let result = (|| -> Result<()> {
+        let tx = conn.unchecked_transaction()?;
+        for step in migration.steps {
+            match step {
+                Step::Sql(sql) => tx.execute_batch(sql)?,
+                Step::AddColumn {
+                    table,
+                    column,
+                    definition,
+                } => ensure_sqlite_column(&tx, table, column, definition)?,
+            }
+        }
+        tx.execute(
+            "INSERT INTO schema_migrations(version, name, checksum, applied_unix_ms, execution_ms) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                migration.version,
+                migration.name,
+                migration.checksum(),
+                applied_unix_ms,
+                started.elapsed().as_millis() as i64
+            ],
+        )?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+        Ok(())
+    })();
+
+    if migration.foreign_keys_off {
+        conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+    }
+    result?;
+
+    info!(
+        version = migration.version,
+        name = migration.name,
+        elapsed_ms = started.elapsed().as_millis(),
+        "applied sqlite schema migration"
+    );
+    Ok(applied_unix_ms)
+}
+
+fn ensure_sqlite_column(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({table})"))?;
+    let mut rows = stmt.query([])?;
+
+    while let Some(row) = rows.next()? {
+        let existing_name: String = row.get(1)?;
+        if existing_name == column {
+            return Ok(());
+        }
+    }
+
+    conn.execute(
+        &format!("ALTER TABLE {table} ADD COLUMN {column} {definition}"),
+        [],
+    )?;
+
+    Ok(())
+}
+
+pub(crate) fn pg_status(
+    client: &mut impl GenericClient,
+    schema: &str,
+) -> Result<Vec<MigrationStatus>> {
+    let applied = pg_applied(client, schema)?;
+    Ok(migration_statuses(POSTGRES_MIGRATIONS, &applied))
+}
+
+/// Applies pending Postgres migrations in one transaction under an advisory lock on the
+/// schema, so shards starting together do not race, and returns them. With `dry_run` nothing
+/// is written.
+pub(crate) fn pg_migrate(
+    client: &mut postgres::Client,
+    schema: &str,
+    dry_run: bool,
+) -> Result<Vec<MigrationStatus>> {
+    if dry_run {
+        let statuses = pg_status(client, schema)?;
+        ensure_consistent(&statuses)?;
+        return Ok(statuses
+            .into_iter()
+            .filter(|status| status.state == MigrationState::Pending)
+            .collect());
+    }
+
+    let schema_ident = pg_ident(schema);
+    let mut tx = client.transaction()?;
+    tx.execute("SELECT pg_advisory_xact_lock(hashtext($1))", &[&schema])?;
+    tx.batch_execute(&POSTGRES_MIGRATIONS_TABLE.replace("{schema}", &schema_ident))?;
+
+    let applied = pg_applied(&mut tx, schema)?;
+    let statuses = migration_statuses(POSTGRES_MIGRATIONS, &applied);
+    ensure_consistent(&statuses)?;
+    let mut pending = statuses
+        .into_iter()
+        .filter(|status| status.state == MigrationState::Pending)
+        .collect::<Vec<_>>();
+
+    for status in &mut pending {
+        let migration = POSTGRES_MIGRATIONS
+            .iter()
+            .find(|migration| migration.version == status.version)
+            .expect("pending status comes from POSTGRES_MIGRATIONS");
+        let started = Instant::now();
+        let applied_unix_ms = unix_now_ms();
+        for step in migration.steps {
+            let sql = match step {
+                Step::Sql(sql) => sql.replace("{schema}", &schema_ident),
+                Step::AddColumn {
+                    table,
+                    column,
+                    definition,
+                } => format!(
+                    "ALTER TABLE {schema_ident}.{table} ADD COLUMN IF NOT EXISTS {column} {}",
+                    definition.replace("{schema}", &schema_ident)
+                ),
+            };
+            tx.batch_execute(&sql).with_context(|| {
+                format!(
+                    "postgres migration {} ({}) failed",
+                    migration.version, migration.name
+                )
+            })?;
+        }
+        tx.execute(
+            &format!(
+                "INSERT INTO {schema_ident}.schema_migrations(version, name, checksum, applied_unix_ms, execution_ms) VALUES ($1, $2, $3, $4, $5)"
+            ),
+            &[
+                &migration.version,
+                &migration.name,
+                &migration.checksum(),
+                &applied_unix_ms,
+                &(started.elapsed().as_millis() as i64),
+            ],
+        )?;
+        info!(
+            schema,
+            version = migration.version,
+            name = migration.name,
+            elapsed_ms = started.elapsed().as_millis(),
+            "applied postgres schema migration"
+        );
+        status.state = MigrationState::Applied;
+        status.applied_unix_ms = Some(applied_unix_ms);
+    }
+
+    tx.commit()?;
+    Ok(pending)
+}
+
+fn pg_applied(client: &mut impl GenericClient, schema: &str) -> Result<Vec<AppliedMigration>> {
+    let exists = client
+        .query_one(
+            "SELECT to_regclass(format('%I.schema_migrations', $1::text)) IS NOT NULL",
+            &[&schema],
+        )?
+        .get::<_, bool>(0);
+    if !exists {
+        return Ok(Vec::new());
+    }
+
+    let rows = client.query(
+        &format!(
+            "SELECT version, name, checksum, applied_unix_ms FROM {}.schema_migrations ORDER BY version",
+            pg_ident(schema)
+        ),
+        &[],
+    )?;
+    Ok(rows
+        .into_iter()
+        .map(|row| AppliedMigration {
+            version: row.get(0),
+            name: row.get(1),
+            checksum: row.get(2),
+            applied_unix_ms: row.get(3),
+        })
+        .collect())
+}
+
+/// Renders statuses as a fixed-width table for `migrate status`.
+pub fn render_status_table(statuses: &[MigrationStatus]) -> String {
+    let mut out = format!(
+        "{:>7}  {:<36}{:<10}{:<14}{}\n",
+        "version", "name", "state", "checksum", "applied_unix_ms"
+    );
+    for status in statuses {
+        out.push_str(&format!(
+            "{:>7}  {:<36}{:<10}{:<14}{}\n",
+            status.version,
+            status.name,
+            status.state.as_str(),
+            &status.checksum[..status.checksum.len().min(12)],
+            status
+                .applied_unix_ms
+                .map_or_else(|| "-".to_owned(), |ms| ms.to_string())
+        ));
+    }
+    out
+}
+
+fn unix_now_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |now| now.as_millis() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{build_pg_config, test_postgres_config};
+    use tempfile::tempdir;
+
+    fn table_exists(conn: &Connection, name: &str) -> bool {
+        conn.query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            params![name],
+            |_| Ok(()),
+        )
+        .optional()
+        .expect("query sqlite_master")
+        .is_some()
+    }
+
+    /// Runs migrations the way builds from before `schema_migrations` did, without recording them.
+    fn apply_untracked(conn: &Connection, migrations: &[Migration]) {
+        for migration in migrations {
+            for step in migration.steps {
+                match step {
+                    Step::Sql(sql) => conn.execute_batch(sql).expect("legacy sql"),
+                    Step::AddColumn {
+                        table,
+                        column,
+                        definition,
+                    } => ensure_sqlite_column(conn, table, column, definition)
+                        .expect("legacy column"),
+                }
+            }
+        }
+        conn.pragma_update(None, "user_version", migrations.len() as i64)
+            .expect("user_version");
+    }
+
+    #[test]
+    fn versions_are_contiguous_from_one() {
+        for migrations in [SQLITE_MIGRATIONS, POSTGRES_MIGRATIONS] {
+            for (position, migration) in migrations.iter().enumerate() {
+                assert_eq!(migration.version, position as i64 + 1, "{}", migration.name);
+            }
+        }
+    }
+
+    #[test]
+    fn sqlite_applies_every_migration_from_version_zero() {
+        let workdir = tempdir().expect("tempdir");
+        let conn = Connection::open(workdir.path().join("fresh.sqlite")).expect("open db");
+
+        let planned = sqlite_migrate(&conn, true).expect("dry run");
+        assert_eq!(planned.len(), SQLITE_MIGRATIONS.len());
+        assert!(!table_exists(&conn, "schema_migrations"));
+        assert!(!table_exists(&conn, "pages"));
+
+        let applied = sqlite_migrate(&conn, false).expect("migrate");
+        assert_eq!(applied.len(), SQLITE_MIGRATIONS.len());
+        let version: i64 = conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .expect("user_version");
+        assert_eq!(version, SQLITE_SCHEMA_VERSION);
+        for table in [
+            "pages",
+            "ingestion_failures",
+            "pending_redirects",
+            "alias_trigrams",
+            "definition_fts",
+            "page_fts",
+        ] {
+            assert!(table_exists(&conn, table), "{table}");
+        }
+
+        let statuses = sqlite_status(&conn).expect("status");
+        assert!(
+            statuses
+                .iter()
+                .all(|status| status.state == MigrationState::Applied)
+        );
+        assert_eq!(statuses[5].checksum, SQLITE_MIGRATIONS[5].checksum());
+        assert!(sqlite_migrate(&conn, false).expect("rerun").is_empty());
+
+        conn.execute(
+            "UPDATE schema_migrations SET checksum = 'edited' WHERE version = 3",
+            [],
+        )
+        .expect("tamper");
+        let statuses = sqlite_status(&conn).expect("status");
+        assert_eq!(statuses[2].state, MigrationState::Modified);
+        let err = sqlite_migrate(&conn, false).expect_err("modified migration");
+        assert!(err.to_string().contains("add_lookup_indexes"));
+
+        conn.execute(
+            "INSERT INTO schema_migrations VALUES (99, 'from_the_future', 'x', 0, 0)",
+            [],
+        )
+        .expect("insert future migration");
+        assert_eq!(
+            sqlite_status(&conn)
+                .expect("status")
+                .last()
+                .map(|s| s.state),
+            Some(MigrationState::Unknown)
+        );
+    }
+
+    #[test]
+    fn sqlite_adopts_databases_tracked_only_by_user_version() {
+        let workdir = tempdir().expect("tempdir");
+        let conn = Connection::open(workdir.path().join("legacy.sqlite")).expect("open db");
+        apply_untracked(&conn, &SQLITE_MIGRATIONS[..4]);
+
+        let pending = sqlite_migrate(&conn, true).expect("dry run");
+        assert_eq!(
+            pending
+                .iter()
+                .map(|status| status.version)
+                .collect::<Vec<_>>(),
+            vec![5, 6, 7, 8, 9, 10, 11]
+        );
+        assert!(!table_exists(&conn, "schema_migrations"));
+
+        sqlite_migrate(&conn, false).expect("migrate");
+        let recorded: i64 = conn
+            .query_row("SELECT COUNT(*) FROM schema_migrations", [], |row| {
+                row.get(0)
+            })
+            .expect("count");
+        assert_eq!(recorded, SQLITE_SCHEMA_VERSION);
+        assert!(table_exists(&conn, "source_archives"));
+    }
+
+    #[test]
+    fn sqlite_edition_rekey_keeps_an_existing_edition_column() {
+        let workdir = tempdir().expect("tempdir");
+        let conn = Connection::open(workdir.path().join("edition.sqlite")).expect("open db");
+        apply_untracked(&conn, &SQLITE_MIGRATIONS[..5]);
+        conn.execute_batch(
+            r#"
+            ALTER TABLE pages ADD COLUMN edition TEXT NOT NULL DEFAULT 'en';
+            INSERT INTO pages(id, url, title, namespace, mime_type, edition)
+            VALUES (1, 'chat', 'chat', 'A', 'text/html', 'fr'),
+                   (2, 'cat', 'cat', 'A', 'text/html', 'en');
+            "#,
+        )
+        .expect("seed pages");
+
+        sqlite_migrate(&conn, false).expect("migrate");
+        let editions: Vec<(i64, String)> = conn
+            .prepare("SELECT id, edition FROM pages ORDER BY id")
+            .expect("prepare")
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .expect("query")
+            .collect::<rusqlite::Result<_>>()
+            .expect("rows");
+        assert_eq!(editions, vec![(1, "fr".to_owned()), (2, "en".to_owned())]);
+        conn.execute(
+            "INSERT INTO pages(url, title, namespace, mime_type, edition) \
+             VALUES ('chat', 'chat', 'A', 'text/html', 'en')",
+            [],
+        )
+        .expect("same url in another edition");
+    }
+
+    #[test]
+    fn sqlite_definition_fts_migration_backfills_live_pages() {
+        let workdir = tempdir().expect("tempdir");
+        let conn = Connection::open(workdir.path().join("backfill.sqlite")).expect("open db");
+        sqlite_migrate(&conn, false).expect("migrate");
+        conn.execute_batch(
+            r#"
+            INSERT INTO pages(id, url, title, namespace, mime_type, updated_at)
+            VALUES (1, 'cat', 'cat', 'A', 'text/html', ''),
+                   (2, 'dog', 'dog', 'A', 'text/html', '');
+            UPDATE pages SET deleted_at = 'now' WHERE id = 2;
+            INSERT INTO definitions(page_id, language, def_order, definition_text)
+            VALUES (1, 'English', 0, 'a small feline'), (1, 'English', 1, 'a spiteful woman'),
+                   (2, 'English', 0, 'a domesticated canine');
+            DROP TABLE definition_fts;
+            DELETE FROM schema_migrations WHERE version = 10;
+            PRAGMA user_version = 9;
+            "#,
+        )
+        .expect("seed pages");
+
+        let applied = sqlite_migrate(&conn, false).expect("replay");
+        assert_eq!(
+            applied
+                .iter()
+                .map(|status| status.version)
+                .collect::<Vec<_>>(),
+            vec![10]
+        );
+        let matches: Vec<(i64, i64)> = conn
+            .prepare(
+                "SELECT page_id, def_order FROM definition_fts \
+                 WHERE definition_fts MATCH 'feline OR canine' ORDER BY page_id",
+            )
+            .expect("prepare")
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .expect("query")
+            .collect::<rusqlite::Result<_>>()
+            .expect("rows");
+        assert_eq!(matches, vec![(1, 0)]);
+    }
+
+    #[test]
+    #[ignore = "needs ZIMRS_TEST_PG_HOST"]
+    fn postgres_applies_every_migration_from_version_zero() {
+        let schema = format!("zimrs_migrations_{}", std::process::id());
+        let config = test_postgres_config(&schema);
+        let mut client = build_pg_config(&config.postgres)
+            .expect("pg config")
+            .connect(postgres::NoTls)
+            .expect("connect to test postgres");
+
+        let schema_ident = pg_ident(&schema);
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema_ident} CASCADE; CREATE SCHEMA {schema_ident};"
+            ))
+            .expect("create schema");
+
+        let planned = pg_migrate(&mut client, &schema, true).expect("dry run");
+        assert_eq!(planned.len(), POSTGRES_MIGRATIONS.len());
+        assert!(
+            pg_applied(&mut client, &schema)
+                .expect("applied")
+                .is_empty()
+        );
+
+        let applied = pg_migrate(&mut client, &schema, false).expect("migrate");
+        assert_eq!(applied.len(), POSTGRES_MIGRATIONS.len());
+        let statuses = pg_status(&mut client, &schema).expect("status");
+        assert!(
+            statuses
+                .iter()
+                .all(|status| status.state == MigrationState::Applied)
+        );
+        assert!(
+            pg_migrate(&mut client, &schema, false)
+                .expect("rerun")
+                .is_empty()
+        );
+        let edition_index: bool = client
+            .query_one(
+                "SELECT to_regclass(format('%I.idx_pages_edition_url', $1::text)) IS NOT NULL",
+                &[&schema],
+            )
+            .expect("query index")
+            .get(0);
+        assert!(edition_index);
+        let trigram_index: bool = client
+            .query_one(
+                "SELECT to_regclass(format('%I.idx_aliases_norm_trgm', $1::text)) IS NOT NULL",
+                &[&schema],
+            )
+            .expect("query index")
+            .get(0);
+        assert!(trigram_index);
+        let page_fts: bool = client
+            .query_one(
+                "SELECT to_regclass(format('%I.page_fts', $1::text)) IS NOT NULL",
+                &[&schema],
+            )
+            .expect("query table")
+            .get(0);
+        assert!(page_fts);
+
+        // Replaying the full-text migrations backfills pages stored before them.
+        client
+            .batch_execute(&format!(
+                r#"
+                INSERT INTO {schema_ident}.pages(id, url, title, namespace, mime_type)
+                VALUES (1, 'run', 'run', 'A', 'text/html');
+                INSERT INTO {schema_ident}.definitions(page_id, language, def_order, definition_text)
+                VALUES (1, 'English', 0, 'running quickly'), (1, 'Klingon', 0, 'running');
+                DROP TABLE {schema_ident}.page_language_fts, {schema_ident}.definition_fts;
+                DELETE FROM {schema_ident}.schema_migrations WHERE version IN (5, 6);
+                "#
+            ))
+            .expect("seed pages");
+        let applied = pg_migrate(&mut client, &schema, false).expect("replay");
+        assert_eq!(
+            applied
+                .iter()
+                .map(|status| status.version)
+                .collect::<Vec<_>>(),
+            vec![5, 6]
+        );
+        let vectors = client
+            .query(
+                &format!(
+                    "SELECT language, search_vector::text FROM {schema_ident}.page_language_fts \
+                     ORDER BY language"
+                ),
+                &[],
+            )
+            .expect("query language vectors")
+            .iter()
+            .map(|row| (row.get::<_, String>(0), row.get::<_, String>(1)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vectors,
+            vec![
+                ("English".to_owned(), "'quick':2 'run':1".to_owned()),
+                ("Klingon".to_owned(), "'running':1".to_owned()),
+            ]
+        );
+        let senses: i64 = client
+            .query_one(
+                &format!("SELECT COUNT(*) FROM {schema_ident}.definition_fts"),
+                &[],
+            )
+            .expect("count senses")
+            .get(0);
+        assert_eq!(senses, 2);
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema_ident} CASCADE;"))
+            .expect("drop schema");
+    }
+}
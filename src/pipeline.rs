@@ -1,37 +1,95 @@
+use std::any::Any;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::panic::{self, AssertUnwindSafe};
 use std::path::Path;
 use std::thread;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use anyhow::{Context, Result};
-use crossbeam_channel::{Receiver, Sender, TryRecvError, bounded};
+use crossbeam_channel::{Receiver, SendError, Sender, TryRecvError, bounded};
 use tracing::{debug, info, info_span, trace, warn};
 use zim::{DirectoryEntry, MimeType, Target, Zim};
 
-use crate::config::Config;
-use crate::db::{CheckpointState, Database};
+use crate::archive::describe_archive;
+use crate::config::{Config, GuardAction, PageGuardConfig, RedirectMode};
+use crate::db::{CheckpointState, Database, IngestionFailure, PageLineage, RedirectAlias};
 use crate::extractor::{
-    ExtractedPage, extract_from_html, mime_type_label, namespace_code, sha256_hex,
+    ExtractedPage, GuardTrip, extract_from_html, mime_type_label, namespace_code, sha256_hex,
 };
+use crate::normalization::canonicalize_lemma;
+use crate::progress::{ProgressReporter, ProgressState};
+use crate::sample::plan_sample;
+use crate::selection::{SelectionPatterns, load_title_list, resolve_title_list};
+use crate::shard::{ShardSpec, collect_shard_statuses};
+use crate::sink::{PageSink, PartialWrite, SinkSpec, open_sink};
+use crate::telemetry;
 
 #[derive(Debug, Default, Clone)]
 pub struct RunMetrics {
+    pub edition: String,
+    pub shard: Option<ShardSpec>,
     pub started_unix_ms: u128,
     pub finished_unix_ms: u128,
     pub scanned_entries: u64,
     pub filtered_entries: u64,
     pub ingested_pages: u64,
+    /// Pages a tee stored in some sinks but not all; counted as ingested, never quarantined.
+    pub partial_sink_writes: u64,
+    pub unchanged_pages: u64,
     pub extracted_definitions: u64,
     pub extracted_relations: u64,
     pub extraction_errors: u64,
     pub checkpoint_updates: u64,
     pub resumed_from_checkpoint: bool,
     pub checkpoint_start_index: Option<u32>,
+    pub run_id: Option<i64>,
+    pub source_archive_id: Option<i64>,
+    pub swept_pages: u64,
+    pub swept_fts_rows: u64,
+    pub swept_hot_lookup_rows: u64,
+    pub queued_redirects: u64,
+    pub redirect_aliases: u64,
+    pub unresolved_redirects: u64,
+    pub missing_list_titles: u64,
+    pub guard_truncated_pages: u64,
+    pub guard_skipped_pages: u64,
+    pub extraction_panics: u64,
+    /// Blob bytes read out of clusters for extraction.
+    pub decompressed_bytes: u64,
+    pub stage_timings: StageTimings,
 }
 
 impl RunMetrics {
     pub fn elapsed_ms(&self) -> u128 {
         self.finished_unix_ms.saturating_sub(self.started_unix_ms)
     }
+
+    pub fn entries_per_sec(&self) -> f64 {
+        match self.elapsed_ms() {
+            0 => 0.0,
+            elapsed_ms => self.scanned_entries as f64 * 1_000.0 / elapsed_ms as f64,
+        }
+    }
+
+    pub fn decompressed_mb_per_sec(&self) -> f64 {
+        match self.elapsed_ms() {
+            0 => 0.0,
+            elapsed_ms => self.decompressed_bytes as f64 / 1_000.0 / elapsed_ms as f64,
+        }
+    }
+}
+
+/// Time spent in each conversion stage.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StageTimings {
+    /// Decoding directory entries and applying the selection.
+    pub directory: Duration,
+    /// Loading clusters and reading blobs.
+    pub decompress: Duration,
+    /// HTML extraction, summed across extraction threads.
+    pub extract: Duration,
+    /// Writing pages to the sink.
+    pub persist: Duration,
 }
 
 #[derive(Debug, Clone)]
@@ -42,6 +100,7 @@ struct HtmlJobMeta {
     mime_type: String,
     cluster_idx: u32,
     blob_idx: u32,
+    content_sha256: Option<String>,
 }
 
 #[derive(Debug)]
@@ -60,14 +119,646 @@ enum WorkerJob {
 #[derive(Debug)]
 struct WorkerResult {
     entry_index: u32,
-    page: Option<ExtractedPage>,
-    error: Option<String>,
+    outcome: HtmlOutcome,
+    extract_time: Duration,
+}
+
+/// What guarded extraction made of one HTML entry.
+#[derive(Debug)]
+enum HtmlOutcome {
+    /// Extracted, possibly cut short by the listed guards under `on_exceed = "truncate"`.
+    Page(Box<ExtractedPage>, Vec<GuardTrip>),
+    /// Dropped because a guard tripped under `on_exceed = "skip"`.
+    Skipped { url: String, trips: Vec<GuardTrip> },
+    Failed {
+        url: String,
+        error_chain: String,
+        panicked: bool,
+    },
+}
+
+impl HtmlOutcome {
+    fn into_result(self) -> std::result::Result<ExtractedPage, EntryFailure> {
+        match self {
+            Self::Page(page, _) => Ok(*page),
+            Self::Skipped { url, trips } => Err(EntryFailure {
+                stage: FailureStage::Guard,
+                url: Some(url),
+                error_chain: join_trips(&trips),
+            }),
+            Self::Failed {
+                url, error_chain, ..
+            } => Err(EntryFailure {
+                stage: FailureStage::Extract,
+                url: Some(url),
+                error_chain,
+            }),
+        }
+    }
+}
+
+/// Buffers URLs of pages that exist in the archive but were not rewritten this run, so the
+/// end-of-run sweep does not treat them as deleted.
+struct SeenPages<'a> {
+    db: &'a Database,
+    run_id: i64,
+    edition: String,
+    pending: Vec<String>,
+    flush_size: usize,
+}
+
+impl SeenPages<'_> {
+    fn touch(&mut self, url: String) -> Result<()> {
+        self.pending.push(url);
+        if self.pending.len() >= self.flush_size {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.db
+            .mark_pages_seen(self.run_id, &self.edition, &self.pending)?;
+        self.pending.clear();
+        Ok(())
+    }
+}
+
+/// Converts every configured archive into the configured `[output]` sinks, returning metrics
+/// per archive. The database is only opened when one of the sinks is `db`. With `sample.size`
+/// set, the run writes to the sample database instead (see [`Config::sample_run_config`]).
+pub fn run_conversion(config: &Config) -> Result<Vec<RunMetrics>> {
+    let sample_config;
+    let config = if config.sample.size.is_some() {
+        sample_config = config.sample_run_config();
+        &sample_config
+    } else {
+        config
+    };
+
+    let db = if config.output.uses_database() {
+        ensure_jsonl_settings(config)?;
+        let db = Database::open(config)?;
+        db.init_schema()?;
+        Some(db)
+    } else {
+        None
+    };
+
+    let mut sink = open_sink(&config.output.sinks, db.as_ref())?;
+    let all_metrics = run_conversion_with_sink(config, sink.as_mut(), db.as_ref())?;
+
+    if let Some(db) = &db
+        && config.reindex.auto_incremental
+    {
+        let reindex_metrics =
+            db.incremental_reindex(&config.reindex.watermark_name, config.reindex.chunk_size)?;
+        info!(
+            reindexed_pages = reindex_metrics.updated_pages,
+            hot_lookup_rows = reindex_metrics.hot_lookup_rows,
+            watermark = ?reindex_metrics.watermark,
+            "incremental reindex complete"
+        );
+    }
+
+    if let Some(db) = &db
+        && let Some(rows) = db.finish_hot_lookup()?
+    {
+        info!(hot_lookup_rows = rows, "rebuilt hot_lookup projection");
+    }
+
+    Ok(all_metrics)
+}
+
+/// Converts every configured archive into `sink`. Runs, checkpoints, quarantined entries and
+/// redirect resolution are recorded in `db` when given; without it conversion is a plain
+/// one-shot stream, so incremental mode, sweeps, redirect aliases and shards are rejected.
+pub fn run_conversion_with_sink(
+    config: &Config,
+    sink: &mut dyn PageSink,
+    db: Option<&Database>,
+) -> Result<Vec<RunMetrics>> {
+    let archives = config.archive_configs()?;
+    for archive_config in &archives {
+        ensure_input_exists(&archive_config.input.zim_path)?;
+        ensure_shard_settings(archive_config)?;
+        if db.is_none() {
+            ensure_databaseless_settings(archive_config)?;
+        }
+    }
+
+    let mut all_metrics = Vec::with_capacity(archives.len());
+    for archive_config in &archives {
+        all_metrics.push(convert_archive(archive_config, sink, db)?);
+    }
+
+    Ok(all_metrics)
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct RetryMetrics {
+    pub edition: String,
+    pub run_id: Option<i64>,
+    pub attempted: u64,
+    pub recovered: u64,
+    pub dropped: u64,
+    pub still_failing: u64,
+}
+
+/// Re-processes the entries recorded in `ingestion_failures` for every configured archive.
+/// Entries that now succeed, or that the current selection no longer picks, are cleared.
+pub fn retry_failed(config: &Config) -> Result<Vec<RetryMetrics>> {
+    let archives = config.archive_configs()?;
+    let db = Database::open(config)?;
+    db.init_schema()?;
+
+    let mut all_metrics = Vec::with_capacity(archives.len());
+    for archive_config in &archives {
+        all_metrics.push(retry_archive_failures(archive_config, &db)?);
+    }
+
+    Ok(all_metrics)
+}
+
+enum RetryOutcome {
+    Page(Box<ExtractedPage>),
+    Redirect,
+    Dropped,
 }
 
-#[tracing::instrument(skip(config), fields(zim = %config.input.zim_path.display(), sqlite = %config.input.sqlite_path.display()))]
-pub fn run_conversion(config: &Config) -> Result<RunMetrics> {
+struct EntryFailure {
+    stage: FailureStage,
+    url: Option<String>,
+    error_chain: String,
+}
+
+#[tracing::instrument(skip(config, db), fields(edition = %config.input.edition))]
+fn retry_archive_failures(config: &Config, db: &Database) -> Result<RetryMetrics> {
+    let edition = &config.input.edition;
+    let mut retry = RetryMetrics {
+        edition: edition.clone(),
+        ..RetryMetrics::default()
+    };
+
+    let failures = db.load_failures(edition)?;
+    if failures.is_empty() {
+        info!("no quarantined entries to retry");
+        return Ok(retry);
+    }
+
     ensure_input_exists(&config.input.zim_path)?;
+    let patterns = SelectionPatterns::compile(&config.selection)?;
+    let zim = Zim::new(&config.input.zim_path)
+        .with_context(|| format!("failed to open {}", config.input.zim_path.display()))?;
+    let archive = describe_archive(&config.input.zim_path, &zim)?;
+    let source_archive_id = db.upsert_source_archive(&archive)?;
+
+    let mut metrics = RunMetrics {
+        edition: edition.clone(),
+        started_unix_ms: unix_now_ms()?,
+        source_archive_id: Some(source_archive_id),
+        ..RunMetrics::default()
+    };
+    let run_id = db.begin_run(metrics.started_unix_ms, Some(source_archive_id))?;
+    metrics.run_id = Some(run_id);
+    retry.run_id = Some(run_id);
+    let lineage = PageLineage {
+        run_id: Some(run_id),
+        source_archive_id: Some(source_archive_id),
+        entry_index: None,
+    };
+
+    let mut queued_redirects = Vec::new();
+    for failure in &failures {
+        retry.attempted += 1;
+        metrics.scanned_entries += 1;
+        let entry_index = failure.entry_index;
+
+        let failed = match reprocess_entry(&zim, entry_index, config, &patterns) {
+            Ok(RetryOutcome::Page(page)) => {
+                match db.upsert_page(&page, &lineage.at_entry(entry_index)) {
+                    Ok(()) => {
+                        metrics.ingested_pages += 1;
+                        metrics.extracted_definitions += page.definitions.len() as u64;
+                        metrics.extracted_relations += page.relations.len() as u64;
+                        retry.recovered += 1;
+                        None
+                    }
+                    Err(error) => Some(EntryFailure {
+                        stage: FailureStage::Upsert,
+                        url: Some(page.url),
+                        error_chain: format!("{error:#}"),
+                    }),
+                }
+            }
+            Ok(RetryOutcome::Redirect) => {
+                metrics.queued_redirects += 1;
+                queued_redirects.push(entry_index);
+                retry.recovered += 1;
+                None
+            }
+            Ok(RetryOutcome::Dropped) => {
+                metrics.filtered_entries += 1;
+                retry.dropped += 1;
+                None
+            }
+            Err(failure) => Some(failure),
+        };
+
+        match failed {
+            None => db.clear_failure(edition, entry_index)?,
+            Some(failure) => {
+                metrics.extraction_errors += 1;
+                retry.still_failing += 1;
+                warn!(
+                    entry_index,
+                    stage = failure.stage.as_str(),
+                    error = %failure.error_chain,
+                    "entry still failing"
+                );
+                quarantine(
+                    Some(db),
+                    edition,
+                    Some(run_id),
+                    entry_index,
+                    failure.url.as_deref(),
+                    failure.stage,
+                    failure.error_chain,
+                );
+            }
+        }
+    }
+
+    if !queued_redirects.is_empty() {
+        db.queue_redirects(edition, &queued_redirects)?;
+        resolve_redirect_aliases(&zim, db, config, &mut metrics)?;
+    }
+
+    metrics.finished_unix_ms = unix_now_ms()?;
+    db.finish_run(run_id, &metrics)?;
+
+    info!(
+        attempted = retry.attempted,
+        recovered = retry.recovered,
+        dropped = retry.dropped,
+        still_failing = retry.still_failing,
+        run_id,
+        "retry of quarantined entries complete"
+    );
+
+    Ok(retry)
+}
+
+/// Runs one entry through the same decode/select/extract steps as a convert, sequentially.
+fn reprocess_entry(
+    zim: &Zim,
+    entry_index: u32,
+    config: &Config,
+    patterns: &SelectionPatterns,
+) -> std::result::Result<RetryOutcome, EntryFailure> {
+    match prepare_entry(zim, entry_index, config, patterns)? {
+        PreparedEntry::Html(meta, html) => extract_html_entry(meta, html, config)
+            .into_result()
+            .map(|page| RetryOutcome::Page(Box::new(page))),
+        PreparedEntry::Page(page) => Ok(RetryOutcome::Page(page)),
+        PreparedEntry::Redirect => Ok(RetryOutcome::Redirect),
+        PreparedEntry::Dropped => Ok(RetryOutcome::Dropped),
+    }
+}
+
+/// An entry after the cheap, archive-bound steps: decoded, selected and its blob read.
+enum PreparedEntry {
+    Html(HtmlJobMeta, String),
+    Page(Box<ExtractedPage>),
+    Redirect,
+    Dropped,
+}
+
+fn prepare_entry(
+    zim: &Zim,
+    entry_index: u32,
+    config: &Config,
+    patterns: &SelectionPatterns,
+) -> std::result::Result<PreparedEntry, EntryFailure> {
+    let entry = zim
+        .get_by_url_index(entry_index)
+        .map_err(|error| EntryFailure {
+            stage: FailureStage::DirectoryEntry,
+            url: None,
+            error_chain: error.to_string(),
+        })?;
+
+    if !should_select_entry(&entry, config, patterns) {
+        return Ok(PreparedEntry::Dropped);
+    }
+
+    match entry.target {
+        Some(Target::Redirect(redirect_idx)) => match config.selection.redirect_mode() {
+            RedirectMode::Skip => Ok(PreparedEntry::Dropped),
+            RedirectMode::Alias => Ok(PreparedEntry::Redirect),
+            RedirectMode::Page => Ok(PreparedEntry::Page(Box::new(build_redirect_page(
+                zim,
+                entry,
+                redirect_idx,
+                config,
+            )))),
+        },
+        Some(Target::Cluster(cluster_idx, blob_idx)) => {
+            let cluster = match zim.get_cluster(cluster_idx) {
+                Ok(cluster) => cluster,
+                Err(error) => {
+                    return Err(EntryFailure {
+                        stage: FailureStage::Cluster,
+                        url: Some(entry.url),
+                        error_chain: error.to_string(),
+                    });
+                }
+            };
+            let html = match cluster.get_blob(blob_idx) {
+                Ok(blob) => String::from_utf8_lossy(blob.as_ref()).into_owned(),
+                Err(error) => {
+                    return Err(EntryFailure {
+                        stage: FailureStage::Blob,
+                        url: Some(entry.url),
+                        error_chain: error.to_string(),
+                    });
+                }
+            };
+
+            Ok(PreparedEntry::Html(
+                html_job_meta(entry, cluster_idx, blob_idx, None),
+                html,
+            ))
+        }
+        None => Ok(PreparedEntry::Dropped),
+    }
+}
+
+/// A page yielded by [`PageStream`], tagged with the ZIM entry it came from.
+#[derive(Debug, Clone)]
+pub struct StreamedPage {
+    pub entry_index: u32,
+    pub page: ExtractedPage,
+}
+
+/// An entry [`PageStream`] could not turn into a page. `stage` is one of the
+/// `ingestion_failures` stage names (`directory_entry`, `cluster`, `blob`, `extract`, `guard`).
+#[derive(Debug, Clone)]
+pub struct PageStreamError {
+    pub entry_index: u32,
+    pub url: Option<String>,
+    pub stage: &'static str,
+    pub error_chain: String,
+}
+
+impl std::fmt::Display for PageStreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "entry {} failed at {}: {}",
+            self.entry_index, self.stage, self.error_chain
+        )
+    }
+}
+
+impl std::error::Error for PageStreamError {}
+
+impl PageStreamError {
+    fn from_failure(entry_index: u32, failure: EntryFailure) -> Self {
+        Self {
+            entry_index,
+            url: failure.url,
+            stage: failure.stage.as_str(),
+            error_chain: failure.error_chain,
+        }
+    }
+}
+
+pub type PageStreamItem = std::result::Result<StreamedPage, PageStreamError>;
+
+/// Iterates the pages a convert would write, without a database or sink.
+///
+/// The stream reads the entry window from `selection.start_index`/`max_entries` (narrowed to
+/// `selection.title_list_path` when set), applies the
+/// same selection and extraction settings as `convert`, and extracts on
+/// `workers.extraction_threads` threads. Pages arrive in completion order unless
+/// [`PageStream::preserve_order`] is set. Redirects are yielded only in `page` mode, since
+/// `alias` resolution needs stored target pages.
+pub struct PageStream {
+    zim: Zim,
+    config: Config,
+    patterns: SelectionPatterns,
+    window: EntryWindow,
+    preserve_order: bool,
+    job_sender: Option<Sender<WorkerJob>>,
+    result_receiver: Option<Receiver<WorkerResult>>,
+    workers: Vec<thread::JoinHandle<()>>,
+    inflight_jobs: u64,
+    max_outstanding: usize,
+    arrived: VecDeque<(u32, PageStreamItem)>,
+    ordered_pending: VecDeque<u32>,
+    ordered_done: HashMap<u32, PageStreamItem>,
+}
+
+impl PageStream {
+    /// Opens `config.input.zim_path` and streams it.
+    pub fn open(config: &Config) -> Result<Self> {
+        ensure_input_exists(&config.input.zim_path)?;
+        let zim = Zim::new(&config.input.zim_path)
+            .with_context(|| format!("failed to open {}", config.input.zim_path.display()))?;
+        Self::new(zim, config)
+    }
+
+    pub fn new(zim: Zim, config: &Config) -> Result<Self> {
+        let patterns = SelectionPatterns::compile(&config.selection)?;
+        let total_articles = zim.header.article_count;
+        let start = config.selection.start_index.min(total_articles);
+        let max_entries = config
+            .selection
+            .max_entries
+            .unwrap_or(total_articles.saturating_sub(start));
+        let end = (u64::from(start) + u64::from(max_entries)).min(u64::from(total_articles)) as u32;
+        let (window, _) = entry_window(&zim, config, &patterns, start, end)?;
+
+        let extraction_threads = config.workers.extraction_threads.max(1);
+        let queue_capacity = config.workers.queue_capacity.max(32);
+        let (job_sender, result_receiver, workers) = if extraction_threads > 1 {
+            let (job_tx, job_rx) = bounded::<WorkerJob>(queue_capacity);
+            let (result_tx, result_rx) = bounded::<WorkerResult>(queue_capacity);
+            let handles = spawn_workers(extraction_threads, job_rx, result_tx, config.clone());
+            (Some(job_tx), Some(result_rx), handles)
+        } else {
+            (None, None, Vec::new())
+        };
+
+        Ok(Self {
+            zim,
+            config: config.clone(),
+            patterns,
+            window,
+            preserve_order: false,
+            job_sender,
+            result_receiver,
+            workers,
+            inflight_jobs: 0,
+            max_outstanding: queue_capacity,
+            arrived: VecDeque::new(),
+            ordered_pending: VecDeque::new(),
+            ordered_done: HashMap::new(),
+        })
+    }
+
+    /// Yields pages in entry-index order. Finished pages wait behind slower earlier ones, so
+    /// up to `workers.queue_capacity` pages may be buffered.
+    pub fn preserve_order(mut self, preserve_order: bool) -> Self {
+        self.preserve_order = preserve_order;
+        self
+    }
+
+    fn outstanding(&self) -> usize {
+        if self.preserve_order {
+            self.ordered_pending.len()
+        } else {
+            self.inflight_jobs as usize + self.arrived.len()
+        }
+    }
+
+    fn finish(&mut self, entry_index: u32, item: PageStreamItem) {
+        if self.preserve_order {
+            self.ordered_done.insert(entry_index, item);
+        } else {
+            self.arrived.push_back((entry_index, item));
+        }
+    }
+
+    fn pop_finished(&mut self) -> Option<PageStreamItem> {
+        if !self.preserve_order {
+            return self.arrived.pop_front().map(|(_, item)| item);
+        }
+
+        let next = *self.ordered_pending.front()?;
+        let item = self.ordered_done.remove(&next)?;
+        self.ordered_pending.pop_front();
+        Some(item)
+    }
+
+    /// Prepares the next entry in the window and either finishes it in place or hands the HTML
+    /// to a worker. Returns `false` once the window is exhausted.
+    fn advance(&mut self) -> bool {
+        let Some(entry_index) = self.window.next() else {
+            return false;
+        };
+
+        let item = match prepare_entry(&self.zim, entry_index, &self.config, &self.patterns) {
+            Ok(PreparedEntry::Html(meta, html)) => {
+                let job = HtmlJob {
+                    entry_index,
+                    meta,
+                    html,
+                };
+                let job = match &self.job_sender {
+                    Some(job_tx) => match job_tx.send(WorkerJob::Html(job)) {
+                        Ok(()) => {
+                            self.inflight_jobs += 1;
+                            if self.preserve_order {
+                                self.ordered_pending.push_back(entry_index);
+                            }
+                            return true;
+                        }
+                        Err(SendError(WorkerJob::Html(job))) => {
+                            warn!(
+                                entry_index,
+                                "page stream workers stopped; extracting inline"
+                            );
+                            self.job_sender = None;
+                            job
+                        }
+                        Err(SendError(WorkerJob::Shutdown)) => return true,
+                    },
+                    None => job,
+                };
+
+                extract_html_entry(job.meta, job.html, &self.config)
+                    .into_result()
+                    .map(|page| StreamedPage { entry_index, page })
+                    .map_err(|failure| PageStreamError::from_failure(entry_index, failure))
+            }
+            Ok(PreparedEntry::Page(page)) => Ok(StreamedPage {
+                entry_index,
+                page: *page,
+            }),
+            Ok(PreparedEntry::Redirect | PreparedEntry::Dropped) => return true,
+            Err(failure) => Err(PageStreamError::from_failure(entry_index, failure)),
+        };
+
+        if self.preserve_order {
+            self.ordered_pending.push_back(entry_index);
+        }
+        self.finish(entry_index, item);
+        true
+    }
+
+    fn store_worker_result(&mut self, result: WorkerResult) {
+        let entry_index = result.entry_index;
+        let item = result
+            .outcome
+            .into_result()
+            .map(|page| StreamedPage { entry_index, page })
+            .map_err(|failure| PageStreamError::from_failure(entry_index, failure));
+        self.finish(entry_index, item);
+    }
+}
+
+impl Iterator for PageStream {
+    type Item = PageStreamItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            for result in
+                collect_worker_results(&self.result_receiver, &mut self.inflight_jobs, false)
+            {
+                self.store_worker_result(result);
+            }
+
+            if let Some(item) = self.pop_finished() {
+                return Some(item);
+            }
+
+            if self.outstanding() < self.max_outstanding && self.advance() {
+                continue;
+            }
+
+            let results =
+                collect_worker_results(&self.result_receiver, &mut self.inflight_jobs, true);
+            if results.is_empty() {
+                return None;
+            }
+            for result in results {
+                self.store_worker_result(result);
+            }
+        }
+    }
+}
+
+impl Drop for PageStream {
+    fn drop(&mut self) {
+        // Closing both channels stops the workers even if results were never drained.
+        self.job_sender.take();
+        self.result_receiver.take();
+        for handle in self.workers.drain(..) {
+            if let Err(error) = handle.join() {
+                warn!(?error, "page stream worker join failed");
+            }
+        }
+    }
+}
 
+#[tracing::instrument(skip(config, sink, db), fields(edition = %config.input.edition, zim = %config.input.zim_path.display(), sink = sink.name()))]
+fn convert_archive(
+    config: &Config,
+    sink: &mut dyn PageSink,
+    db: Option<&Database>,
+) -> Result<RunMetrics> {
     let started_unix_ms = unix_now_ms()?;
 
     info!("opening zim archive");
@@ -82,48 +773,144 @@ pub fn run_conversion(config: &Config) -> Result<RunMetrics> {
         "zim header loaded"
     );
 
-    let db = Database::open(config)?;
-    db.init_schema()?;
+    let archive = describe_archive(&config.input.zim_path, &zim)?;
+    info!(
+        zim_uuid = %archive.zim_uuid,
+        file_name = %archive.file_name,
+        size_bytes = archive.size_bytes,
+        zim_date = ?archive.zim_date,
+        zim_language = ?archive.zim_language,
+        checksum_md5 = %archive.checksum_md5,
+        "source archive identified"
+    );
+
+    let source_archive_id = db
+        .map(|db| db.upsert_source_archive(&archive))
+        .transpose()?;
 
     let total_articles = zim.header.article_count;
-    let mut start = config.selection.start_index.min(total_articles);
-    let max_entries = config
-        .selection
-        .max_entries
-        .unwrap_or(total_articles.saturating_sub(start));
+    let shard = config.selection.shard;
+    let checkpoint_name = match shard {
+        Some(shard) => shard.checkpoint_name(&config.checkpoint.name),
+        None => config.checkpoint.name.clone(),
+    };
+    // A shard always stops at its slice boundary, even when resumed part-way through it.
+    let (range_start, shard_end, max_entries) = match shard {
+        Some(shard) => {
+            let (shard_start, shard_end) = shard.index_range(total_articles);
+            info!(
+                %shard,
+                shard_start,
+                shard_end,
+                checkpoint_name = %checkpoint_name,
+                "converting shard"
+            );
+            (shard_start, Some(shard_end), shard_end - shard_start)
+        }
+        None => {
+            let start = config.selection.start_index.min(total_articles);
+            let max_entries = config
+                .selection
+                .max_entries
+                .unwrap_or(total_articles.saturating_sub(start));
+            (start, None, max_entries)
+        }
+    };
+    let mut start = range_start;
 
     let mut metrics = RunMetrics {
+        edition: config.input.edition.clone(),
+        shard,
         started_unix_ms,
         ..RunMetrics::default()
     };
 
-    if config.checkpoint.enabled && config.checkpoint.resume {
-        if let Some(checkpoint) = db.load_checkpoint(&config.checkpoint.name)? {
-            let resumed_index = checkpoint.last_processed_index.saturating_add(1);
-            if resumed_index > start {
-                start = resumed_index.min(total_articles);
-                metrics.resumed_from_checkpoint = true;
-                metrics.checkpoint_start_index = Some(start);
-                info!(
-                    checkpoint_name = %config.checkpoint.name,
-                    resumed_start_index = start,
-                    "resuming from checkpoint"
-                );
-            }
+    let mut resumed_run_id = None;
+    if config.checkpoint.enabled
+        && config.checkpoint.resume
+        && let Some(db) = db
+        && let Some(checkpoint) = db.load_checkpoint(&checkpoint_name)?
+    {
+        let resumed_index = checkpoint.last_processed_index.saturating_add(1);
+        if resumed_index > start {
+            start = resumed_index.min(total_articles);
+            metrics.resumed_from_checkpoint = true;
+            metrics.checkpoint_start_index = Some(start);
+            resumed_run_id = checkpoint.run_id;
+            info!(
+                checkpoint_name = %checkpoint_name,
+                resumed_start_index = start,
+                resumed_run_id = ?checkpoint.run_id,
+                "resuming from checkpoint"
+            );
         }
     }
 
-    let end = (u64::from(start) + u64::from(max_entries)).min(u64::from(total_articles)) as u32;
+    // A resumed run keeps the run id of the interrupted one so the sweep sees both halves.
+    let run_id = match (resumed_run_id, db) {
+        (Some(run_id), _) => Some(run_id),
+        (None, Some(db)) => Some(db.begin_run(started_unix_ms, source_archive_id)?),
+        (None, None) => None,
+    };
+    metrics.run_id = run_id;
+    metrics.source_archive_id = source_archive_id;
+    let lineage = PageLineage {
+        run_id,
+        source_archive_id,
+        entry_index: None,
+    };
+
+    let mut seen_pages = match (db, run_id) {
+        (Some(db), Some(run_id)) if config.sweep.enabled => Some(SeenPages {
+            db,
+            run_id,
+            edition: config.input.edition.clone(),
+            pending: Vec::new(),
+            flush_size: config.sweep.touch_batch_size.max(1),
+        }),
+        _ => None,
+    };
+
+    let known_hashes = if let Some(db) = db
+        && config.incremental.skip_unchanged
+    {
+        let hashes =
+            db.load_content_hashes(&config.input.edition, config.incremental.hash_batch_size)?;
+        info!(
+            known_pages = hashes.len(),
+            "loaded stored content hashes for incremental convert"
+        );
+        Some(hashes)
+    } else {
+        None
+    };
+
+    let end = match shard_end {
+        Some(shard_end) => shard_end,
+        None => (u64::from(start) + u64::from(max_entries)).min(u64::from(total_articles)) as u32,
+    };
+    let patterns = SelectionPatterns::compile(&config.selection)?;
+    let (window, missing_titles) = entry_window(&zim, config, &patterns, start, end)?;
+    metrics.missing_list_titles = missing_titles;
 
     info!(
         start_index = start,
         end_index = end,
+        listed_entries = window.listed_len(),
         extraction_threads = config.workers.extraction_threads,
         "starting extraction window"
     );
 
     let batch_size = config.sqlite.batch_size.max(1) as u64;
     let progress_interval = config.logging.progress_interval.max(1);
+    let window_entries = window
+        .listed_len()
+        .map_or(u64::from(end.saturating_sub(start)), |len| len as u64);
+    let mut progress = ProgressReporter::new(
+        &config.logging,
+        window_entries,
+        shard.map(|shard| shard.to_string()),
+    );
 
     let mut checkpoint_last_idx = start.saturating_sub(1);
 
@@ -140,23 +927,48 @@ pub fn run_conversion(config: &Config) -> Result<RunMetrics> {
         (None, None, Vec::new())
     };
 
+    let redirect_mode = config.selection.redirect_mode();
+    let mut queued_redirects = Vec::new();
     let mut inflight_jobs = 0_u64;
 
-    for idx in start..end {
+    // Entries whose URL could not be decoded cannot be marked seen, so they rule out a sweep.
+    let mut undecoded_entries = 0_u64;
+
+    let registry = telemetry::global();
+    for idx in window {
+        registry.record_run(&metrics);
+        registry.set_queue_depth("extraction", inflight_jobs);
+        registry.set_queue_depth("redirects", queued_redirects.len() as u64);
+        progress.tick(&metrics)?;
         metrics.scanned_entries += 1;
         checkpoint_last_idx = idx;
 
+        let directory_started = Instant::now();
         let entry = match zim.get_by_url_index(idx) {
             Ok(entry) => entry,
             Err(error) => {
                 metrics.extraction_errors += 1;
+                undecoded_entries += 1;
                 warn!(entry_index = idx, error = %error, "failed to decode directory entry");
+                quarantine(
+                    db,
+                    &config.input.edition,
+                    run_id,
+                    idx,
+                    None,
+                    FailureStage::DirectoryEntry,
+                    error.to_string(),
+                );
                 continue;
             }
         };
 
-        if !should_select_entry(&entry, config) {
+        let selected = should_select_entry(&entry, config, &patterns);
+        metrics.stage_timings.directory += directory_started.elapsed();
+        if !selected {
+            // Still in the archive: a narrower selection must not sweep pages stored earlier.
             metrics.filtered_entries += 1;
+            touch_seen(&mut seen_pages, entry.url)?;
             continue;
         }
 
@@ -171,44 +983,49 @@ pub fn run_conversion(config: &Config) -> Result<RunMetrics> {
 
         match entry.target {
             Some(Target::Redirect(redirect_idx)) => {
-                if config.selection.skip_redirects {
-                    metrics.filtered_entries += 1;
-                    continue;
+                match redirect_mode {
+                    RedirectMode::Skip => {
+                        metrics.filtered_entries += 1;
+                        touch_seen(&mut seen_pages, entry.url)?;
+                        continue;
+                    }
+                    RedirectMode::Alias => {
+                        // Targets may not be stored yet; resolve once every page is written.
+                        metrics.queued_redirects += 1;
+                        queued_redirects.push(idx);
+                        touch_seen(&mut seen_pages, entry.url)?;
+                        continue;
+                    }
+                    RedirectMode::Page => {}
                 }
 
-                let redirect_url = zim.get_by_url_index(redirect_idx).ok().map(|e| e.url);
-                let url = entry.url;
-                let title = if entry.title.trim().is_empty() {
-                    redirect_url.clone().unwrap_or_else(|| url.clone())
-                } else {
-                    entry.title
-                };
-
-                let page = ExtractedPage {
-                    url,
-                    title,
-                    namespace: namespace_code(entry.namespace).to_owned(),
-                    mime_type: mime_type_label(&entry.mime_type),
-                    cluster_idx: None,
-                    blob_idx: None,
-                    redirect_url,
-                    content_sha256: None,
-                    raw_html: None,
-                    plain_text: None,
-                    extraction_confidence: 0.0,
-                    definitions: Vec::new(),
-                    relations: Vec::new(),
-                    aliases: Vec::new(),
-                };
-
-                persist_page(&db, &page, &mut metrics)?;
+                let page = build_redirect_page(&zim, entry, redirect_idx, config);
+                persist_page(
+                    sink,
+                    db,
+                    &page,
+                    lineage.at_entry(idx),
+                    &mut metrics,
+                    &mut seen_pages,
+                )?;
             }
             Some(Target::Cluster(cluster_idx, blob_idx)) => {
+                let decompress_started = Instant::now();
                 let cluster = match zim.get_cluster(cluster_idx) {
                     Ok(cluster) => cluster,
                     Err(error) => {
                         metrics.extraction_errors += 1;
                         warn!(entry_index = idx, error = %error, "failed to load cluster");
+                        quarantine(
+                            db,
+                            &config.input.edition,
+                            run_id,
+                            idx,
+                            Some(&entry.url),
+                            FailureStage::Cluster,
+                            error.to_string(),
+                        );
+                        touch_seen(&mut seen_pages, entry.url)?;
                         continue;
                     }
                 };
@@ -218,26 +1035,40 @@ pub fn run_conversion(config: &Config) -> Result<RunMetrics> {
                     Err(error) => {
                         metrics.extraction_errors += 1;
                         warn!(entry_index = idx, error = %error, "failed to read blob");
+                        quarantine(
+                            db,
+                            &config.input.edition,
+                            run_id,
+                            idx,
+                            Some(&entry.url),
+                            FailureStage::Blob,
+                            error.to_string(),
+                        );
+                        touch_seen(&mut seen_pages, entry.url)?;
                         continue;
                     }
                 };
 
                 let html = String::from_utf8_lossy(blob.as_ref()).into_owned();
-
-                let fallback_url = entry.url.clone();
-                let meta = HtmlJobMeta {
-                    url: entry.url,
-                    title: if entry.title.trim().is_empty() {
-                        fallback_url
-                    } else {
-                        entry.title
-                    },
-                    namespace: namespace_code(entry.namespace).to_owned(),
-                    mime_type: mime_type_label(&entry.mime_type),
-                    cluster_idx,
-                    blob_idx,
+                metrics.decompressed_bytes += blob.as_ref().len() as u64;
+                metrics.stage_timings.decompress += decompress_started.elapsed();
+
+                let content_sha256 = match &known_hashes {
+                    Some(known_hashes) => {
+                        let content_sha256 = sha256_hex(&html);
+                        if known_hashes.get(&entry.url) == Some(&content_sha256) {
+                            metrics.unchanged_pages += 1;
+                            trace!(entry_index = idx, "content hash unchanged; skipping page");
+                            touch_seen(&mut seen_pages, entry.url)?;
+                            continue;
+                        }
+                        Some(content_sha256)
+                    }
+                    None => None,
                 };
 
+                let meta = html_job_meta(entry, cluster_idx, blob_idx, content_sha256);
+
                 if let Some(job_tx) = &job_sender {
                     inflight_jobs += 1;
                     job_tx
@@ -252,13 +1083,33 @@ pub fn run_conversion(config: &Config) -> Result<RunMetrics> {
                             )
                         })?;
                 } else {
-                    match build_page_from_html(meta, html, config) {
+                    let job = HtmlJob {
+                        entry_index: idx,
+                        meta,
+                        html,
+                    };
+                    match tally_html_outcome(run_html_job(job, config), &mut metrics) {
                         Ok(page) => {
-                            persist_page(&db, &page, &mut metrics)?;
+                            persist_page(
+                                sink,
+                                db,
+                                &page,
+                                lineage.at_entry(idx),
+                                &mut metrics,
+                                &mut seen_pages,
+                            )?;
                         }
-                        Err(error) => {
-                            metrics.extraction_errors += 1;
-                            warn!(entry_index = idx, error = %error, "entry extraction failed");
+                        Err(failure) => {
+                            quarantine(
+                                db,
+                                &config.input.edition,
+                                run_id,
+                                idx,
+                                failure.url.as_deref(),
+                                failure.stage,
+                                failure.error_chain,
+                            );
+                            touch_seen(&mut seen_pages, failure.url.unwrap_or_default())?;
                         }
                     }
                 }
@@ -269,6 +1120,7 @@ pub fn run_conversion(config: &Config) -> Result<RunMetrics> {
                     MimeType::DeletedEntry | MimeType::LinkTarget
                 ) {
                     metrics.filtered_entries += 1;
+                    touch_seen(&mut seen_pages, entry.url)?;
                     continue;
                 }
 
@@ -277,55 +1129,85 @@ pub fn run_conversion(config: &Config) -> Result<RunMetrics> {
                     entry_index = idx,
                     "entry had no target payload and was skipped"
                 );
+                touch_seen(&mut seen_pages, entry.url)?;
             }
         }
 
         let worker_results = collect_worker_results(&result_receiver, &mut inflight_jobs, false);
         for result in worker_results {
-            if let Some(error) = result.error {
-                metrics.extraction_errors += 1;
-                warn!(entry_index = result.entry_index, error = %error, "worker extraction failed");
-                continue;
-            }
-
-            if let Some(page) = result.page {
-                persist_page(&db, &page, &mut metrics)?;
+            let entry_index = result.entry_index;
+            match tally_html_outcome(result, &mut metrics) {
+                Ok(page) => {
+                    persist_page(
+                        sink,
+                        db,
+                        &page,
+                        lineage.at_entry(entry_index),
+                        &mut metrics,
+                        &mut seen_pages,
+                    )?;
+                }
+                Err(failure) => {
+                    quarantine(
+                        db,
+                        &config.input.edition,
+                        run_id,
+                        entry_index,
+                        failure.url.as_deref(),
+                        failure.stage,
+                        failure.error_chain,
+                    );
+                    touch_seen(&mut seen_pages, failure.url.unwrap_or_default())?;
+                }
             }
         }
 
         if config.checkpoint.enabled
             && config.checkpoint.every_n_entries > 0
-            && metrics.scanned_entries % config.checkpoint.every_n_entries == 0
+            && let Some(db) = db
+            && metrics
+                .scanned_entries
+                .is_multiple_of(config.checkpoint.every_n_entries)
         {
-            db.save_checkpoint(
-                &config.checkpoint.name,
+            flush_sink(sink, Some(db))?;
+            db.queue_redirects(&config.input.edition, &queued_redirects)?;
+            queued_redirects.clear();
+            save_checkpoint(
+                db,
+                &checkpoint_name,
                 &CheckpointState {
                     last_processed_index: checkpoint_last_idx,
                     ingested_pages: metrics.ingested_pages,
                     extracted_definitions: metrics.extracted_definitions,
                     extracted_relations: metrics.extracted_relations,
+                    run_id,
                 },
             )?;
             metrics.checkpoint_updates += 1;
         }
 
-        if metrics.ingested_pages % batch_size == 0 && metrics.ingested_pages > 0 {
+        if metrics.ingested_pages.is_multiple_of(batch_size) && metrics.ingested_pages > 0 {
             trace!(
                 ingested_pages = metrics.ingested_pages,
-                backend = db.backend_name(),
+                sink = sink.name(),
                 "batch boundary reached"
             );
         }
 
-        if metrics.scanned_entries % progress_interval == 0 {
+        if metrics.scanned_entries.is_multiple_of(progress_interval) {
+            let snapshot = progress.snapshot(&metrics, ProgressState::Running);
             info!(
+                window_entries,
                 scanned_entries = metrics.scanned_entries,
                 ingested_pages = metrics.ingested_pages,
+                unchanged_pages = metrics.unchanged_pages,
                 filtered_entries = metrics.filtered_entries,
                 extracted_definitions = metrics.extracted_definitions,
                 extracted_relations = metrics.extracted_relations,
                 extraction_errors = metrics.extraction_errors,
                 inflight_jobs,
+                entries_per_sec = snapshot.entries_per_sec.round(),
+                eta_secs = snapshot.eta_secs.map(f64::round),
                 "progress"
             );
         }
@@ -344,14 +1226,30 @@ pub fn run_conversion(config: &Config) -> Result<RunMetrics> {
         }
 
         for result in worker_results {
-            if let Some(error) = result.error {
-                metrics.extraction_errors += 1;
-                warn!(entry_index = result.entry_index, error = %error, "worker extraction failed");
-                continue;
-            }
-
-            if let Some(page) = result.page {
-                persist_page(&db, &page, &mut metrics)?;
+            let entry_index = result.entry_index;
+            match tally_html_outcome(result, &mut metrics) {
+                Ok(page) => {
+                    persist_page(
+                        sink,
+                        db,
+                        &page,
+                        lineage.at_entry(entry_index),
+                        &mut metrics,
+                        &mut seen_pages,
+                    )?;
+                }
+                Err(failure) => {
+                    quarantine(
+                        db,
+                        &config.input.edition,
+                        run_id,
+                        entry_index,
+                        failure.url.as_deref(),
+                        failure.stage,
+                        failure.error_chain,
+                    );
+                    touch_seen(&mut seen_pages, failure.url.unwrap_or_default())?;
+                }
             }
         }
     }
@@ -362,40 +1260,102 @@ pub fn run_conversion(config: &Config) -> Result<RunMetrics> {
         }
     }
 
-    if config.checkpoint.enabled {
-        db.save_checkpoint(
-            &config.checkpoint.name,
-            &CheckpointState {
-                last_processed_index: checkpoint_last_idx,
-                ingested_pages: metrics.ingested_pages,
-                extracted_definitions: metrics.extracted_definitions,
-                extracted_relations: metrics.extracted_relations,
-            },
-        )?;
-        metrics.checkpoint_updates += 1;
+    flush_sink(sink, db)?;
+
+    if let Some(db) = db {
+        db.queue_redirects(&config.input.edition, &queued_redirects)?;
+        queued_redirects.clear();
+
+        if config.checkpoint.enabled {
+            save_checkpoint(
+                db,
+                &checkpoint_name,
+                &CheckpointState {
+                    last_processed_index: checkpoint_last_idx,
+                    ingested_pages: metrics.ingested_pages,
+                    extracted_definitions: metrics.extracted_definitions,
+                    extracted_relations: metrics.extracted_relations,
+                    run_id,
+                },
+            )?;
+            metrics.checkpoint_updates += 1;
+        }
+    }
+
+    if let Some(seen_pages) = seen_pages.as_mut() {
+        seen_pages.flush()?;
+
+        // Only a run that covered the whole archive under one run id can tell which pages are gone.
+        let covers_full_range = range_start == 0
+            && config.selection.title_list_path.is_none()
+            && end == total_articles
+            && (!metrics.resumed_from_checkpoint || resumed_run_id.is_some());
+        if covers_full_range && undecoded_entries > 0 {
+            warn!(
+                undecoded_entries,
+                "sweep skipped: some directory entries could not be decoded"
+            );
+        } else if covers_full_range {
+            let sweep = seen_pages.db.sweep_unseen_pages(
+                seen_pages.run_id,
+                &config.input.edition,
+                config.sweep.policy,
+            )?;
+            metrics.swept_pages = sweep.swept_pages;
+            metrics.swept_fts_rows = sweep.removed_fts_rows;
+            metrics.swept_hot_lookup_rows = sweep.removed_hot_lookup_rows;
+            info!(
+                policy = ?config.sweep.policy,
+                swept_pages = sweep.swept_pages,
+                removed_fts_rows = sweep.removed_fts_rows,
+                removed_hot_lookup_rows = sweep.removed_hot_lookup_rows,
+                "swept pages missing from archive"
+            );
+        } else if let Some(shard) = shard.filter(|shard| shard.count > 1) {
+            warn!(
+                shard = %shard,
+                "sweep skipped: a shard covers only its own slice; sweep from an unsharded run"
+            );
+        } else {
+            warn!(
+                start_index = range_start,
+                end_index = end,
+                total_articles,
+                "sweep skipped: run did not cover the full archive index range"
+            );
+        }
+    }
+
+    if redirect_mode == RedirectMode::Alias
+        && let Some(db) = db
+    {
+        // A redirect may point into another shard's range, so only the last shard to finish
+        // resolves the queue.
+        let shards_pending = match shard {
+            Some(shard) => collect_shard_statuses(config, db, shard.count, total_articles)?
+                .iter()
+                .filter(|status| !status.is_complete())
+                .count(),
+            None => 0,
+        };
+        if shards_pending == 0 {
+            resolve_redirect_aliases(&zim, db, config, &mut metrics)?;
+        } else {
+            info!(
+                shards_pending,
+                "redirects stay queued until the remaining shards finish"
+            );
+        }
     }
 
     metrics.finished_unix_ms = unix_now_ms()?;
+    progress.finish(&metrics)?;
+    registry.record_run(&metrics);
+    registry.set_queue_depth("extraction", 0);
+    registry.set_queue_depth("redirects", 0);
 
-    db.insert_run_metrics(
-        metrics.started_unix_ms,
-        metrics.finished_unix_ms,
-        metrics.scanned_entries,
-        metrics.filtered_entries,
-        metrics.ingested_pages,
-        metrics.extracted_definitions,
-        metrics.extracted_relations,
-        metrics.extraction_errors,
-    )?;
-
-    if config.reindex.auto_incremental {
-        let reindex_metrics =
-            db.incremental_reindex(&config.reindex.watermark_name, config.reindex.chunk_size)?;
-        info!(
-            reindexed_pages = reindex_metrics.updated_pages,
-            watermark = ?reindex_metrics.watermark,
-            "incremental reindex complete"
-        );
+    if let (Some(db), Some(run_id)) = (db, run_id) {
+        db.finish_run(run_id, &metrics)?;
     }
 
     info!(
@@ -403,17 +1363,171 @@ pub fn run_conversion(config: &Config) -> Result<RunMetrics> {
         scanned_entries = metrics.scanned_entries,
         filtered_entries = metrics.filtered_entries,
         ingested_pages = metrics.ingested_pages,
+        partial_sink_writes = metrics.partial_sink_writes,
+        unchanged_pages = metrics.unchanged_pages,
         extracted_definitions = metrics.extracted_definitions,
         extracted_relations = metrics.extracted_relations,
         extraction_errors = metrics.extraction_errors,
         checkpoint_updates = metrics.checkpoint_updates,
         resumed_from_checkpoint = metrics.resumed_from_checkpoint,
+        swept_pages = metrics.swept_pages,
+        redirect_aliases = metrics.redirect_aliases,
+        unresolved_redirects = metrics.unresolved_redirects,
+        entries_per_sec = metrics.entries_per_sec().round(),
+        decompressed_mb_per_sec = metrics.decompressed_mb_per_sec(),
         "conversion complete"
     );
 
     Ok(metrics)
 }
 
+/// Entry indexes a run visits: every index in `[start, end)`, or only the entries named in
+/// `selection.title_list_path` or drawn by `sample.size` that fall inside it.
+pub(crate) enum EntryWindow {
+    Range(std::ops::Range<u32>),
+    Listed(std::vec::IntoIter<u32>),
+}
+
+impl EntryWindow {
+    fn listed_len(&self) -> Option<usize> {
+        match self {
+            Self::Range(_) => None,
+            Self::Listed(indexes) => Some(indexes.len()),
+        }
+    }
+}
+
+impl Iterator for EntryWindow {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        match self {
+            Self::Range(range) => range.next(),
+            Self::Listed(indexes) => indexes.next(),
+        }
+    }
+}
+
+/// Builds the entry window and returns it with the number of listed titles not in the archive.
+pub(crate) fn entry_window(
+    zim: &Zim,
+    config: &Config,
+    patterns: &SelectionPatterns,
+    start: u32,
+    end: u32,
+) -> Result<(EntryWindow, u64)> {
+    if config.sample.size.is_some() {
+        if config.selection.title_list_path.is_some() {
+            anyhow::bail!("sample.size cannot be combined with selection.title_list_path");
+        }
+        if let Some(shard) = config.selection.shard {
+            anyhow::bail!("sample.size cannot be combined with shard {shard}");
+        }
+        let plan = plan_sample(zim, config, patterns, start, end)?;
+        return Ok((EntryWindow::Listed(plan.entry_indexes.into_iter()), 0));
+    }
+
+    let Some(path) = &config.selection.title_list_path else {
+        return Ok((EntryWindow::Range(start..end), 0));
+    };
+
+    let titles = load_title_list(path)?;
+    let resolution = resolve_title_list(zim, &titles, &config.selection.include_namespaces);
+    if !resolution.missing_titles.is_empty() {
+        warn!(
+            title_list = %path.display(),
+            missing_titles = resolution.missing_titles.len(),
+            sample = ?resolution.missing_titles.iter().take(10).collect::<Vec<_>>(),
+            "listed titles not found in archive"
+        );
+    }
+
+    let indexes = resolution
+        .entry_indexes
+        .into_iter()
+        .filter(|entry_index| (start..end).contains(entry_index))
+        .collect::<Vec<_>>();
+    info!(
+        title_list = %path.display(),
+        listed_titles = titles.len(),
+        resolved_entries = indexes.len(),
+        "resolved title list"
+    );
+
+    Ok((
+        EntryWindow::Listed(indexes.into_iter()),
+        resolution.missing_titles.len() as u64,
+    ))
+}
+
+/// Without a database there is no stored state to compare against, sweep, resolve into or
+/// checkpoint for shards.
+fn ensure_databaseless_settings(config: &Config) -> Result<()> {
+    if config.incremental.skip_unchanged {
+        anyhow::bail!("incremental convert needs the db sink");
+    }
+    if config.sweep.enabled {
+        anyhow::bail!("sweep needs the db sink");
+    }
+    if config.selection.redirect_mode() == RedirectMode::Alias {
+        anyhow::bail!("redirect_mode = \"alias\" needs the db sink");
+    }
+    if let Some(shard) = config.selection.shard {
+        anyhow::bail!("shard {shard} needs the db sink for its checkpoint");
+    }
+
+    Ok(())
+}
+
+/// A JSONL sink is rewritten on every run, so alongside the db sink it cannot pick up after a
+/// checkpoint or leave out the pages an incremental run skips.
+fn ensure_jsonl_settings(config: &Config) -> Result<()> {
+    let Some(path) = config.output.sinks.iter().find_map(|spec| match spec {
+        SinkSpec::Jsonl(path) => Some(path),
+        _ => None,
+    }) else {
+        return Ok(());
+    };
+
+    if config.incremental.skip_unchanged {
+        anyhow::bail!(
+            "jsonl:{} cannot be combined with incremental convert; unchanged pages would be missing from it",
+            path.display()
+        );
+    }
+    if config.checkpoint.enabled && config.checkpoint.resume {
+        anyhow::bail!(
+            "jsonl:{} cannot resume from a checkpoint; pass --no-resume to rewrite it from the start",
+            path.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Shards share one database, so each needs its own checkpoint and must not reset the schema or
+/// pick its own index window.
+fn ensure_shard_settings(config: &Config) -> Result<()> {
+    let Some(shard) = config.selection.shard else {
+        return Ok(());
+    };
+
+    if config.sample.size.is_some() {
+        anyhow::bail!("shard {shard} cannot be combined with sample.size");
+    }
+    if !config.checkpoint.enabled {
+        anyhow::bail!("shard {shard} requires checkpoint.enabled = true");
+    }
+    if config.sqlite.overwrite {
+        anyhow::bail!("shard {shard} cannot be combined with --overwrite");
+    }
+    if config.selection.start_index != 0 || config.selection.max_entries.is_some() {
+        anyhow::bail!("shard {shard} cannot be combined with start_index or max_entries");
+    }
+
+    Ok(())
+}
+
 fn spawn_workers(
     extraction_threads: usize,
     receiver: Receiver<WorkerJob>,
@@ -428,27 +1542,11 @@ fn spawn_workers(
         let worker_config = config.clone();
 
         handles.push(thread::spawn(move || {
-            loop {
-                let Ok(job) = rx.recv() else {
-                    break;
-                };
-
+            while let Ok(job) = rx.recv() {
                 match job {
                     WorkerJob::Shutdown => break,
                     WorkerJob::Html(job) => {
-                        let result = match build_page_from_html(job.meta, job.html, &worker_config)
-                        {
-                            Ok(page) => WorkerResult {
-                                entry_index: job.entry_index,
-                                page: Some(page),
-                                error: None,
-                            },
-                            Err(error) => WorkerResult {
-                                entry_index: job.entry_index,
-                                page: None,
-                                error: Some(error.to_string()),
-                            },
-                        };
+                        let result = run_html_job(job, &worker_config);
 
                         if tx.send(result).is_err() {
                             break;
@@ -504,15 +1602,145 @@ fn collect_worker_results(
     out
 }
 
-fn persist_page(db: &Database, page: &ExtractedPage, metrics: &mut RunMetrics) -> Result<()> {
-    if let Err(error) = db.upsert_page(page) {
+/// Resolves every queued redirect of this edition to its final content entry and attaches the
+/// redirect name to the stored target page. Runs after the sweep so only live pages get aliases.
+fn resolve_redirect_aliases(
+    zim: &Zim,
+    db: &Database,
+    config: &Config,
+    metrics: &mut RunMetrics,
+) -> Result<()> {
+    let edition = &config.input.edition;
+    let pending = db.load_pending_redirects(edition)?;
+    let max_hops = config.selection.max_redirect_hops.max(1);
+    let mut aliases = Vec::with_capacity(pending.len());
+
+    for &entry_index in &pending {
+        let resolved = zim
+            .get_by_url_index(entry_index)
+            .map_err(anyhow::Error::from)
+            .and_then(|redirect| {
+                let target = resolve_redirect_chain(zim, entry_index, &redirect, max_hops)?;
+                Ok((redirect, target))
+            });
+        let (redirect, target) = match resolved {
+            Ok(resolved) => resolved,
+            Err(error) => {
+                metrics.unresolved_redirects += 1;
+                warn!(entry_index, error = %error, "redirect could not be resolved");
+                continue;
+            }
+        };
+
+        let alias = if redirect.title.trim().is_empty() {
+            redirect.url
+        } else {
+            redirect.title
+        };
+        let normalized_alias = canonicalize_lemma(&alias);
+        if normalized_alias.len() < config.extraction.alias_min_length {
+            continue;
+        }
+
+        aliases.push(RedirectAlias {
+            target_url: target.url,
+            alias,
+            normalized_alias,
+        });
+    }
+
+    let inserted = db.insert_redirect_aliases(edition, &aliases)?;
+    metrics.redirect_aliases = inserted.inserted_aliases;
+    metrics.unresolved_redirects += inserted.missing_targets;
+    db.clear_pending_redirects(edition)?;
+
+    info!(
+        pending_redirects = pending.len(),
+        redirect_aliases = inserted.inserted_aliases,
+        missing_targets = inserted.missing_targets,
+        unresolved_redirects = metrics.unresolved_redirects,
+        "resolved redirect aliases"
+    );
+
+    Ok(())
+}
+
+/// Follows a redirect chain to the first non-redirect entry. Cycles and chains longer than
+/// `max_hops` are errors.
+fn resolve_redirect_chain(
+    zim: &Zim,
+    entry_index: u32,
+    redirect: &DirectoryEntry,
+    max_hops: usize,
+) -> Result<DirectoryEntry> {
+    let Some(Target::Redirect(mut next_index)) = redirect.target else {
+        anyhow::bail!("entry is not a redirect");
+    };
+    let mut visited = HashSet::from([entry_index]);
+
+    for _ in 0..max_hops {
+        if !visited.insert(next_index) {
+            anyhow::bail!("redirect cycle through entry index {next_index}");
+        }
+
+        let entry = zim.get_by_url_index(next_index)?;
+        match entry.target {
+            Some(Target::Redirect(following)) => next_index = following,
+            Some(Target::Cluster(_, _)) => return Ok(entry),
+            None => anyhow::bail!("redirect target {next_index} has no payload"),
+        }
+    }
+
+    anyhow::bail!("redirect chain exceeds {max_hops} hops")
+}
+
+fn persist_page(
+    sink: &mut dyn PageSink,
+    db: Option<&Database>,
+    page: &ExtractedPage,
+    lineage: PageLineage,
+    metrics: &mut RunMetrics,
+    seen_pages: &mut Option<SeenPages>,
+) -> Result<()> {
+    let started = Instant::now();
+    let written = sink.write_page(page, &lineage);
+    let elapsed = started.elapsed();
+    metrics.stage_timings.persist += elapsed;
+    if db.is_some() {
+        telemetry::global().observe_db_write("page", elapsed);
+    }
+
+    if let Err(error) = &written
+        && let Some(partial) = error.downcast_ref::<PartialWrite>()
+    {
+        metrics.partial_sink_writes += 1;
+        warn!(
+            url = %page.url,
+            stored_in = ?partial.stored_in,
+            error = %partial,
+            "page missing from some sinks"
+        );
+    } else if let Err(error) = written {
         metrics.extraction_errors += 1;
+        let error_chain = format!("{error:#}");
         warn!(
+            sink = sink.name(),
             error = %error,
-            error_chain = %format!("{error:#}"),
-            "database upsert failed"
+            error_chain = %error_chain,
+            "page write failed"
         );
-        return Ok(());
+        if let Some(entry_index) = lineage.entry_index {
+            quarantine(
+                db,
+                &page.edition,
+                lineage.run_id,
+                entry_index,
+                Some(&page.url),
+                FailureStage::Upsert,
+                error_chain,
+            );
+        }
+        return touch_seen(seen_pages, page.url.clone());
     }
 
     metrics.ingested_pages += 1;
@@ -522,12 +1750,277 @@ fn persist_page(db: &Database, page: &ExtractedPage, metrics: &mut RunMetrics) -
     Ok(())
 }
 
-fn build_page_from_html(meta: HtmlJobMeta, html: String, config: &Config) -> Result<ExtractedPage> {
-    let content_sha256 = Some(sha256_hex(&html));
+fn flush_sink(sink: &mut dyn PageSink, db: Option<&Database>) -> Result<()> {
+    let started = Instant::now();
+    sink.flush()?;
+    if db.is_some() {
+        telemetry::global().observe_db_write("flush", started.elapsed());
+    }
+    Ok(())
+}
+
+/// Saves a convert checkpoint and reports its latency and age to the metrics registry.
+fn save_checkpoint(db: &Database, checkpoint_name: &str, state: &CheckpointState) -> Result<()> {
+    let started = Instant::now();
+    db.save_checkpoint(checkpoint_name, state)?;
+    let registry = telemetry::global();
+    registry.observe_db_write("checkpoint", started.elapsed());
+    registry.checkpoint_saved(checkpoint_name);
+    Ok(())
+}
+
+fn touch_seen(seen_pages: &mut Option<SeenPages>, url: String) -> Result<()> {
+    match seen_pages {
+        Some(seen_pages) => seen_pages.touch(url),
+        None => Ok(()),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum FailureStage {
+    DirectoryEntry,
+    Cluster,
+    Blob,
+    Extract,
+    Guard,
+    Upsert,
+}
+
+impl FailureStage {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::DirectoryEntry => "directory_entry",
+            Self::Cluster => "cluster",
+            Self::Blob => "blob",
+            Self::Extract => "extract",
+            Self::Guard => "guard",
+            Self::Upsert => "upsert",
+        }
+    }
+}
+
+/// Records a failed entry for `retry-failed`. A failure to record is logged, never fatal, and
+/// without a database the caller's warning is the only record.
+fn quarantine(
+    db: Option<&Database>,
+    edition: &str,
+    run_id: Option<i64>,
+    entry_index: u32,
+    url: Option<&str>,
+    stage: FailureStage,
+    error_chain: String,
+) {
+    let Some(db) = db else {
+        return;
+    };
+
+    let failure = IngestionFailure {
+        edition: edition.to_owned(),
+        entry_index,
+        url: url.map(ToOwned::to_owned),
+        stage: stage.as_str().to_owned(),
+        error_chain,
+        run_id,
+        attempts: 1,
+    };
+
+    if let Err(error) = db.record_failure(&failure) {
+        warn!(
+            entry_index,
+            stage = stage.as_str(),
+            error = %error,
+            "failed to record ingestion failure"
+        );
+    }
+}
+
+fn build_redirect_page(
+    zim: &Zim,
+    entry: DirectoryEntry,
+    redirect_idx: u32,
+    config: &Config,
+) -> ExtractedPage {
+    let redirect_url = zim.get_by_url_index(redirect_idx).ok().map(|e| e.url);
+    let url = entry.url;
+    let title = if entry.title.trim().is_empty() {
+        redirect_url.clone().unwrap_or_else(|| url.clone())
+    } else {
+        entry.title
+    };
+
+    ExtractedPage {
+        edition: config.input.edition.clone(),
+        url,
+        title,
+        namespace: namespace_code(entry.namespace).to_owned(),
+        mime_type: mime_type_label(&entry.mime_type),
+        cluster_idx: None,
+        blob_idx: None,
+        redirect_url,
+        content_sha256: None,
+        raw_html: None,
+        plain_text: None,
+        extraction_confidence: 0.0,
+        definitions: Vec::new(),
+        relations: Vec::new(),
+        aliases: Vec::new(),
+    }
+}
+
+fn html_job_meta(
+    entry: DirectoryEntry,
+    cluster_idx: u32,
+    blob_idx: u32,
+    content_sha256: Option<String>,
+) -> HtmlJobMeta {
+    let title = if entry.title.trim().is_empty() {
+        entry.url.clone()
+    } else {
+        entry.title
+    };
+
+    HtmlJobMeta {
+        url: entry.url,
+        title,
+        namespace: namespace_code(entry.namespace).to_owned(),
+        mime_type: mime_type_label(&entry.mime_type),
+        cluster_idx,
+        blob_idx,
+        content_sha256,
+    }
+}
+
+/// Extracts one HTML entry under `extraction.guards`. A panic inside extraction becomes a
+/// failed outcome instead of taking down the worker or the run.
+fn extract_html_entry(meta: HtmlJobMeta, html: String, config: &Config) -> HtmlOutcome {
+    let guards = &config.extraction.guards;
+    if let Some(trip) = skipped_before_parsing(guards, &html) {
+        return HtmlOutcome::Skipped {
+            url: meta.url,
+            trips: vec![trip],
+        };
+    }
+
+    let url = meta.url.clone();
+    match panic::catch_unwind(AssertUnwindSafe(|| {
+        build_page_from_html(meta, html, config)
+    })) {
+        Ok(Ok((_, trips))) if !trips.is_empty() && guards.on_exceed == GuardAction::Skip => {
+            HtmlOutcome::Skipped { url, trips }
+        }
+        Ok(Ok((page, trips))) => HtmlOutcome::Page(Box::new(page), trips),
+        Ok(Err(error)) => HtmlOutcome::Failed {
+            url,
+            error_chain: format!("{error:#}"),
+            panicked: false,
+        },
+        Err(payload) => HtmlOutcome::Failed {
+            url,
+            error_chain: format!("extraction panicked: {}", panic_message(payload.as_ref())),
+            panicked: true,
+        },
+    }
+}
+
+/// The trip that makes `guards` skip `html` before it is parsed, if any.
+pub(crate) fn skipped_before_parsing(guards: &PageGuardConfig, html: &str) -> Option<GuardTrip> {
+    (guards.on_exceed == GuardAction::Skip
+        && guards.max_html_bytes > 0
+        && html.len() > guards.max_html_bytes)
+        .then_some(GuardTrip::HtmlBytes {
+            limit: guards.max_html_bytes,
+            actual: html.len(),
+        })
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("non-string panic payload")
+}
+
+fn run_html_job(job: HtmlJob, config: &Config) -> WorkerResult {
+    let started = Instant::now();
+    let outcome = extract_html_entry(job.meta, job.html, config);
+    WorkerResult {
+        entry_index: job.entry_index,
+        outcome,
+        extract_time: started.elapsed(),
+    }
+}
+
+/// Counts extraction time, guard trips, skips and panics in `metrics` and logs why, then hands
+/// back the page to persist or the failure to quarantine.
+fn tally_html_outcome(
+    result: WorkerResult,
+    metrics: &mut RunMetrics,
+) -> std::result::Result<ExtractedPage, EntryFailure> {
+    let WorkerResult {
+        entry_index,
+        outcome,
+        extract_time,
+    } = result;
+    metrics.stage_timings.extract += extract_time;
+
+    match &outcome {
+        HtmlOutcome::Page(page, trips) if !trips.is_empty() => {
+            metrics.guard_truncated_pages += 1;
+            warn!(
+                entry_index,
+                url = %page.url,
+                reason = %join_trips(trips),
+                "page truncated by extraction guards"
+            );
+        }
+        HtmlOutcome::Page(..) => {}
+        HtmlOutcome::Skipped { url, trips } => {
+            metrics.guard_skipped_pages += 1;
+            warn!(
+                entry_index,
+                url = %url,
+                reason = %join_trips(trips),
+                "page skipped by extraction guards"
+            );
+        }
+        HtmlOutcome::Failed {
+            url,
+            error_chain,
+            panicked,
+        } => {
+            metrics.extraction_errors += 1;
+            if *panicked {
+                metrics.extraction_panics += 1;
+            }
+            warn!(entry_index, url = %url, error = %error_chain, "entry extraction failed");
+        }
+    }
+
+    outcome.into_result()
+}
+
+fn join_trips(trips: &[GuardTrip]) -> String {
+    trips
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn build_page_from_html(
+    meta: HtmlJobMeta,
+    html: String,
+    config: &Config,
+) -> Result<(ExtractedPage, Vec<GuardTrip>)> {
+    let content_sha256 = meta.content_sha256.unwrap_or_else(|| sha256_hex(&html));
     let extraction = extract_from_html(&meta.title, &html, &config.extraction);
+    // A truncated page stays without a hash so incremental runs keep retrying it.
+    let content_sha256 = extraction.guard_trips.is_empty().then_some(content_sha256);
     let raw_html = config.extraction.store_raw_html.then_some(html);
 
-    Ok(ExtractedPage {
+    let page = ExtractedPage {
+        edition: config.input.edition.clone(),
         url: meta.url,
         title: meta.title,
         namespace: meta.namespace,
@@ -542,10 +2035,11 @@ fn build_page_from_html(meta: HtmlJobMeta, html: String, config: &Config) -> Res
         definitions: extraction.definitions,
         relations: extraction.relations,
         aliases: extraction.aliases,
-    })
+    };
+    Ok((page, extraction.guard_trips))
 }
 
-fn ensure_input_exists(path: &Path) -> Result<()> {
+pub(crate) fn ensure_input_exists(path: &Path) -> Result<()> {
     if path.exists() {
         return Ok(());
     }
@@ -560,67 +2054,124 @@ fn unix_now_ms() -> Result<u128> {
     Ok(value.as_millis())
 }
 
-fn should_select_entry(entry: &DirectoryEntry, config: &Config) -> bool {
+pub(crate) fn should_select_entry(
+    entry: &DirectoryEntry,
+    config: &Config,
+    patterns: &SelectionPatterns,
+) -> bool {
+    let Some(rule) = selection_filter(entry, config, patterns) else {
+        return true;
+    };
+
+    debug!(
+        rule = rule.as_str(),
+        url = %entry.url,
+        title = %entry.title,
+        mime = %mime_type_label(&entry.mime_type),
+        "entry filtered"
+    );
+    false
+}
+
+/// Selection rules in the order [`selection_filter`] applies them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FilterRule {
+    Namespace,
+    EmptyTitle,
+    UrlPrefix,
+    TitlePrefix,
+    UrlPattern,
+    TitlePattern,
+    MimeType,
+}
+
+impl FilterRule {
+    pub const ALL: [Self; 7] = [
+        Self::Namespace,
+        Self::EmptyTitle,
+        Self::UrlPrefix,
+        Self::TitlePrefix,
+        Self::UrlPattern,
+        Self::TitlePattern,
+        Self::MimeType,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Namespace => "namespace",
+            Self::EmptyTitle => "empty_title",
+            Self::UrlPrefix => "url_prefix",
+            Self::TitlePrefix => "title_prefix",
+            Self::UrlPattern => "url_pattern",
+            Self::TitlePattern => "title_pattern",
+            Self::MimeType => "mime_type",
+        }
+    }
+}
+
+/// The first selection rule that rejects `entry`, or `None` when it is selected.
+pub(crate) fn selection_filter(
+    entry: &DirectoryEntry,
+    config: &Config,
+    patterns: &SelectionPatterns,
+) -> Option<FilterRule> {
+    let selection = &config.selection;
     let resolved_title = entry_title_or_url(entry);
 
     let namespace = namespace_code(entry.namespace);
-    if !config.selection.include_namespaces.is_empty()
-        && !config
-            .selection
+    if !selection.include_namespaces.is_empty()
+        && !selection
             .include_namespaces
             .iter()
             .any(|item| item == namespace)
     {
-        debug!(namespace, title = %entry.title, "filtered by namespace");
-        return false;
+        return Some(FilterRule::Namespace);
     }
 
-    if config.selection.require_title && resolved_title.trim().is_empty() {
-        debug!(
-            url = %entry.url,
-            raw_title = %entry.title,
-            "filtered due to empty resolved title"
-        );
-        return false;
+    if selection.require_title && resolved_title.trim().is_empty() {
+        return Some(FilterRule::EmptyTitle);
     }
 
-    if config
-        .selection
+    if selection
         .exclude_url_prefixes
         .iter()
         .any(|prefix| entry.url.starts_with(prefix))
     {
-        debug!(url = %entry.url, "filtered by url prefix");
-        return false;
+        return Some(FilterRule::UrlPrefix);
     }
 
-    if config
-        .selection
+    if selection
         .exclude_title_prefixes
         .iter()
         .any(|prefix| resolved_title.starts_with(prefix))
     {
-        debug!(
-            title = %resolved_title,
-            raw_title = %entry.title,
-            "filtered by title prefix"
-        );
-        return false;
+        return Some(FilterRule::TitlePrefix);
     }
 
-    let mime = mime_type_label(&entry.mime_type);
-    if !config.selection.include_mime_prefixes.is_empty()
-        && !config
-            .selection
+    if !patterns.allows_url(&entry.url) {
+        return Some(FilterRule::UrlPattern);
+    }
+
+    if !patterns.allows_title(resolved_title) {
+        return Some(FilterRule::TitlePattern);
+    }
+
+    // Alias mode folds redirects into their targets, so the MIME filter applies to the target.
+    // Otherwise redirects are matched as `redirect`, as before redirect modes existed.
+    let alias_redirect = matches!(entry.mime_type, MimeType::Redirect)
+        && selection.redirect_mode() == RedirectMode::Alias;
+    if !alias_redirect && !selection.include_mime_prefixes.is_empty() {
+        let mime = mime_type_label(&entry.mime_type);
+        if !selection
             .include_mime_prefixes
             .iter()
             .any(|prefix| mime.starts_with(prefix))
-    {
-        debug!(mime, "filtered by mime type");
-        return false;
+        {
+            return Some(FilterRule::MimeType);
+        }
     }
 
-    true
+    None
 }
 
 fn entry_title_or_url(entry: &DirectoryEntry) -> &str {
@@ -635,9 +2186,16 @@ fn entry_title_or_url(entry: &DirectoryEntry) -> &str {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use zim::{MimeType, Namespace};
+    use rusqlite::Connection;
+    use tempfile::tempdir;
+    use zim::Namespace;
 
-    use crate::config::Config;
+    use crate::config::{Config, GuardAction, SampleStratify, StorageBackend, SweepPolicy};
+    use crate::db::IngestionFailure;
+    use crate::query::Dictionary;
+    use crate::search::SearchQuery;
+    use crate::sink::{DatabaseSink, SinkSpec, TeeSink};
+    use crate::test_zim::{FixtureEntry, write_test_zim};
 
     fn base_entry() -> DirectoryEntry {
         DirectoryEntry {
@@ -654,7 +2212,36 @@ mod tests {
     fn require_title_accepts_url_fallback_when_title_is_empty() {
         let cfg = Config::default();
         let entry = base_entry();
-        assert!(should_select_entry(&entry, &cfg));
+        assert!(should_select_entry(
+            &entry,
+            &cfg,
+            &SelectionPatterns::default()
+        ));
+    }
+
+    #[test]
+    fn mime_filter_exempts_redirects_only_in_alias_mode() {
+        let mut cfg = Config::default();
+        cfg.selection.skip_redirects = false;
+        let redirect = DirectoryEntry {
+            mime_type: MimeType::Redirect,
+            ..base_entry()
+        };
+        let patterns = SelectionPatterns::default();
+        assert_eq!(cfg.selection.redirect_mode(), RedirectMode::Page);
+        assert_eq!(
+            selection_filter(&redirect, &cfg, &patterns),
+            Some(FilterRule::MimeType)
+        );
+
+        cfg.selection
+            .include_mime_prefixes
+            .push("redirect".to_owned());
+        assert_eq!(selection_filter(&redirect, &cfg, &patterns), None);
+
+        cfg.selection.include_mime_prefixes = vec!["text/html".to_owned()];
+        cfg.selection.redirect_mode = Some(RedirectMode::Alias);
+        assert_eq!(selection_filter(&redirect, &cfg, &patterns), None);
     }
 
     #[test]
@@ -662,6 +2249,591 @@ mod tests {
         let mut cfg = Config::default();
         cfg.selection.exclude_title_prefixes = vec!["bed".to_owned()];
         let entry = base_entry();
-        assert!(!should_select_entry(&entry, &cfg));
+        assert!(!should_select_entry(
+            &entry,
+            &cfg,
+            &SelectionPatterns::default()
+        ));
+    }
+
+    fn fixture_config(workdir: &Path, entries: &[FixtureEntry]) -> Config {
+        let zim_path = workdir.join("fixture.zim");
+        write_test_zim(&zim_path, entries);
+
+        let mut config = Config {
+            backend: StorageBackend::Sqlite,
+            ..Config::default()
+        };
+        config.input.zim_path = zim_path;
+        config.input.sqlite_path = workdir.join("fixture.sqlite");
+        config.workers.extraction_threads = 1;
+        config.extraction.min_definition_chars = 1;
+        config
+    }
+
+    const ENTRY_HTML: &str = "<h2>English</h2><h3>Noun</h3><ol><li>A sample definition.</li></ol>";
+
+    #[test]
+    fn retry_failed_recovers_and_clears_quarantined_entries() {
+        let workdir = tempdir().expect("tempdir");
+        let mut config = fixture_config(
+            workdir.path(),
+            &[
+                FixtureEntry::article("alpha", ENTRY_HTML),
+                FixtureEntry::article("beta", ENTRY_HTML),
+                FixtureEntry::redirect("gamma", "beta"),
+                FixtureEntry::metadata("Date", "2026-02-01"),
+            ],
+        );
+        config.selection.redirect_mode = Some(RedirectMode::Alias);
+
+        let converted = run_conversion(&config).expect("convert");
+        assert_eq!(converted[0].ingested_pages, 2);
+        assert_eq!(converted[0].redirect_aliases, 1);
+
+        let db = Database::open(&config).expect("open db");
+        for (entry_index, stage) in [(1, "upsert"), (2, "upsert"), (3, "extract")] {
+            db.record_failure(&IngestionFailure {
+                edition: "en".to_owned(),
+                entry_index,
+                url: None,
+                stage: stage.to_owned(),
+                error_chain: "simulated".to_owned(),
+                run_id: converted[0].run_id,
+                attempts: 1,
+            })
+            .expect("record failure");
+        }
+        drop(db);
+
+        let conn = Connection::open(&config.input.sqlite_path).expect("open sqlite");
+        conn.execute("DELETE FROM pages WHERE url = 'beta'", [])
+            .expect("drop beta");
+        drop(conn);
+
+        let retried = retry_failed(&config).expect("retry");
+        assert_eq!(retried[0].attempted, 3);
+        assert_eq!(retried[0].recovered, 2);
+        assert_eq!(retried[0].dropped, 1);
+        assert_eq!(retried[0].still_failing, 0);
+
+        let conn = Connection::open(&config.input.sqlite_path).expect("reopen sqlite");
+        let (beta_run, beta_entry): (i64, i64) = conn
+            .query_row(
+                "SELECT written_run_id, zim_entry_index FROM pages WHERE url = 'beta'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .expect("beta restored");
+        assert_eq!(Some(beta_run), retried[0].run_id);
+        assert_eq!(beta_entry, 1);
+        let remaining: i64 = conn
+            .query_row("SELECT COUNT(*) FROM ingestion_failures", [], |row| {
+                row.get(0)
+            })
+            .expect("count failures");
+        assert_eq!(remaining, 0);
+        let redirect_alias: String = conn
+            .query_row(
+                "SELECT p.url FROM lemma_aliases a JOIN pages p ON p.id = a.page_id WHERE a.source = 'redirect' AND a.alias = 'gamma'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("redirect alias restored");
+        assert_eq!(redirect_alias, "beta");
+        let zim_date: Option<String> = conn
+            .query_row("SELECT zim_date FROM source_archives", [], |row| row.get(0))
+            .expect("archive row");
+        assert_eq!(zim_date.as_deref(), Some("2026-02-01"));
+    }
+
+    #[test]
+    fn page_stream_matches_sequential_extraction_in_order() {
+        let workdir = tempdir().expect("tempdir");
+        let mut entries = (0..12)
+            .map(|idx| FixtureEntry::article(&format!("word{idx:02}"), ENTRY_HTML))
+            .collect::<Vec<_>>();
+        entries.push(FixtureEntry::redirect("word99", "word03"));
+        entries.push(FixtureEntry::metadata("Date", "2026-02-01"));
+        let mut config = fixture_config(workdir.path(), &entries);
+        config.selection.redirect_mode = Some(RedirectMode::Page);
+        config
+            .selection
+            .include_mime_prefixes
+            .push("redirect".to_owned());
+
+        let sequential = PageStream::open(&config)
+            .expect("open stream")
+            .map(|item| item.expect("page").entry_index)
+            .collect::<Vec<_>>();
+        assert_eq!(sequential, (0..13).collect::<Vec<_>>());
+
+        config.workers.extraction_threads = 4;
+        config.workers.queue_capacity = 4;
+        let ordered = PageStream::open(&config)
+            .expect("open stream")
+            .preserve_order(true)
+            .map(|item| item.expect("page"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ordered
+                .iter()
+                .map(|page| page.entry_index)
+                .collect::<Vec<_>>(),
+            sequential
+        );
+        assert_eq!(ordered[12].page.redirect_url.as_deref(), Some("word03"));
+        assert_eq!(ordered[0].page.definitions.len(), 1);
+
+        let mut unordered = PageStream::open(&config)
+            .expect("open stream")
+            .map(|item| item.expect("page").entry_index)
+            .collect::<Vec<_>>();
+        unordered.sort_unstable();
+        assert_eq!(unordered, sequential);
+
+        let mut partial = PageStream::open(&config).expect("open stream");
+        assert!(partial.next().is_some());
+        drop(partial);
+    }
+
+    #[test]
+    fn title_list_and_patterns_narrow_the_visited_entries() {
+        let workdir = tempdir().expect("tempdir");
+        let entries = ["apple", "banana", "cherry", "damson", "elder"]
+            .iter()
+            .map(|word| FixtureEntry::article(word, ENTRY_HTML))
+            .collect::<Vec<_>>();
+        let mut config = fixture_config(workdir.path(), &entries);
+
+        let list_path = workdir.path().join("words.txt");
+        std::fs::write(&list_path, "damson\nbanana\nelder\nfig\n").expect("write list");
+        config.selection.title_list_path = Some(list_path);
+        config.selection.exclude_title_patterns = vec!["^e".to_owned()];
+
+        let metrics = run_conversion(&config).expect("convert");
+        assert_eq!(metrics[0].scanned_entries, 3);
+        assert_eq!(metrics[0].filtered_entries, 1);
+        assert_eq!(metrics[0].ingested_pages, 2);
+        assert_eq!(metrics[0].missing_list_titles, 1);
+
+        let conn = Connection::open(&config.input.sqlite_path).expect("open sqlite");
+        let mut stmt = conn
+            .prepare("SELECT url FROM pages ORDER BY url")
+            .expect("prepare");
+        let urls = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .expect("query")
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .expect("rows");
+        assert_eq!(urls, vec!["banana", "damson"]);
+    }
+
+    #[test]
+    fn jsonl_sink_converts_without_a_database() {
+        let workdir = tempdir().expect("tempdir");
+        let mut config = fixture_config(
+            workdir.path(),
+            &[
+                FixtureEntry::article("alpha", ENTRY_HTML),
+                FixtureEntry::article("beta", ENTRY_HTML),
+                FixtureEntry::redirect("gamma", "beta"),
+            ],
+        );
+        let jsonl_path = workdir.path().join("out/pages.jsonl");
+        config.output.sinks = vec![SinkSpec::Jsonl(jsonl_path.clone()), SinkSpec::Null];
+
+        let metrics = run_conversion(&config).expect("convert");
+        assert_eq!(metrics[0].ingested_pages, 2);
+        assert_eq!(metrics[0].run_id, None);
+        assert!(!config.input.sqlite_path.exists());
+
+        let lines = std::fs::read_to_string(&jsonl_path).expect("read jsonl");
+        let pages = lines
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).expect("json line"))
+            .collect::<Vec<_>>();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0]["url"], "alpha");
+        assert_eq!(pages[0]["zim_entry_index"], 0);
+        assert_eq!(pages[1]["definitions"][0]["text"], "A sample definition.");
+
+        config.selection.redirect_mode = Some(RedirectMode::Alias);
+        assert!(run_conversion(&config).is_err());
+    }
+
+    struct FailingSink;
+
+    impl PageSink for FailingSink {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        fn write_page(&mut self, _page: &ExtractedPage, _lineage: &PageLineage) -> Result<()> {
+            anyhow::bail!("disk full")
+        }
+    }
+
+    #[test]
+    fn tee_reports_partial_writes_without_quarantining_stored_pages() {
+        let workdir = tempdir().expect("tempdir");
+        let mut config = fixture_config(
+            workdir.path(),
+            &[
+                FixtureEntry::article("alpha", ENTRY_HTML),
+                FixtureEntry::article("beta", ENTRY_HTML),
+            ],
+        );
+        let jsonl_path = workdir.path().join("pages.jsonl");
+        config.output.sinks = vec![SinkSpec::Jsonl(jsonl_path.clone()), SinkSpec::Database];
+        assert!(
+            run_conversion(&config).is_err(),
+            "resume would truncate jsonl"
+        );
+        config.checkpoint.resume = false;
+        config.incremental.skip_unchanged = true;
+        assert!(
+            run_conversion(&config).is_err(),
+            "incremental skips jsonl pages"
+        );
+        config.incremental.skip_unchanged = false;
+        assert!(!jsonl_path.exists());
+
+        let db = Database::open(&config).expect("open db");
+        db.init_schema().expect("init schema");
+        let mut sink = TeeSink::new(vec![
+            Box::new(DatabaseSink::new(&db)),
+            Box::new(FailingSink),
+        ]);
+        let metrics = run_conversion_with_sink(&config, &mut sink, Some(&db)).expect("convert");
+        assert_eq!(metrics[0].ingested_pages, 2);
+        assert_eq!(metrics[0].partial_sink_writes, 2);
+        assert_eq!(metrics[0].extraction_errors, 0);
+        drop(sink);
+        drop(db);
+
+        let conn = Connection::open(&config.input.sqlite_path).expect("open sqlite");
+        let (pages, failures): (i64, i64) = conn
+            .query_row(
+                "SELECT (SELECT COUNT(*) FROM pages), (SELECT COUNT(*) FROM ingestion_failures)",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .expect("count rows");
+        assert_eq!((pages, failures), (2, 0));
+    }
+
+    #[test]
+    fn convert_reports_throughput_and_writes_a_final_status_file() {
+        let workdir = tempdir().expect("tempdir");
+        let mut config = fixture_config(
+            workdir.path(),
+            &[
+                FixtureEntry::article("alpha", ENTRY_HTML),
+                FixtureEntry::article("beta", ENTRY_HTML),
+                FixtureEntry::metadata("Title", "fixture"),
+            ],
+        );
+        let status_path = workdir.path().join("out/status.json");
+        config.input.edition = "progress-fixture".to_owned();
+        config.logging.status_file = Some(status_path.clone());
+        config.output.sinks = vec![SinkSpec::Null];
+
+        let metrics = run_conversion(&config).expect("convert");
+        assert_eq!(metrics[0].ingested_pages, 2);
+        assert!(metrics[0].decompressed_bytes >= 2 * ENTRY_HTML.len() as u64);
+        assert!(metrics[0].stage_timings.extract > Duration::ZERO);
+
+        let status: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&status_path).expect("read status"))
+                .expect("status json");
+        assert_eq!(status["state"], "complete");
+        assert_eq!(status["edition"], "progress-fixture");
+        assert_eq!(status["window_entries"], 3);
+        assert_eq!(status["scanned_entries"], 3);
+        assert_eq!(status["ingested_pages"], 2);
+        assert_eq!(status["eta_secs"], 0.0);
+        assert!(
+            status["stage_secs"]["extract"]
+                .as_f64()
+                .expect("extract secs")
+                > 0.0
+        );
+
+        let exposition = telemetry::global().render();
+        assert!(
+            exposition.contains("zimrs_ingested_pages_total{edition=\"progress-fixture\"} 2\n")
+        );
+        assert!(exposition.contains("zimrs_queue_depth{queue=\"extraction\"} "));
+    }
+
+    #[test]
+    fn reverse_lookup_follows_reconverts_with_fts_disabled() {
+        let workdir = tempdir().expect("tempdir");
+        let mut config = fixture_config(
+            workdir.path(),
+            &[FixtureEntry::article(
+                "tabby",
+                "<h2>English</h2><h3>Noun</h3><ol><li>A gossiping old woman.</li></ol>",
+            )],
+        );
+        config.sqlite.enable_fts = false;
+        config.checkpoint.resume = false;
+        run_conversion(&config).expect("convert");
+
+        write_test_zim(
+            &config.input.zim_path,
+            &[FixtureEntry::article(
+                "tabby",
+                "<h2>English</h2><h3>Noun</h3><ol><li>A striped domestic cat.</li></ol>",
+            )],
+        );
+        run_conversion(&config).expect("reconvert");
+
+        let dictionary = Dictionary::open(&config).expect("open dictionary");
+        let reverse = |text: &str| {
+            dictionary
+                .reverse_lookup(&SearchQuery::new(text))
+                .expect("reverse lookup")
+        };
+        assert!(reverse("gossiping woman").is_empty());
+        let striped = reverse("striped cat");
+        assert_eq!(striped.len(), 1);
+        assert_eq!(striped[0].title, "tabby");
+
+        let conn = Connection::open(&config.input.sqlite_path).expect("open sqlite");
+        let search_rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM page_fts", [], |row| row.get(0))
+            .expect("count page_fts");
+        assert_eq!(search_rows, 0);
+    }
+
+    #[test]
+    fn narrowing_the_selection_sweeps_nothing() {
+        let workdir = tempdir().expect("tempdir");
+        let mut config = fixture_config(
+            workdir.path(),
+            &[
+                FixtureEntry::article("alpha", ENTRY_HTML),
+                FixtureEntry::article("beta", ENTRY_HTML),
+                FixtureEntry::redirect("gamma", "beta"),
+            ],
+        );
+        config.selection.redirect_mode = Some(RedirectMode::Page);
+        config
+            .selection
+            .include_mime_prefixes
+            .push("redirect".to_owned());
+        config.checkpoint.resume = false;
+        config.sweep.enabled = true;
+        config.sweep.policy = SweepPolicy::Delete;
+        assert_eq!(
+            run_conversion(&config).expect("convert")[0].ingested_pages,
+            3
+        );
+
+        config.selection.exclude_url_patterns = vec!["^beta$".to_owned()];
+        config.selection.redirect_mode = Some(RedirectMode::Skip);
+        let narrowed = run_conversion(&config).expect("narrowed convert");
+        assert_eq!(narrowed[0].ingested_pages, 1);
+        assert_eq!(narrowed[0].filtered_entries, 2);
+        assert_eq!(narrowed[0].swept_pages, 0);
+
+        let conn = Connection::open(&config.input.sqlite_path).expect("open sqlite");
+        let live: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM pages WHERE deleted_at IS NULL",
+                [],
+                |row| row.get(0),
+            )
+            .expect("count pages");
+        assert_eq!(live, 3);
+    }
+
+    #[test]
+    fn guard_skips_are_counted_quarantined_and_retryable() {
+        let workdir = tempdir().expect("tempdir");
+        let huge_html = format!("{ENTRY_HTML}{}", "<p>padding</p>".repeat(64));
+        let mut config = fixture_config(
+            workdir.path(),
+            &[
+                FixtureEntry::article("alpha", ENTRY_HTML),
+                FixtureEntry::article("huge", &huge_html),
+            ],
+        );
+        config.extraction.guards.max_html_bytes = ENTRY_HTML.len() + 16;
+        config.extraction.guards.on_exceed = GuardAction::Skip;
+
+        let skipped = run_conversion(&config).expect("convert");
+        assert_eq!(skipped[0].ingested_pages, 1);
+        assert_eq!(skipped[0].guard_skipped_pages, 1);
+        assert_eq!(skipped[0].extraction_errors, 0);
+
+        let db = Database::open(&config).expect("open db");
+        let failures = db.load_failures("en").expect("load failures");
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].stage, "guard");
+        assert!(failures[0].error_chain.contains("byte limit"));
+        drop(db);
+
+        config.extraction.guards.on_exceed = GuardAction::Truncate;
+        config.checkpoint.resume = false;
+        let truncated = run_conversion(&config).expect("convert");
+        assert_eq!(truncated[0].ingested_pages, 2);
+        assert_eq!(truncated[0].guard_truncated_pages, 1);
+
+        config.incremental.skip_unchanged = true;
+        let incremental = run_conversion(&config).expect("incremental convert");
+        assert_eq!(
+            incremental[0].unchanged_pages, 1,
+            "only alpha is up to date"
+        );
+        assert_eq!(incremental[0].ingested_pages, 1);
+        let conn = Connection::open(&config.input.sqlite_path).expect("open sqlite");
+        let huge_hash: Option<String> = conn
+            .query_row(
+                "SELECT content_sha256 FROM pages WHERE url = 'huge'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("huge page");
+        assert_eq!(huge_hash, None);
+        drop(conn);
+        config.incremental.skip_unchanged = false;
+
+        config.extraction.guards.max_html_bytes = 0;
+        let retried = retry_failed(&config).expect("retry");
+        assert_eq!(retried[0].recovered, 1);
+    }
+
+    #[test]
+    fn sample_run_writes_a_reproducible_language_stratified_sample() {
+        let workdir = tempdir().expect("tempdir");
+        let french_html = "<h2>French</h2><h3>Noun</h3><ol><li>Une définition.</li></ol>";
+        let mut entries = (0..8)
+            .map(|i| FixtureEntry::article(&format!("en{i}"), ENTRY_HTML))
+            .collect::<Vec<_>>();
+        entries.push(FixtureEntry::article("fr0", french_html));
+        entries.push(FixtureEntry::article("fr1", french_html));
+        let mut config = fixture_config(workdir.path(), &entries);
+        config.sample.size = Some(4);
+        config.sample.seed = 11;
+        config.sample.stratify = SampleStratify::Language;
+        config.sample.sqlite_path = workdir.path().join("sample.sqlite");
+
+        let sampled_urls = |config: &Config| {
+            let metrics = run_conversion(config).expect("sample convert");
+            assert_eq!(metrics[0].ingested_pages, 4);
+            let conn = Connection::open(&config.sample.sqlite_path).expect("open sample db");
+            let mut stmt = conn
+                .prepare("SELECT url FROM pages ORDER BY url")
+                .expect("prepare");
+            stmt.query_map([], |row| row.get::<_, String>(0))
+                .expect("query")
+                .collect::<rusqlite::Result<Vec<_>>>()
+                .expect("rows")
+        };
+
+        let first = sampled_urls(&config);
+        assert_eq!(first, sampled_urls(&config));
+        assert!(!config.input.sqlite_path.exists());
+        assert!(first.iter().any(|url| url.starts_with("fr")));
+        assert!(first.iter().any(|url| url.starts_with("en")));
+
+        config.selection.title_list_path = Some(workdir.path().join("titles.txt"));
+        assert!(run_conversion(&config).is_err());
+    }
+
+    #[test]
+    fn shards_convert_disjoint_ranges_and_last_shard_resolves_redirects() {
+        let workdir = tempdir().expect("tempdir");
+        let mut config = fixture_config(
+            workdir.path(),
+            &[
+                FixtureEntry::redirect("alpha", "delta"),
+                FixtureEntry::article("beta", ENTRY_HTML),
+                FixtureEntry::article("delta", ENTRY_HTML),
+                FixtureEntry::metadata("Date", "2026-02-01"),
+            ],
+        );
+        config.selection.redirect_mode = Some(RedirectMode::Alias);
+
+        config.selection.shard = Some(ShardSpec::new(1, 2).expect("shard spec"));
+        let first = run_conversion(&config).expect("convert shard 1");
+        assert_eq!(first[0].scanned_entries, 2);
+        assert_eq!(first[0].ingested_pages, 1);
+        assert_eq!(first[0].queued_redirects, 1);
+        assert_eq!(first[0].redirect_aliases, 0);
+
+        config.selection.shard = Some(ShardSpec::new(2, 2).expect("shard spec"));
+        let second = run_conversion(&config).expect("convert shard 2");
+        assert_eq!(second[0].scanned_entries, 2);
+        assert_eq!(second[0].ingested_pages, 1);
+        assert_eq!(second[0].redirect_aliases, 1);
+        assert_ne!(first[0].run_id, second[0].run_id);
+
+        let db = Database::open(&config).expect("open db");
+        let statuses = collect_shard_statuses(&config, &db, 2, 4).expect("shard statuses");
+        assert!(statuses.iter().all(|status| status.is_complete()));
+        assert_eq!(
+            statuses
+                .iter()
+                .map(|status| status.checkpoint_name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["default:shard-1-of-2", "default:shard-2-of-2"]
+        );
+        drop(db);
+
+        let conn = Connection::open(&config.input.sqlite_path).expect("open sqlite");
+        let redirect_target: String = conn
+            .query_row(
+                "SELECT p.url FROM lemma_aliases a JOIN pages p ON p.id = a.page_id WHERE a.source = 'redirect' AND a.alias = 'alpha'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("redirect alias");
+        assert_eq!(redirect_target, "delta");
+    }
+
+    #[test]
+    fn resumed_shard_stops_at_its_own_end() {
+        let workdir = tempdir().expect("tempdir");
+        let entries = (0..6)
+            .map(|idx| FixtureEntry::article(&format!("word{idx}"), ENTRY_HTML))
+            .collect::<Vec<_>>();
+        let mut config = fixture_config(workdir.path(), &entries);
+        let shard = ShardSpec::new(1, 2).expect("shard spec");
+        config.selection.shard = Some(shard);
+
+        let db = Database::open(&config).expect("open db");
+        db.init_schema().expect("init schema");
+        db.save_checkpoint(
+            &shard.checkpoint_name(&config.checkpoint.name),
+            &CheckpointState {
+                last_processed_index: 0,
+                ingested_pages: 1,
+                extracted_definitions: 1,
+                extracted_relations: 0,
+                run_id: None,
+            },
+        )
+        .expect("save checkpoint");
+        drop(db);
+
+        let resumed = run_conversion(&config).expect("resume shard");
+        assert!(resumed[0].resumed_from_checkpoint);
+        assert_eq!(resumed[0].scanned_entries, 2);
+        assert_eq!(resumed[0].ingested_pages, 2);
+
+        let finished = run_conversion(&config).expect("rerun finished shard");
+        assert_eq!(finished[0].scanned_entries, 0);
+
+        let conn = Connection::open(&config.input.sqlite_path).expect("open sqlite");
+        let max_entry: i64 = conn
+            .query_row("SELECT MAX(zim_entry_index) FROM pages", [], |row| {
+                row.get(0)
+            })
+            .expect("max entry index");
+        assert_eq!(max_entry, 2);
     }
 }
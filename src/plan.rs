@@ -0,0 +1,313 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use zim::{Target, Zim};
+
+use crate::config::{Config, RedirectMode};
+use crate::extractor::{mime_type_label, namespace_code};
+use crate::pipeline::{FilterRule, ensure_input_exists, entry_window, selection_filter};
+use crate::selection::SelectionPatterns;
+
+/// What `convert` would select from one archive, gathered from directory entries alone.
+#[derive(Debug, Clone, Serialize)]
+pub struct SelectionPlan {
+    pub edition: String,
+    pub zim_path: String,
+    pub start_index: u32,
+    pub end_index: u32,
+    pub scanned_entries: u64,
+    pub selected_entries: u64,
+    pub selected_content_entries: u64,
+    pub redirect_entries: u64,
+    /// Redirects that pass the filters and become pages or aliases under `redirect_mode`.
+    pub selected_redirects: u64,
+    /// Redirects that pass the filters but are dropped because `redirect_mode` is `skip`.
+    pub skipped_redirects: u64,
+    pub redirect_mode: RedirectMode,
+    /// Entries whose directory record could not be decoded.
+    pub unreadable_entries: u64,
+    pub missing_list_titles: u64,
+    pub namespaces: BTreeMap<String, PlanCounts>,
+    pub mime_types: BTreeMap<String, PlanCounts>,
+    /// Entries rejected by each selection rule, in the order the rules are checked. An entry is
+    /// counted only under the first rule that rejects it.
+    pub filter_rules: Vec<RuleCount>,
+    pub clusters_to_decompress: u64,
+    /// On-disk (compressed) size of those clusters. Their decompressed size is only known after
+    /// decompressing them, which a plan never does.
+    pub compressed_cluster_bytes: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct PlanCounts {
+    pub entries: u64,
+    pub selected: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleCount {
+    pub rule: &'static str,
+    pub rejected: u64,
+}
+
+/// Plans every configured archive. Sample settings are ignored, since drawing a stratified
+/// sample would read page content.
+pub fn plan_selection(config: &Config) -> Result<Vec<SelectionPlan>> {
+    let mut plans = Vec::new();
+    for archive_config in config.archive_configs()? {
+        ensure_input_exists(&archive_config.input.zim_path)?;
+        let zim = Zim::new(&archive_config.input.zim_path).with_context(|| {
+            format!("failed to open {}", archive_config.input.zim_path.display())
+        })?;
+        plans.push(plan_archive(&zim, &archive_config)?);
+    }
+    Ok(plans)
+}
+
+pub fn plan_archive(zim: &Zim, config: &Config) -> Result<SelectionPlan> {
+    let mut config = config.clone();
+    config.sample.size = None;
+    let patterns = SelectionPatterns::compile(&config.selection)?;
+
+    let total_entries = zim.header.article_count;
+    let (start, end) = match config.selection.shard {
+        Some(shard) => shard.index_range(total_entries),
+        None => {
+            let start = config.selection.start_index.min(total_entries);
+            let max_entries = config
+                .selection
+                .max_entries
+                .unwrap_or(total_entries.saturating_sub(start));
+            let end = (u64::from(start) + u64::from(max_entries)).min(u64::from(total_entries));
+            (start, end as u32)
+        }
+    };
+    let (window, missing_list_titles) = entry_window(zim, &config, &patterns, start, end)?;
+
+    let mut plan = SelectionPlan {
+        edition: config.input.edition.clone(),
+        zim_path: config.input.zim_path.display().to_string(),
+        start_index: start,
+        end_index: end,
+        scanned_entries: 0,
+        selected_entries: 0,
+        selected_content_entries: 0,
+        redirect_entries: 0,
+        selected_redirects: 0,
+        skipped_redirects: 0,
+        redirect_mode: config.selection.redirect_mode(),
+        unreadable_entries: 0,
+        missing_list_titles,
+        namespaces: BTreeMap::new(),
+        mime_types: BTreeMap::new(),
+        filter_rules: FilterRule::ALL
+            .iter()
+            .map(|rule| RuleCount {
+                rule: rule.as_str(),
+                rejected: 0,
+            })
+            .collect(),
+        clusters_to_decompress: 0,
+        compressed_cluster_bytes: 0,
+    };
+
+    let mut touched_clusters = vec![false; zim.cluster_list.len()];
+    for entry_index in window {
+        plan.scanned_entries += 1;
+        let entry = match zim.get_by_url_index(entry_index) {
+            Ok(entry) => entry,
+            Err(_) => {
+                plan.unreadable_entries += 1;
+                continue;
+            }
+        };
+
+        let is_redirect = matches!(entry.target, Some(Target::Redirect(_)));
+        plan.redirect_entries += u64::from(is_redirect);
+
+        let rejected_by = selection_filter(&entry, &config, &patterns);
+        // Convert drops these after the filters, counting them as filtered.
+        let skipped_redirect =
+            rejected_by.is_none() && is_redirect && plan.redirect_mode == RedirectMode::Skip;
+        let selected = u64::from(rejected_by.is_none() && !skipped_redirect);
+        for counts in [
+            plan.namespaces
+                .entry(namespace_code(entry.namespace).to_owned())
+                .or_default(),
+            plan.mime_types
+                .entry(mime_type_label(&entry.mime_type))
+                .or_default(),
+        ] {
+            counts.entries += 1;
+            counts.selected += selected;
+        }
+
+        if let Some(rule) = rejected_by {
+            if let Some(position) = FilterRule::ALL.iter().position(|item| *item == rule) {
+                plan.filter_rules[position].rejected += 1;
+            }
+            continue;
+        }
+        if skipped_redirect {
+            plan.skipped_redirects += 1;
+            continue;
+        }
+
+        plan.selected_entries += 1;
+        match entry.target {
+            Some(Target::Redirect(_)) => plan.selected_redirects += 1,
+            Some(Target::Cluster(cluster_idx, _)) => {
+                plan.selected_content_entries += 1;
+                if let Some(touched) = touched_clusters.get_mut(cluster_idx as usize) {
+                    *touched = true;
+                }
+            }
+            None => {}
+        }
+    }
+
+    for (cluster_idx, _) in touched_clusters
+        .iter()
+        .enumerate()
+        .filter(|(_, touched)| **touched)
+    {
+        plan.clusters_to_decompress += 1;
+        let cluster_start = zim.cluster_list[cluster_idx];
+        let cluster_end = zim
+            .cluster_list
+            .get(cluster_idx + 1)
+            .copied()
+            .unwrap_or(zim.header.checksum_pos);
+        plan.compressed_cluster_bytes += cluster_end.saturating_sub(cluster_start);
+    }
+
+    Ok(plan)
+}
+
+/// Renders plans as fixed-width text tables, one block per archive.
+pub fn render_plan_table(plans: &[SelectionPlan]) -> String {
+    let mut out = String::new();
+    for plan in plans {
+        let _ = writeln!(
+            out,
+            "edition {} ({}), entries {}..{}",
+            plan.edition, plan.zim_path, plan.start_index, plan.end_index
+        );
+        let redirects = format!("{:?}", plan.redirect_mode).to_lowercase();
+        for (label, value) in [
+            ("scanned entries", plan.scanned_entries.to_string()),
+            ("selected entries", plan.selected_entries.to_string()),
+            ("  content", plan.selected_content_entries.to_string()),
+            (
+                "  redirects",
+                format!("{} (mode {redirects})", plan.selected_redirects),
+            ),
+            ("  skipped redirects", plan.skipped_redirects.to_string()),
+            ("redirect entries", plan.redirect_entries.to_string()),
+            ("unreadable entries", plan.unreadable_entries.to_string()),
+            ("missing list titles", plan.missing_list_titles.to_string()),
+            (
+                "clusters to decompress",
+                plan.clusters_to_decompress.to_string(),
+            ),
+            (
+                "compressed cluster bytes",
+                plan.compressed_cluster_bytes.to_string(),
+            ),
+        ] {
+            let _ = writeln!(out, "  {label:<24}{value:>14}");
+        }
+
+        for (heading, rows) in [
+            ("namespace", &plan.namespaces),
+            ("mime type", &plan.mime_types),
+        ] {
+            let _ = writeln!(out, "\n  {heading:<24}{:>14}{:>14}", "entries", "selected");
+            for (key, counts) in rows {
+                let _ = writeln!(
+                    out,
+                    "  {key:<24}{:>14}{:>14}",
+                    counts.entries, counts.selected
+                );
+            }
+        }
+
+        let _ = writeln!(out, "\n  {:<24}{:>14}", "filter rule", "rejected");
+        for rule in &plan.filter_rules {
+            let _ = writeln!(out, "  {:<24}{:>14}", rule.rule, rule.rejected);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_zim::{FixtureEntry, write_test_zim};
+    use tempfile::tempdir;
+
+    #[test]
+    fn plan_counts_rules_in_order_without_reading_content() {
+        let workdir = tempdir().expect("tempdir");
+        let zim_path = workdir.path().join("plan.zim");
+        write_test_zim(
+            &zim_path,
+            &[
+                FixtureEntry::article("alpha", "<p>alpha</p>"),
+                FixtureEntry::article("Special:Random", "<p>special</p>"),
+                FixtureEntry::article("beta/translations", "<p>beta</p>"),
+                FixtureEntry::redirect("gamma", "alpha"),
+                FixtureEntry::metadata("Title", "fixture"),
+            ],
+        );
+        let zim = Zim::new(&zim_path).expect("open zim");
+
+        let mut config = Config::default();
+        config.input.zim_path = zim_path;
+        config.selection.exclude_url_patterns = vec!["/".to_owned()];
+        // Outside alias mode redirects pass the MIME filter only as `redirect`.
+        config
+            .selection
+            .include_mime_prefixes
+            .push("redirect".to_owned());
+
+        let plan = plan_archive(&zim, &config).expect("plan");
+        assert_eq!(plan.scanned_entries, 5);
+        assert_eq!(plan.selected_entries, 1, "skip mode drops the redirect");
+        assert_eq!(plan.selected_content_entries, 1);
+        assert_eq!(plan.selected_redirects, 0);
+        assert_eq!(plan.skipped_redirects, 1);
+        assert_eq!(plan.redirect_entries, 1);
+        assert_eq!(plan.clusters_to_decompress, 1);
+        assert!(plan.compressed_cluster_bytes > 0);
+
+        let rejected = plan
+            .filter_rules
+            .iter()
+            .filter(|rule| rule.rejected > 0)
+            .map(|rule| (rule.rule, rule.rejected))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rejected,
+            vec![("namespace", 1), ("url_prefix", 1), ("url_pattern", 1)]
+        );
+        assert_eq!(plan.namespaces["A"].entries, 4);
+        assert_eq!(plan.namespaces["A"].selected, 1);
+        assert_eq!(plan.mime_types["text/html"].entries, 3);
+
+        let table = render_plan_table(std::slice::from_ref(&plan));
+        assert!(table.contains("url_pattern"));
+        let json = serde_json::to_value(&plan).expect("json");
+        assert_eq!(json["redirect_mode"], "skip");
+
+        config.selection.redirect_mode = Some(RedirectMode::Alias);
+        let plan = plan_archive(&zim, &config).expect("plan");
+        assert_eq!(plan.selected_entries, 2);
+        assert_eq!(plan.selected_redirects, 1);
+        assert_eq!(plan.skipped_redirects, 0);
+        assert_eq!(plan.namespaces["A"].selected, 2);
+    }
+}
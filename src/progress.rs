@@ -0,0 +1,261 @@
+use std::fs;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::config::LoggingConfig;
+use crate::pipeline::{RunMetrics, StageTimings};
+
+const BAR_WIDTH: usize = 30;
+
+/// Snapshot of a running convert, as written to `logging.status_file`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgressSnapshot {
+    pub edition: String,
+    pub shard: Option<String>,
+    pub state: ProgressState,
+    pub updated_unix_ms: u64,
+    pub elapsed_secs: f64,
+    /// Entries in this run's index window, after resuming from a checkpoint.
+    pub window_entries: u64,
+    pub scanned_entries: u64,
+    pub filtered_entries: u64,
+    pub ingested_pages: u64,
+    pub extraction_errors: u64,
+    pub decompressed_bytes: u64,
+    pub entries_per_sec: f64,
+    pub decompressed_mb_per_sec: f64,
+    /// Remaining window entries at the average rate so far; unset until something was scanned.
+    pub eta_secs: Option<f64>,
+    pub stage_secs: StageSeconds,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProgressState {
+    Running,
+    Complete,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct StageSeconds {
+    pub directory: f64,
+    pub decompress: f64,
+    /// Summed across extraction threads, so it can exceed wall-clock time.
+    pub extract: f64,
+    pub persist: f64,
+}
+
+impl From<&StageTimings> for StageSeconds {
+    fn from(timings: &StageTimings) -> Self {
+        Self {
+            directory: timings.directory.as_secs_f64(),
+            decompress: timings.decompress.as_secs_f64(),
+            extract: timings.extract.as_secs_f64(),
+            persist: timings.persist.as_secs_f64(),
+        }
+    }
+}
+
+/// Turns run metrics into throughput and ETA figures and publishes them to the status file and
+/// progress bar configured under `[logging]`, at most once per `status_interval_ms`.
+pub struct ProgressReporter {
+    status_file: Option<PathBuf>,
+    draw_bar: bool,
+    interval: Duration,
+    started: Instant,
+    last_publish: Option<Instant>,
+    window_entries: u64,
+    shard: Option<String>,
+}
+
+impl ProgressReporter {
+    pub fn new(config: &LoggingConfig, window_entries: u64, shard: Option<String>) -> Self {
+        Self {
+            status_file: config.status_file.clone(),
+            draw_bar: config.progress_bar && std::io::stderr().is_terminal(),
+            interval: Duration::from_millis(config.status_interval_ms),
+            started: Instant::now(),
+            last_publish: None,
+            window_entries,
+            shard,
+        }
+    }
+
+    pub fn snapshot(&self, metrics: &RunMetrics, state: ProgressState) -> ProgressSnapshot {
+        let elapsed_secs = self.started.elapsed().as_secs_f64();
+        let entries_per_sec = per_second(metrics.scanned_entries as f64, elapsed_secs);
+        let remaining = self.window_entries.saturating_sub(metrics.scanned_entries);
+        let eta_secs = match state {
+            ProgressState::Complete => Some(0.0),
+            ProgressState::Running if entries_per_sec > 0.0 => {
+                Some(remaining as f64 / entries_per_sec)
+            }
+            ProgressState::Running => None,
+        };
+
+        ProgressSnapshot {
+            edition: metrics.edition.clone(),
+            shard: self.shard.clone(),
+            state,
+            updated_unix_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |now| now.as_millis() as u64),
+            elapsed_secs,
+            window_entries: self.window_entries,
+            scanned_entries: metrics.scanned_entries,
+            filtered_entries: metrics.filtered_entries,
+            ingested_pages: metrics.ingested_pages,
+            extraction_errors: metrics.extraction_errors,
+            decompressed_bytes: metrics.decompressed_bytes,
+            entries_per_sec,
+            decompressed_mb_per_sec: per_second(
+                metrics.decompressed_bytes as f64 / 1_000_000.0,
+                elapsed_secs,
+            ),
+            eta_secs,
+            stage_secs: StageSeconds::from(&metrics.stage_timings),
+        }
+    }
+
+    /// Publishes a running snapshot if the interval has passed since the last one.
+    pub fn tick(&mut self, metrics: &RunMetrics) -> Result<()> {
+        if self.status_file.is_none() && !self.draw_bar {
+            return Ok(());
+        }
+        if self
+            .last_publish
+            .is_some_and(|last| last.elapsed() < self.interval)
+        {
+            return Ok(());
+        }
+
+        self.last_publish = Some(Instant::now());
+        self.publish(&self.snapshot(metrics, ProgressState::Running))
+    }
+
+    /// Publishes the final snapshot and ends the progress bar line.
+    pub fn finish(&mut self, metrics: &RunMetrics) -> Result<()> {
+        self.publish(&self.snapshot(metrics, ProgressState::Complete))?;
+        if self.draw_bar {
+            eprintln!();
+        }
+        Ok(())
+    }
+
+    fn publish(&self, snapshot: &ProgressSnapshot) -> Result<()> {
+        if let Some(path) = &self.status_file {
+            write_status_file(path, snapshot)?;
+        }
+        if self.draw_bar {
+            let mut stderr = std::io::stderr().lock();
+            let _ = write!(stderr, "\r{}", render_bar(snapshot));
+            let _ = stderr.flush();
+        }
+        Ok(())
+    }
+}
+
+pub fn write_status_file(path: &Path, snapshot: &ProgressSnapshot) -> Result<()> {
+    write_atomically(path, &serde_json::to_vec_pretty(snapshot)?)
+}
+
+/// Writes `contents` next to `path` and renames it into place, so readers never see a partial
+/// file.
+pub(crate) fn write_atomically(path: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+    fs::write(&tmp_path, contents)
+        .with_context(|| format!("failed to write {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, path).with_context(|| format!("failed to replace {}", path.display()))
+}
+
+fn render_bar(snapshot: &ProgressSnapshot) -> String {
+    let fraction = if snapshot.window_entries == 0 {
+        1.0
+    } else {
+        (snapshot.scanned_entries as f64 / snapshot.window_entries as f64).min(1.0)
+    };
+    let filled = (fraction * BAR_WIDTH as f64).round() as usize;
+    let eta = snapshot
+        .eta_secs
+        .map_or_else(|| "--:--:--".to_owned(), format_duration);
+
+    format!(
+        "[{}{}] {:>5.1}% {}/{} entries {:>8.0} e/s {:>7.1} MB/s ETA {eta}",
+        "#".repeat(filled),
+        "-".repeat(BAR_WIDTH - filled),
+        fraction * 100.0,
+        snapshot.scanned_entries,
+        snapshot.window_entries,
+        snapshot.entries_per_sec,
+        snapshot.decompressed_mb_per_sec,
+    )
+}
+
+fn format_duration(secs: f64) -> String {
+    let secs = secs.max(0.0).round() as u64;
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+fn per_second(amount: f64, elapsed_secs: f64) -> f64 {
+    if elapsed_secs > 0.0 {
+        amount / elapsed_secs
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn status_file_is_replaced_with_the_latest_snapshot() {
+        let workdir = tempdir().expect("tempdir");
+        let status_path = workdir.path().join("status/convert.json");
+        let config = LoggingConfig {
+            status_file: Some(status_path.clone()),
+            status_interval_ms: 0,
+            ..LoggingConfig::default()
+        };
+        let mut reporter = ProgressReporter::new(&config, 10, Some("1/2".to_owned()));
+
+        let mut metrics = RunMetrics {
+            edition: "en".to_owned(),
+            scanned_entries: 4,
+            ..RunMetrics::default()
+        };
+        reporter.tick(&metrics).expect("tick");
+        let running: serde_json::Value =
+            serde_json::from_slice(&fs::read(&status_path).expect("read status")).expect("json");
+        assert_eq!(running["state"], "running");
+        assert_eq!(running["window_entries"], 10);
+        assert_eq!(running["scanned_entries"], 4);
+        assert_eq!(running["shard"], "1/2");
+
+        metrics.scanned_entries = 10;
+        reporter.finish(&metrics).expect("finish");
+        let complete: serde_json::Value =
+            serde_json::from_slice(&fs::read(&status_path).expect("read status")).expect("json");
+        assert_eq!(complete["state"], "complete");
+        assert_eq!(complete["eta_secs"], 0.0);
+        assert!(!workdir.path().join("status/convert.json.tmp").exists());
+
+        let bar = render_bar(&reporter.snapshot(&metrics, ProgressState::Running));
+        assert!(bar.starts_with(&format!("[{}]", "#".repeat(BAR_WIDTH))));
+        assert_eq!(format_duration(3_725.0), "1:02:05");
+    }
+}
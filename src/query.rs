@@ -0,0 +1,2020 @@
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use postgres::NoTls;
+use r2d2::Pool;
+use r2d2_postgres::PostgresConnectionManager;
+use rusqlite::{Connection, OpenFlags, OptionalExtension, params};
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::config::{Config, StorageBackend};
+use crate::db::{build_pg_config, pg_table, validate_pg_identifier};
+use crate::export::{
+    ExportPage, fetch_aliases_postgres, fetch_aliases_sqlite, fetch_definitions_postgres,
+    fetch_definitions_sqlite, fetch_relations_postgres, fetch_relations_sqlite,
+};
+use crate::extractor::{TEXT_SEARCH_CONFIGS, canonical_language_name, text_search_config};
+use crate::normalization::canonicalize_lemma;
+use crate::search::{
+    HIGHLIGHT_END, HIGHLIGHT_START, ReverseHit, SearchHit, SearchQuery, fts5_query, fts5_query_any,
+    normalize_score, parse_search_query, tsquery, tsquery_any,
+};
+use crate::suggest::{
+    AliasMatch, FUZZY_CANDIDATES, MatchKind, SuggestQuery, rank_matches, trigram_query,
+};
+
+const PAGE_COLUMNS: &str = "id, url, title, namespace, mime_type, redirect_url, content_sha256, extraction_confidence, plain_text, raw_html, edition";
+
+/// What a lookup term is matched against.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LookupBy {
+    /// Normalized aliases (titles, inflections, redirects), the usual dictionary lookup.
+    #[default]
+    Alias,
+    /// The exact page title.
+    Headword,
+}
+
+#[derive(Debug, Clone)]
+pub struct LookupQuery {
+    pub term: String,
+    pub by: LookupBy,
+    /// Language name or ISO code; aliases must be in it, headword pages must define it.
+    pub language: Option<String>,
+    pub edition: Option<String>,
+    pub offset: usize,
+    pub limit: usize,
+    pub include_raw_html: bool,
+}
+
+impl LookupQuery {
+    pub fn new(term: impl Into<String>) -> Self {
+        Self {
+            term: term.into(),
+            by: LookupBy::Alias,
+            language: None,
+            edition: None,
+            offset: 0,
+            limit: 20,
+            include_raw_html: false,
+        }
+    }
+}
+
+/// Which path answered a lookup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LookupSource {
+    /// The Postgres `hot_lookup` projection.
+    Hot,
+    /// The canonical tables after the projection had no hit.
+    Fallback,
+    /// The canonical tables directly (SQLite, headwords, non-hot languages).
+    Canonical,
+}
+
+impl LookupSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Hot => "hot",
+            Self::Fallback => "fallback",
+            Self::Canonical => "canonical",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LookupResult {
+    pub source: LookupSource,
+    /// Matching live pages in page id order, hydrated like `export-json` records.
+    pub pages: Vec<ExportPage>,
+}
+
+/// A normalized alias starting with the requested prefix.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Completion {
+    pub normalized_alias: String,
+    /// Smallest stored spelling of the alias.
+    pub alias: String,
+    pub page_count: i64,
+}
+
+/// One relation touching a headword: `outgoing` relations are stored on its pages,
+/// `incoming` ones on other pages and target it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RelationNeighbour {
+    pub direction: String,
+    pub relation_type: String,
+    pub language: String,
+    /// The related term: the relation target when outgoing, the linking page title when
+    /// incoming.
+    pub term: String,
+    /// The page the relation is stored on.
+    pub page_id: i64,
+    pub confidence: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Route {
+    Hot,
+    Canonical,
+}
+
+/// Read-only SQLite connections handed out to concurrent readers.
+struct SqlitePool {
+    connections: Vec<Mutex<Connection>>,
+    next: AtomicUsize,
+}
+
+impl SqlitePool {
+    fn get(&self) -> MutexGuard<'_, Connection> {
+        for connection in &self.connections {
+            if let Ok(guard) = connection.try_lock() {
+                return guard;
+            }
+        }
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.connections.len();
+        // A panicked reader leaves nothing half-written on a read-only connection.
+        self.connections[index]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+enum Backend {
+    Sqlite(SqlitePool),
+    Postgres {
+        pool: Pool<PostgresConnectionManager<NoTls>>,
+        schema: String,
+        /// Whether `idx_aliases_norm_trgm` exists; `pg_trgm` is optional.
+        trigram_index: bool,
+    },
+}
+
+/// Read-only handle over a converted dictionary. Never migrates or writes; open the
+/// database with [`crate::db::Database`] first when the schema may be behind.
+pub struct Dictionary {
+    backend: Backend,
+    /// Resolved `hot_lookup.languages`, empty when hot-first routing is off.
+    hot_languages: Vec<String>,
+}
+
+impl Dictionary {
+    /// Opens the configured backend with up to `serve.threads` connections.
+    pub fn open(config: &Config) -> Result<Self> {
+        let pool_size = config.serve.threads.clamp(1, 64);
+        match config.backend {
+            StorageBackend::Sqlite => {
+                let db_path = &config.input.sqlite_path;
+                let mut connections = Vec::with_capacity(pool_size);
+                for _ in 0..pool_size {
+                    let conn = Connection::open_with_flags(
+                        db_path,
+                        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+                    )
+                    .with_context(|| format!("failed to open {}", db_path.display()))?;
+                    conn.busy_timeout(Duration::from_millis(config.sqlite.busy_timeout_ms))?;
+                    connections.push(Mutex::new(conn));
+                }
+
+                Ok(Self {
+                    backend: Backend::Sqlite(SqlitePool {
+                        connections,
+                        next: AtomicUsize::new(0),
+                    }),
+                    hot_languages: Vec::new(),
+                })
+            }
+            StorageBackend::Postgres => {
+                validate_pg_identifier(&config.postgres.schema, "postgres.schema")?;
+                let hot_languages = if config.hot_lookup.hot_first {
+                    config.hot_lookup.resolved_languages()?
+                } else {
+                    Vec::new()
+                };
+
+                let manager =
+                    PostgresConnectionManager::new(build_pg_config(&config.postgres)?, NoTls);
+                let pool = Pool::builder()
+                    .max_size(pool_size as u32)
+                    .min_idle(Some(1))
+                    .build(manager)
+                    .context("failed to create postgres connection pool")?;
+                let trigram_index: bool = pool
+                    .get()
+                    .context("failed to checkout postgres connection")?
+                    .query_one(
+                        "SELECT to_regclass(format('%I.idx_aliases_norm_trgm', $1::text)) IS NOT NULL",
+                        &[&config.postgres.schema],
+                    )?
+                    .get(0);
+                if !trigram_index {
+                    warn!(
+                        schema = %config.postgres.schema,
+                        "lemma_aliases has no pg_trgm index; fuzzy suggestions are off and suggest returns prefix matches only"
+                    );
+                }
+
+                Ok(Self {
+                    backend: Backend::Postgres {
+                        pool,
+                        schema: config.postgres.schema.clone(),
+                        trigram_index,
+                    },
+                    hot_languages,
+                })
+            }
+        }
+    }
+
+    pub fn backend_name(&self) -> &'static str {
+        match self.backend {
+            Backend::Sqlite(_) => "sqlite",
+            Backend::Postgres { .. } => "postgres",
+        }
+    }
+
+    /// Finds live pages for `query`. Alias lookups in a hot language read the projection
+    /// first and fall back to the canonical tables when it has no hit; everything else reads
+    /// the canonical tables.
+    pub fn lookup(&self, query: &LookupQuery) -> Result<LookupResult> {
+        let started = Instant::now();
+        let language = resolve_language(query.language.as_deref());
+
+        let hot = query.by == LookupBy::Alias
+            && language
+                .as_ref()
+                .is_some_and(|language| self.hot_languages.contains(language));
+
+        let (source, ids) = if hot {
+            let ids = self.page_ids(query, language.as_deref(), Route::Hot)?;
+            if ids.is_empty() {
+                let ids = self.page_ids(query, language.as_deref(), Route::Canonical)?;
+                (LookupSource::Fallback, ids)
+            } else {
+                (LookupSource::Hot, ids)
+            }
+        } else {
+            let ids = self.page_ids(query, language.as_deref(), Route::Canonical)?;
+            (LookupSource::Canonical, ids)
+        };
+        let pages = self.hydrate(&ids, query.include_raw_html)?;
+
+        info!(
+            lookup_source = source.as_str(),
+            requested_language = language.as_deref().unwrap_or(""),
+            lookup_ms = started.elapsed().as_secs_f64() * 1000.0,
+            hit_count = pages.len(),
+            "dictionary lookup"
+        );
+
+        Ok(LookupResult { source, pages })
+    }
+
+    /// One live page by id.
+    pub fn page(&self, id: i64, include_raw_html: bool) -> Result<Option<ExportPage>> {
+        Ok(self.hydrate(&[id], include_raw_html)?.pop())
+    }
+
+    /// Normalized aliases of live pages starting with `prefix`, in alias order.
+    pub fn complete(
+        &self,
+        prefix: &str,
+        language: Option<&str>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<Completion>> {
+        let prefix = canonicalize_lemma(prefix);
+        if prefix.is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
+        // Every alias starting with `prefix` sorts below this bound in byte order.
+        let upper = format!("{prefix}{}", char::MAX);
+        let language = resolve_language(language);
+        let (limit, offset) = (limit as i64, offset as i64);
+
+        match &self.backend {
+            Backend::Sqlite(pool) => {
+                let conn = pool.get();
+                let mut stmt = conn.prepare_cached(
+                    r#"
+                    SELECT a.normalized_alias, MIN(a.alias), COUNT(DISTINCT a.page_id)
+                    FROM lemma_aliases a
+                    JOIN pages p ON p.id = a.page_id AND p.deleted_at IS NULL
+                    WHERE a.normalized_alias >= ?1 AND a.normalized_alias < ?2
+                      AND (?3 IS NULL OR a.language = ?3)
+                    GROUP BY a.normalized_alias
+                    ORDER BY a.normalized_alias ASC
+                    LIMIT ?4 OFFSET ?5
+                    "#,
+                )?;
+                let completions = stmt
+                    .query_map(params![prefix, upper, language, limit, offset], |row| {
+                        Ok(Completion {
+                            normalized_alias: row.get(0)?,
+                            alias: row.get(1)?,
+                            page_count: row.get(2)?,
+                        })
+                    })?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                Ok(completions)
+            }
+            Backend::Postgres { pool, schema, .. } => {
+                let mut conn = pool
+                    .get()
+                    .context("failed to checkout postgres connection")?;
+                let rows = conn.query(
+                    &pg_completions_sql(schema),
+                    &[&prefix, &upper, &language, &limit, &offset],
+                )?;
+                Ok(rows
+                    .into_iter()
+                    .map(|row| Completion {
+                        normalized_alias: row.get(0),
+                        alias: row.get(1),
+                        page_count: row.get(2),
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    /// Aliases starting with the normalized term, shortest first, at most
+    /// `query.per_language` per language.
+    pub fn prefix_matches(&self, query: &SuggestQuery) -> Result<Vec<AliasMatch>> {
+        let prefix = canonicalize_lemma(&query.term);
+        if prefix.is_empty() || query.per_language == 0 {
+            return Ok(Vec::new());
+        }
+        let upper = format!("{prefix}{}", char::MAX);
+        let language = resolve_language(query.language.as_deref());
+        let per_language = query.per_language as i64;
+
+        // The window keeps the same rows `rank_matches` does: a completion's edit distance is
+        // its extra length.
+        let rows = match &self.backend {
+            Backend::Sqlite(pool) => {
+                let conn = pool.get();
+                let mut stmt = conn.prepare_cached(
+                    r#"
+                    SELECT language, normalized_alias, alias, page_count
+                    FROM (
+                        SELECT a.language, a.normalized_alias, MIN(a.alias) AS alias,
+                               COUNT(DISTINCT a.page_id) AS page_count,
+                               ROW_NUMBER() OVER (
+                                   PARTITION BY a.language
+                                   ORDER BY length(a.normalized_alias),
+                                            COUNT(DISTINCT a.page_id) DESC, a.normalized_alias
+                               ) AS n
+                        FROM lemma_aliases a
+                        JOIN pages p ON p.id = a.page_id AND p.deleted_at IS NULL
+                        WHERE a.normalized_alias >= ?1 AND a.normalized_alias < ?2
+                          AND (?3 IS NULL OR a.language = ?3)
+                        GROUP BY a.language, a.normalized_alias
+                    )
+                    WHERE n <= ?4
+                    "#,
+                )?;
+                stmt.query_map(params![prefix, upper, language, per_language], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?
+            }
+            Backend::Postgres { pool, schema, .. } => {
+                let mut conn = pool
+                    .get()
+                    .context("failed to checkout postgres connection")?;
+                conn.query(
+                    &pg_prefix_matches_sql(schema),
+                    &[&prefix, &upper, &language, &per_language],
+                )?
+                .into_iter()
+                .map(|row| (row.get(0), row.get(1), row.get(2), row.get(3)))
+                .collect()
+            }
+        };
+        Ok(rank_matches(
+            &prefix,
+            MatchKind::Prefix,
+            rows,
+            usize::MAX,
+            query.per_language,
+        ))
+    }
+
+    /// Aliases within `query.max_edits` edits of the normalized term, closest first, at most
+    /// `query.per_language` per language. Candidates are the aliases sharing the most
+    /// trigrams with the term (`pg_trgm` distance on Postgres, the FTS5 trigram index on
+    /// SQLite), so terms need at least three characters. Empty on Postgres schemas without
+    /// `pg_trgm`.
+    pub fn fuzzy_matches(&self, query: &SuggestQuery) -> Result<Vec<AliasMatch>> {
+        let term = canonicalize_lemma(&query.term);
+        let Some(trigrams) = trigram_query(&term) else {
+            return Ok(Vec::new());
+        };
+        if query.per_language == 0 {
+            return Ok(Vec::new());
+        }
+        let language = resolve_language(query.language.as_deref());
+
+        let rows = match &self.backend {
+            Backend::Sqlite(pool) => {
+                let conn = pool.get();
+                let mut stmt = conn.prepare_cached(
+                    r#"
+                    WITH candidates AS (
+                        SELECT DISTINCT normalized_alias FROM (
+                            SELECT a.normalized_alias
+                            FROM alias_trigrams t
+                            JOIN lemma_aliases a ON a.id = t.rowid
+                            WHERE alias_trigrams MATCH ?1
+                              AND (?2 IS NULL OR a.language = ?2)
+                            ORDER BY t.rank
+                            LIMIT ?3
+                        )
+                    )
+                    SELECT a.language, a.normalized_alias, MIN(a.alias),
+                           COUNT(DISTINCT a.page_id)
+                    FROM candidates c
+                    JOIN lemma_aliases a ON a.normalized_alias = c.normalized_alias
+                    JOIN pages p ON p.id = a.page_id AND p.deleted_at IS NULL
+                    WHERE ?2 IS NULL OR a.language = ?2
+                    GROUP BY a.language, a.normalized_alias
+                    "#,
+                )?;
+                stmt.query_map(params![trigrams, language, FUZZY_CANDIDATES], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?
+            }
+            Backend::Postgres {
+                trigram_index: false,
+                ..
+            } => Vec::new(),
+            Backend::Postgres { pool, schema, .. } => {
+                let mut conn = pool
+                    .get()
+                    .context("failed to checkout postgres connection")?;
+                conn.query(
+                    &format!(
+                        r#"
+                        WITH candidates AS (
+                            SELECT DISTINCT normalized_alias FROM (
+                                SELECT normalized_alias
+                                FROM {aliases}
+                                WHERE $2::text IS NULL OR language = $2
+                                ORDER BY normalized_alias <-> $1
+                                LIMIT $3
+                            ) nearest
+                        )
+                        SELECT a.language, a.normalized_alias, MIN(a.alias),
+                               COUNT(DISTINCT a.page_id)
+                        FROM candidates c
+                        JOIN {aliases} a ON a.normalized_alias = c.normalized_alias
+                        JOIN {pages} p ON p.id = a.page_id AND p.deleted_at IS NULL
+                        WHERE $2::text IS NULL OR a.language = $2
+                        GROUP BY a.language, a.normalized_alias
+                        "#,
+                        aliases = pg_table(schema, "lemma_aliases"),
+                        pages = pg_table(schema, "pages")
+                    ),
+                    &[&term, &language, &FUZZY_CANDIDATES],
+                )?
+                .into_iter()
+                .map(|row| (row.get(0), row.get(1), row.get(2), row.get(3)))
+                .collect()
+            }
+        };
+        Ok(rank_matches(
+            &term,
+            MatchKind::Fuzzy,
+            rows,
+            query.max_edits,
+            query.per_language,
+        ))
+    }
+
+    /// Search-box suggestions: prefix completions first, then fuzzy matches that are not
+    /// already completions, at most `query.per_language` of each kind per language.
+    pub fn suggest(&self, query: &SuggestQuery) -> Result<Vec<AliasMatch>> {
+        let mut matches = self.prefix_matches(query)?;
+        for fuzzy in self.fuzzy_matches(query)? {
+            if !matches.iter().any(|known| {
+                known.language == fuzzy.language && known.normalized_alias == fuzzy.normalized_alias
+            }) {
+                matches.push(fuzzy);
+            }
+        }
+        Ok(matches)
+    }
+
+    /// Ranked full-text search over `page_fts` (`bm25` on SQLite, `ts_rank_cd` on Postgres)
+    /// with a highlighted snippet per hit; see [`SearchQuery`] for the syntax.
+    pub fn search(&self, query: &SearchQuery) -> Result<Vec<SearchHit>> {
+        let started = Instant::now();
+        let terms = parse_search_query(&query.text);
+        if terms.is_empty() || query.limit == 0 {
+            return Ok(Vec::new());
+        }
+        let language = resolve_language(query.language.as_deref());
+        let (limit, offset) = (query.limit as i64, query.offset as i64);
+
+        let hits = match &self.backend {
+            Backend::Sqlite(pool) => {
+                let conn = pool.get();
+                let mut stmt = conn
+                    .prepare_cached(
+                        r#"
+                        SELECT p.id, p.edition, p.title, p.url, -bm25(page_fts),
+                               snippet(page_fts, 3, ?5, ?6, '…', 16)
+                        FROM page_fts
+                        JOIN pages p ON p.id = page_fts.page_id AND p.deleted_at IS NULL
+                        WHERE page_fts MATCH ?1
+                          AND (?2 IS NULL OR EXISTS (
+                              SELECT 1 FROM definitions d WHERE d.page_id = p.id AND d.language = ?2
+                          ))
+                        ORDER BY bm25(page_fts) ASC, p.id ASC
+                        LIMIT ?3 OFFSET ?4
+                        "#,
+                    )
+                    .context("sqlite full-text search over page_fts failed")?;
+                stmt.query_map(
+                    params![
+                        fts5_query(&terms),
+                        language,
+                        limit,
+                        offset,
+                        HIGHLIGHT_START,
+                        HIGHLIGHT_END
+                    ],
+                    |row| {
+                        Ok(SearchHit {
+                            page_id: row.get(0)?,
+                            edition: row.get(1)?,
+                            title: row.get(2)?,
+                            url: row.get(3)?,
+                            score: normalize_score(row.get(4)?),
+                            snippet: row.get(5)?,
+                        })
+                    },
+                )?
+                .collect::<rusqlite::Result<Vec<_>>>()?
+            }
+            Backend::Postgres { pool, schema, .. } => {
+                let mut conn = pool
+                    .get()
+                    .context("failed to checkout postgres connection")?;
+                // Pages match on the `simple` vector or, for a requested language, on that
+                // language's stemmed definitions, and rank by the better of the two.
+                // Normalization 1 divides the rank by 1 + log(document length), as bm25
+                // normalizes for length; `normalize_score` then maps it to [0, 1).
+                let config = language.as_deref().map_or("simple", text_search_config);
+                let rows = conn
+                    .query(
+                        &format!(
+                            r#"
+                            WITH q AS (
+                                SELECT to_tsquery('simple', $1) AS sq,
+                                       to_tsquery($6::text::regconfig, $1) AS lq
+                            ),
+                            matched AS (
+                                SELECT f.page_id FROM {page_fts} f, q WHERE f.search_vector @@ q.sq
+                                UNION
+                                SELECT l.page_id FROM {language_fts} l, q
+                                WHERE l.language = $2::text AND l.search_vector @@ q.lq
+                            )
+                            SELECT id, edition, title, url, rank,
+                                   ts_headline($6::text::regconfig, plain_text, hq, $5)
+                            FROM (
+                                SELECT p.id, p.edition, p.title, p.url, f.plain_text,
+                                       q.sq || q.lq AS hq,
+                                       GREATEST(
+                                           ts_rank_cd(f.search_vector, q.sq, 1),
+                                           COALESCE(ts_rank_cd(l.search_vector, q.lq, 1), 0)
+                                       )::float8 AS rank
+                                FROM matched m
+                                CROSS JOIN q
+                                JOIN {page_fts} f ON f.page_id = m.page_id
+                                JOIN {pages} p ON p.id = m.page_id AND p.deleted_at IS NULL
+                                LEFT JOIN {language_fts} l
+                                  ON l.page_id = m.page_id AND l.language = $2::text
+                                WHERE $2::text IS NULL OR EXISTS (
+                                    SELECT 1 FROM {definitions} d
+                                    WHERE d.page_id = p.id AND d.language = $2::text
+                                )
+                                ORDER BY rank DESC, p.id ASC
+                                LIMIT $3 OFFSET $4
+                            ) hits
+                            ORDER BY rank DESC, id ASC
+                            "#,
+                            page_fts = pg_table(schema, "page_fts"),
+                            language_fts = pg_table(schema, "page_language_fts"),
+                            pages = pg_table(schema, "pages"),
+                            definitions = pg_table(schema, "definitions")
+                        ),
+                        &[
+                            &tsquery(&terms),
+                            &language,
+                            &limit,
+                            &offset,
+                            &format!(
+                                "StartSel={HIGHLIGHT_START}, StopSel={HIGHLIGHT_END}, MaxWords=24, MinWords=8, MaxFragments=1"
+                            ),
+                            &config,
+                        ],
+                    )
+                    .context("postgres full-text search over page_fts and page_language_fts failed")?;
+                rows.into_iter()
+                    .map(|row| SearchHit {
+                        page_id: row.get(0),
+                        edition: row.get(1),
+                        title: row.get(2),
+                        url: row.get(3),
+                        score: normalize_score(row.get(4)),
+                        snippet: row.get(5),
+                    })
+                    .collect()
+            }
+        };
+
+        info!(
+            search_terms = terms.len(),
+            requested_language = language.as_deref().unwrap_or(""),
+            search_ms = started.elapsed().as_secs_f64() * 1000.0,
+            hit_count = hits.len(),
+            "full-text search"
+        );
+        Ok(hits)
+    }
+
+    /// Reverse dictionary: headwords whose senses match a description, ranked by their best
+    /// matching sense. Any query term may match (same syntax as [`Dictionary::search`]); senses
+    /// covering more of the description rank higher. `query.language` limits the senses
+    /// searched and, on Postgres, picks the stemmer; otherwise every configured stemmer is
+    /// tried.
+    pub fn reverse_lookup(&self, query: &SearchQuery) -> Result<Vec<ReverseHit>> {
+        let started = Instant::now();
+        let terms = parse_search_query(&query.text);
+        if terms.is_empty() || query.limit == 0 {
+            return Ok(Vec::new());
+        }
+        let language = resolve_language(query.language.as_deref());
+        let (limit, offset) = (query.limit as i64, query.offset as i64);
+
+        let hits = match &self.backend {
+            Backend::Sqlite(pool) => {
+                let conn = pool.get();
+                let mut stmt = conn
+                    .prepare_cached(
+                        r#"
+                        WITH senses AS (
+                            SELECT page_id, language, def_order, definition_text,
+                                   -bm25(definition_fts) AS rank
+                            FROM definition_fts
+                            WHERE definition_fts MATCH ?1 AND (?2 IS NULL OR language = ?2)
+                        ),
+                        best AS (
+                            SELECT *, ROW_NUMBER() OVER (
+                                PARTITION BY page_id ORDER BY rank DESC, language, def_order
+                            ) AS n
+                            FROM senses
+                        )
+                        SELECT p.id, p.edition, p.title, p.url, b.language, b.def_order,
+                               b.definition_text, b.rank
+                        FROM best b
+                        JOIN pages p ON p.id = b.page_id AND p.deleted_at IS NULL
+                        WHERE b.n = 1
+                        ORDER BY b.rank DESC, p.id ASC
+                        LIMIT ?3 OFFSET ?4
+                        "#,
+                    )
+                    .context("sqlite reverse lookup over definition_fts failed")?;
+                stmt.query_map(
+                    params![fts5_query_any(&terms), language, limit, offset],
+                    |row| {
+                        Ok(ReverseHit {
+                            page_id: row.get(0)?,
+                            edition: row.get(1)?,
+                            title: row.get(2)?,
+                            url: row.get(3)?,
+                            language: row.get(4)?,
+                            sense: row.get(5)?,
+                            definition: row.get(6)?,
+                            score: normalize_score(row.get(7)?),
+                        })
+                    },
+                )?
+                .collect::<rusqlite::Result<Vec<_>>>()?
+            }
+            Backend::Postgres { pool, schema, .. } => {
+                let mut conn = pool
+                    .get()
+                    .context("failed to checkout postgres connection")?;
+                // Senses are stemmed with their language's configuration, so without a
+                // language the query ORs the parse of every configuration. The names are
+                // constants and safe to inline.
+                let configs = match language.as_deref() {
+                    Some(language) => vec![text_search_config(language)],
+                    None => std::iter::once("simple")
+                        .chain(TEXT_SEARCH_CONFIGS.iter().map(|(_, config)| *config))
+                        .collect(),
+                };
+                let tsquery_sql = configs
+                    .iter()
+                    .map(|config| format!("to_tsquery('{config}', $1)"))
+                    .collect::<Vec<_>>()
+                    .join(" || ");
+                let rows = conn
+                    .query(
+                        &format!(
+                            r#"
+                            WITH q AS (SELECT {tsquery_sql} AS q),
+                            best AS (
+                                SELECT DISTINCT ON (f.page_id)
+                                       f.page_id, f.definition_id,
+                                       ts_rank_cd(f.search_vector, q.q, 1)::float8 AS rank
+                                FROM {definition_fts} f, q
+                                WHERE f.search_vector @@ q.q
+                                  AND ($2::text IS NULL OR f.language = $2::text)
+                                ORDER BY f.page_id, rank DESC, f.definition_id
+                            )
+                            SELECT p.id, p.edition, p.title, p.url, d.language, d.def_order,
+                                   d.definition_text, b.rank
+                            FROM best b
+                            JOIN {pages} p ON p.id = b.page_id AND p.deleted_at IS NULL
+                            JOIN {definitions} d ON d.id = b.definition_id
+                            ORDER BY b.rank DESC, p.id ASC
+                            LIMIT $3 OFFSET $4
+                            "#,
+                            definition_fts = pg_table(schema, "definition_fts"),
+                            pages = pg_table(schema, "pages"),
+                            definitions = pg_table(schema, "definitions")
+                        ),
+                        &[&tsquery_any(&terms), &language, &limit, &offset],
+                    )
+                    .context("postgres reverse lookup over definition_fts failed")?;
+                rows.into_iter()
+                    .map(|row| ReverseHit {
+                        page_id: row.get(0),
+                        edition: row.get(1),
+                        title: row.get(2),
+                        url: row.get(3),
+                        language: row.get(4),
+                        sense: row.get(5),
+                        definition: row.get(6),
+                        score: normalize_score(row.get(7)),
+                    })
+                    .collect()
+            }
+        };
+
+        info!(
+            search_terms = terms.len(),
+            requested_language = language.as_deref().unwrap_or(""),
+            search_ms = started.elapsed().as_secs_f64() * 1000.0,
+            hit_count = hits.len(),
+            "reverse lookup"
+        );
+        Ok(hits)
+    }
+
+    /// Relations stored on the pages titled `title` (outgoing), then relations on live pages
+    /// that target it (incoming), each ordered by type, language and page.
+    pub fn relation_neighbours(
+        &self,
+        title: &str,
+        language: Option<&str>,
+        relation_type: Option<&str>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<RelationNeighbour>> {
+        let title = title.trim();
+        let target = canonicalize_lemma(title);
+        if title.is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
+        let language = resolve_language(language);
+        let (limit, offset) = (limit as i64, offset as i64);
+
+        match &self.backend {
+            Backend::Sqlite(pool) => {
+                let conn = pool.get();
+                let mut stmt = conn.prepare_cached(
+                    r#"
+                    SELECT direction, relation_type, language, term, page_id, confidence
+                    FROM (
+                        SELECT 'outgoing' AS direction, r.relation_type, r.language,
+                               r.target_term AS term, p.id AS page_id, r.confidence, r.rel_order
+                        FROM relations r
+                        JOIN pages p ON p.id = r.page_id AND p.deleted_at IS NULL
+                        WHERE p.title = ?1
+                          AND (?3 IS NULL OR r.language = ?3)
+                          AND (?4 IS NULL OR r.relation_type = ?4)
+                        UNION ALL
+                        SELECT 'incoming', r.relation_type, r.language, p.title, p.id,
+                               r.confidence, r.rel_order
+                        FROM relations r
+                        JOIN pages p ON p.id = r.page_id AND p.deleted_at IS NULL
+                        WHERE r.normalized_target = ?2 AND p.title <> ?1
+                          AND (?3 IS NULL OR r.language = ?3)
+                          AND (?4 IS NULL OR r.relation_type = ?4)
+                    )
+                    ORDER BY direction DESC, relation_type, language, page_id, rel_order
+                    LIMIT ?5 OFFSET ?6
+                    "#,
+                )?;
+                let neighbours = stmt
+                    .query_map(
+                        params![title, target, language, relation_type, limit, offset],
+                        |row| {
+                            Ok(RelationNeighbour {
+                                direction: row.get(0)?,
+                                relation_type: row.get(1)?,
+                                language: row.get(2)?,
+                                term: row.get(3)?,
+                                page_id: row.get(4)?,
+                                confidence: row.get(5)?,
+                            })
+                        },
+                    )?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                Ok(neighbours)
+            }
+            Backend::Postgres { pool, schema, .. } => {
+                let mut conn = pool
+                    .get()
+                    .context("failed to checkout postgres connection")?;
+                let relations = pg_table(schema, "relations");
+                let pages = pg_table(schema, "pages");
+                let rows = conn.query(
+                    &format!(
+                        r#"
+                        SELECT direction, relation_type, language, term, page_id, confidence
+                        FROM (
+                            SELECT 'outgoing' AS direction, r.relation_type, r.language,
+                                   r.target_term AS term, p.id AS page_id, r.confidence, r.rel_order
+                            FROM {relations} r
+                            JOIN {pages} p ON p.id = r.page_id AND p.deleted_at IS NULL
+                            WHERE p.title = $1
+                              AND ($3::text IS NULL OR r.language = $3)
+                              AND ($4::text IS NULL OR r.relation_type = $4)
+                            UNION ALL
+                            SELECT 'incoming', r.relation_type, r.language, p.title, p.id,
+                                   r.confidence, r.rel_order
+                            FROM {relations} r
+                            JOIN {pages} p ON p.id = r.page_id AND p.deleted_at IS NULL
+                            WHERE md5(r.normalized_target) = md5($2) AND r.normalized_target = $2
+                              AND p.title <> $1
+                              AND ($3::text IS NULL OR r.language = $3)
+                              AND ($4::text IS NULL OR r.relation_type = $4)
+                        ) neighbours
+                        ORDER BY direction DESC, relation_type, language, page_id, rel_order
+                        LIMIT $5 OFFSET $6
+                        "#
+                    ),
+                    &[&title, &target, &language, &relation_type, &limit, &offset],
+                )?;
+                Ok(rows
+                    .into_iter()
+                    .map(|row| RelationNeighbour {
+                        direction: row.get(0),
+                        relation_type: row.get(1),
+                        language: row.get(2),
+                        term: row.get(3),
+                        page_id: row.get(4),
+                        confidence: row.get(5),
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    fn page_ids(
+        &self,
+        query: &LookupQuery,
+        language: Option<&str>,
+        route: Route,
+    ) -> Result<Vec<i64>> {
+        let term = match query.by {
+            LookupBy::Alias => canonicalize_lemma(&query.term),
+            LookupBy::Headword => query.term.trim().to_owned(),
+        };
+        if term.is_empty() || query.limit == 0 {
+            return Ok(Vec::new());
+        }
+        let edition = query.edition.as_deref();
+        let limit = query.limit as i64;
+        let offset = query.offset as i64;
+
+        match &self.backend {
+            Backend::Sqlite(conn) => {
+                let conn = conn.get();
+                let sql = match query.by {
+                    LookupBy::Alias => {
+                        r#"
+                        SELECT DISTINCT p.id
+                        FROM lemma_aliases a
+                        JOIN pages p ON p.id = a.page_id AND p.deleted_at IS NULL
+                        WHERE a.normalized_alias = ?1
+                          AND (?2 IS NULL OR a.language = ?2)
+                          AND (?3 IS NULL OR p.edition = ?3)
+                        ORDER BY p.id ASC
+                        LIMIT ?4 OFFSET ?5
+                        "#
+                    }
+                    LookupBy::Headword => {
+                        r#"
+                        SELECT p.id
+                        FROM pages p
+                        WHERE p.title = ?1
+                          AND p.deleted_at IS NULL
+                          AND (?2 IS NULL OR EXISTS (
+                              SELECT 1 FROM definitions d WHERE d.page_id = p.id AND d.language = ?2
+                          ))
+                          AND (?3 IS NULL OR p.edition = ?3)
+                        ORDER BY p.id ASC
+                        LIMIT ?4 OFFSET ?5
+                        "#
+                    }
+                };
+                let mut stmt = conn.prepare_cached(sql)?;
+                let ids = stmt
+                    .query_map(params![term, language, edition, limit, offset], |row| {
+                        row.get(0)
+                    })?
+                    .collect::<rusqlite::Result<Vec<i64>>>()?;
+                Ok(ids)
+            }
+            Backend::Postgres { pool, schema, .. } => {
+                let mut conn = pool
+                    .get()
+                    .context("failed to checkout postgres connection")?;
+                let pages = pg_table(schema, "pages");
+                let sql = match (query.by, route) {
+                    (LookupBy::Alias, Route::Hot) => format!(
+                        r#"
+                        SELECT DISTINCT h.page_id
+                        FROM {} h
+                        JOIN {pages} p ON p.id = h.page_id AND p.deleted_at IS NULL
+                        WHERE h.normalized_alias = $1
+                          AND h.language = $2::text
+                          AND ($3::text IS NULL OR p.edition = $3)
+                        ORDER BY h.page_id ASC
+                        LIMIT $4 OFFSET $5
+                        "#,
+                        pg_table(schema, "hot_lookup")
+                    ),
+                    (LookupBy::Alias, Route::Canonical) => format!(
+                        r#"
+                        SELECT DISTINCT p.id
+                        FROM {} a
+                        JOIN {pages} p ON p.id = a.page_id AND p.deleted_at IS NULL
+                        WHERE a.normalized_alias = $1
+                          AND ($2::text IS NULL OR a.language = $2)
+                          AND ($3::text IS NULL OR p.edition = $3)
+                        ORDER BY p.id ASC
+                        LIMIT $4 OFFSET $5
+                        "#,
+                        pg_table(schema, "lemma_aliases")
+                    ),
+                    (LookupBy::Headword, _) => format!(
+                        r#"
+                        SELECT p.id
+                        FROM {pages} p
+                        WHERE p.title = $1
+                          AND p.deleted_at IS NULL
+                          AND ($2::text IS NULL OR EXISTS (
+                              SELECT 1 FROM {} d WHERE d.page_id = p.id AND d.language = $2
+                          ))
+                          AND ($3::text IS NULL OR p.edition = $3)
+                        ORDER BY p.id ASC
+                        LIMIT $4 OFFSET $5
+                        "#,
+                        pg_table(schema, "definitions")
+                    ),
+                };
+                let rows = conn.query(&sql, &[&term, &language, &edition, &limit, &offset])?;
+                Ok(rows.into_iter().map(|row| row.get(0)).collect())
+            }
+        }
+    }
+
+    fn hydrate(&self, ids: &[i64], include_raw_html: bool) -> Result<Vec<ExportPage>> {
+        let mut pages = Vec::with_capacity(ids.len());
+        match &self.backend {
+            Backend::Sqlite(conn) => {
+                let conn = conn.get();
+                for &id in ids {
+                    let page = conn
+                        .query_row(
+                            &format!(
+                                "SELECT {PAGE_COLUMNS} FROM pages WHERE id = ?1 AND deleted_at IS NULL"
+                            ),
+                            [id],
+                            |row| {
+                                Ok(ExportPage {
+                                    id: row.get(0)?,
+                                    edition: row.get(10)?,
+                                    url: row.get(1)?,
+                                    title: row.get(2)?,
+                                    namespace: row.get(3)?,
+                                    mime_type: row.get(4)?,
+                                    redirect_url: row.get(5)?,
+                                    content_sha256: row.get(6)?,
+                                    extraction_confidence: row.get(7)?,
+                                    plain_text: row.get(8)?,
+                                    raw_html: if include_raw_html { row.get(9)? } else { None },
+                                    definitions: Vec::new(),
+                                    relations: Vec::new(),
+                                    aliases: Vec::new(),
+                                })
+                            },
+                        )
+                        .optional()?;
+                    let Some(mut page) = page else {
+                        continue;
+                    };
+                    page.definitions = fetch_definitions_sqlite(&conn, id)?;
+                    page.relations = fetch_relations_sqlite(&conn, id)?;
+                    page.aliases = fetch_aliases_sqlite(&conn, id)?;
+                    pages.push(page);
+                }
+            }
+            Backend::Postgres { pool, schema, .. } => {
+                let mut conn = pool
+                    .get()
+                    .context("failed to checkout postgres connection")?;
+                let sql = format!(
+                    "SELECT {PAGE_COLUMNS} FROM {} WHERE id = $1 AND deleted_at IS NULL",
+                    pg_table(schema, "pages")
+                );
+                for &id in ids {
+                    let Some(row) = conn.query_opt(&sql, &[&id])? else {
+                        continue;
+                    };
+                    pages.push(ExportPage {
+                        id,
+                        edition: row.get(10),
+                        url: row.get(1),
+                        title: row.get(2),
+                        namespace: row.get(3),
+                        mime_type: row.get(4),
+                        redirect_url: row.get(5),
+                        content_sha256: row.get(6),
+                        extraction_confidence: row.get(7),
+                        plain_text: row.get(8),
+                        raw_html: if include_raw_html { row.get(9) } else { None },
+                        definitions: fetch_definitions_postgres(&mut *conn, schema, id)?,
+                        relations: fetch_relations_postgres(&mut *conn, schema, id)?,
+                        aliases: fetch_aliases_postgres(&mut *conn, schema, id)?,
+                    });
+                }
+            }
+        }
+        Ok(pages)
+    }
+}
+
+/// Trims `language` and resolves ISO codes to the stored language name.
+/// Completions on Postgres, in byte order. The `COLLATE "C"` range is served by
+/// `idx_aliases_norm_prefix`.
+fn pg_completions_sql(schema: &str) -> String {
+    format!(
+        r#"
+        SELECT a.normalized_alias, MIN(a.alias), COUNT(DISTINCT a.page_id)
+        FROM {} a
+        JOIN {} p ON p.id = a.page_id AND p.deleted_at IS NULL
+        WHERE a.normalized_alias COLLATE "C" >= $1
+          AND a.normalized_alias COLLATE "C" < $2
+          AND ($3::text IS NULL OR a.language = $3)
+        GROUP BY a.normalized_alias
+        ORDER BY a.normalized_alias COLLATE "C" ASC
+        LIMIT $4 OFFSET $5
+        "#,
+        pg_table(schema, "lemma_aliases"),
+        pg_table(schema, "pages")
+    )
+}
+
+/// Prefix candidates on Postgres. The `COLLATE "C"` range is served by
+/// `idx_aliases_norm_prefix`.
+fn pg_prefix_matches_sql(schema: &str) -> String {
+    format!(
+        r#"
+        SELECT language, normalized_alias, alias, page_count
+        FROM (
+            SELECT a.language, a.normalized_alias, MIN(a.alias) AS alias,
+                   COUNT(DISTINCT a.page_id) AS page_count,
+                   ROW_NUMBER() OVER (
+                       PARTITION BY a.language
+                       ORDER BY length(a.normalized_alias),
+                                COUNT(DISTINCT a.page_id) DESC,
+                                a.normalized_alias COLLATE "C"
+                   ) AS n
+            FROM {} a
+            JOIN {} p ON p.id = a.page_id AND p.deleted_at IS NULL
+            WHERE a.normalized_alias COLLATE "C" >= $1
+              AND a.normalized_alias COLLATE "C" < $2
+              AND ($3::text IS NULL OR a.language = $3)
+            GROUP BY a.language, a.normalized_alias
+        ) ranked
+        WHERE n <= $4
+        "#,
+        pg_table(schema, "lemma_aliases"),
+        pg_table(schema, "pages")
+    )
+}
+
+fn resolve_language(language: Option<&str>) -> Option<String> {
+    language
+        .map(str::trim)
+        .filter(|language| !language.is_empty())
+        .map(canonical_language_name)
+}
+
+/// Formats one page for `zimrs lookup`: senses grouped by language with their confidence,
+/// then relations (with `relations`) and aliases. `language` keeps only that language's
+/// senses and relations.
+pub fn render_entry(page: &ExportPage, language: Option<&str>, relations: bool) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{}  [{} {}/{}, page {}]",
+        page.title, page.edition, page.namespace, page.url, page.id
+    );
+    if let Some(target) = &page.redirect_url {
+        let _ = writeln!(out, "  redirects to {target}");
+    }
+
+    let mut current_language = None;
+    let mut sense = 0;
+    for definition in page
+        .definitions
+        .iter()
+        .filter(|definition| language.is_none_or(|language| definition.language == language))
+    {
+        if current_language != Some(definition.language.as_str()) {
+            current_language = Some(definition.language.as_str());
+            sense = 0;
+            let _ = writeln!(out, "\n  {}", definition.language);
+        }
+        sense += 1;
+        let _ = writeln!(
+            out,
+            "    {sense:>2}. {}  ({:.2})",
+            definition.text, definition.confidence
+        );
+    }
+    if current_language.is_none() {
+        let _ = writeln!(out, "\n  no senses");
+    }
+
+    if relations {
+        let mut current_type = None;
+        for relation in page
+            .relations
+            .iter()
+            .filter(|relation| language.is_none_or(|language| relation.language == language))
+        {
+            if current_type != Some(relation.relation_type.as_str()) {
+                current_type = Some(relation.relation_type.as_str());
+                let _ = writeln!(out, "\n  {}", relation.relation_type);
+            }
+            let _ = writeln!(
+                out,
+                "    {} ({}, {:.2})",
+                relation.target_term, relation.language, relation.confidence
+            );
+        }
+        if current_type.is_none() {
+            let _ = writeln!(out, "\n  no relations");
+        }
+    }
+
+    if !page.aliases.is_empty() {
+        let aliases = page
+            .aliases
+            .iter()
+            .map(|alias| match &alias.language {
+                Some(language) => format!("{} ({language} {})", alias.alias, alias.source),
+                None => format!("{} ({})", alias.alias, alias.source),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let _ = writeln!(out, "\n  aliases: {aliases}");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use tempfile::tempdir;
+
+    use super::{Dictionary, LookupBy, LookupQuery, LookupSource, render_entry};
+    use crate::config::{Config, HotLookupMaintenance, StorageBackend};
+    use crate::db::{Database, PageLineage, test_postgres_config};
+    use crate::export::ExportRelation;
+    use crate::extractor::ExtractedPage;
+    use crate::search::SearchQuery;
+    use crate::suggest::{AliasMatch, MatchKind, SuggestQuery};
+    use crate::test_zim::page;
+
+    fn seed(db: &Database) {
+        let lineage = PageLineage::default();
+        for page in [
+            page(
+                "salve",
+                &[("English", "an ointment"), ("Latin", "hello")],
+                &[("English", "salve"), ("Latin", "salve")],
+            ),
+            page(
+                "salves",
+                &[("English", "plural of salve")],
+                &[("English", "salves"), ("English", "salve")],
+            ),
+            page("Salbe", &[("German", "ointment")], &[("German", "Salbe")]),
+            ExtractedPage {
+                edition: "fr".to_owned(),
+                ..page("salve", &[("French", "volley")], &[("French", "salve")])
+            },
+        ] {
+            db.upsert_page(&page, &lineage).expect("upsert page");
+        }
+    }
+
+    fn seed_search(db: &Database) {
+        for (url, language, text) in [
+            (
+                "cat",
+                "English",
+                "The cat is a small domesticated feline. A feline kept as a pet.",
+            ),
+            (
+                "lion",
+                "German",
+                "The lion is a large feline of Africa and India.",
+            ),
+            (
+                "catalog",
+                "English",
+                "A catalog is a systematic list of items.",
+            ),
+        ] {
+            let page = ExtractedPage {
+                plain_text: Some(text.to_owned()),
+                ..page(url, &[(language, text)], &[(language, url)])
+            };
+            db.upsert_page(&page, &PageLineage::default())
+                .expect("upsert page");
+        }
+    }
+
+    fn seed_reverse(db: &Database) {
+        for (url, language, senses) in [
+            (
+                "tabby",
+                "English",
+                &[
+                    "A domesticated cat with a striped coat.",
+                    "A gossiping old woman.",
+                ][..],
+            ),
+            ("lynx", "English", &["A wild feline with tufted ears."][..]),
+            ("Katze", "German", &["A small domesticated feline."][..]),
+        ] {
+            let definitions = senses
+                .iter()
+                .map(|sense| (language, *sense))
+                .collect::<Vec<_>>();
+            db.upsert_page(
+                &page(url, &definitions, &[(language, url)]),
+                &PageLineage::default(),
+            )
+            .expect("upsert page");
+        }
+    }
+
+    /// Expectations on [`seed_reverse`] data that hold on both backends.
+    fn assert_reverse_lookup(dictionary: &Dictionary) {
+        let reverse = |text: &str, language: Option<&str>| {
+            dictionary
+                .reverse_lookup(&SearchQuery {
+                    language: language.map(str::to_owned),
+                    ..SearchQuery::new(text)
+                })
+                .expect("reverse lookup")
+        };
+
+        // `cat` from `seed_search` also covers the whole description; the backends may order
+        // the two full matches differently but rank both above partial ones.
+        let hits = reverse("small domesticated feline", None);
+        let mut full = vec![hits[0].title.as_str(), hits[1].title.as_str()];
+        full.sort();
+        assert_eq!(full, vec!["Katze", "cat"]);
+        assert!(hits[2..].iter().all(|hit| hit.score < hits[1].score));
+        assert!(hits.iter().all(|hit| hit.score < 1.0));
+        let katze = hits
+            .iter()
+            .find(|hit| hit.title == "Katze")
+            .expect("Katze hit");
+        assert_eq!(katze.definition, "A small domesticated feline.");
+        let tabby = hits
+            .iter()
+            .find(|hit| hit.title == "tabby")
+            .expect("tabby hit");
+        assert_eq!((tabby.language.as_str(), tabby.sense), ("English", 0));
+        assert!(hits.iter().any(|hit| hit.title == "lynx"));
+        assert_eq!(hits.iter().filter(|hit| hit.title == "tabby").count(), 1);
+
+        let english = reverse("small domesticated feline", Some("en"));
+        assert!(english.iter().all(|hit| hit.language == "English"));
+        assert!(english.iter().all(|hit| hit.title != "Katze"));
+
+        let gossip = reverse("gossiping woman", None);
+        assert_eq!(gossip.len(), 1);
+        assert_eq!((gossip[0].title.as_str(), gossip[0].sense), ("tabby", 1));
+        assert!(reverse("zebra", None).is_empty());
+    }
+
+    /// Rewriting a page replaces its senses in the reverse index; expects [`seed_reverse`] data.
+    fn assert_reverse_lookup_follows_rewrites(db: &Database, dictionary: &Dictionary) {
+        db.upsert_page(
+            &page(
+                "tabby",
+                &[("English", "A striped domestic cat.")],
+                &[("English", "tabby")],
+            ),
+            &PageLineage::default(),
+        )
+        .expect("rewrite page");
+        assert!(
+            dictionary
+                .reverse_lookup(&SearchQuery::new("gossiping woman"))
+                .expect("reverse lookup")
+                .is_empty()
+        );
+        let striped = dictionary
+            .reverse_lookup(&SearchQuery::new("striped"))
+            .expect("reverse lookup");
+        assert_eq!(striped.len(), 1);
+        assert_eq!(
+            (striped[0].title.as_str(), striped[0].definition.as_str()),
+            ("tabby", "A striped domestic cat.")
+        );
+    }
+
+    /// Expectations on [`seed`] data that hold on both backends.
+    fn assert_suggestions(dictionary: &Dictionary) {
+        let suggest = |matches: Vec<AliasMatch>| {
+            matches
+                .into_iter()
+                .map(|hit| {
+                    (
+                        hit.language.unwrap_or_default(),
+                        hit.normalized_alias,
+                        hit.distance,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let owned = |rows: &[(&str, &str, usize)]| {
+            rows.iter()
+                .map(|(language, alias, distance)| {
+                    ((*language).to_owned(), (*alias).to_owned(), *distance)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let query = SuggestQuery {
+            per_language: 1,
+            ..SuggestQuery::new("SAL")
+        };
+        assert_eq!(
+            suggest(dictionary.prefix_matches(&query).expect("prefix")),
+            owned(&[
+                ("English", "salve", 2),
+                ("German", "salbe", 2),
+                ("French", "salve", 2),
+                ("Latin", "salve", 2),
+            ])
+        );
+        let query = SuggestQuery {
+            language: Some("en".to_owned()),
+            ..SuggestQuery::new("salv")
+        };
+        assert_eq!(
+            suggest(dictionary.prefix_matches(&query).expect("prefix")),
+            owned(&[("English", "salve", 1), ("English", "salves", 2)])
+        );
+
+        let query = SuggestQuery {
+            max_edits: 1,
+            ..SuggestQuery::new("salvo")
+        };
+        let fuzzy = dictionary.fuzzy_matches(&query).expect("fuzzy");
+        assert_eq!(
+            suggest(fuzzy.clone()),
+            owned(&[
+                ("English", "salve", 1),
+                ("French", "salve", 1),
+                ("Latin", "salve", 1),
+            ])
+        );
+        assert_eq!(fuzzy[0].page_count, 2);
+        assert!((fuzzy[0].similarity - 0.8).abs() < 1e-9);
+        assert!(
+            dictionary
+                .fuzzy_matches(&SuggestQuery::new("sa"))
+                .expect("fuzzy")
+                .is_empty()
+        );
+
+        let query = SuggestQuery {
+            language: Some("English".to_owned()),
+            ..SuggestQuery::new("salves")
+        };
+        let kinds = dictionary
+            .suggest(&query)
+            .expect("suggest")
+            .into_iter()
+            .map(|hit| (hit.kind, hit.normalized_alias))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                (MatchKind::Prefix, "salves".to_owned()),
+                (MatchKind::Fuzzy, "salve".to_owned()),
+            ]
+        );
+    }
+
+    /// Expectations on [`seed_search`] data that hold on both backends.
+    fn assert_search(dictionary: &Dictionary) {
+        let search = |text: &str, language: Option<&str>| {
+            dictionary
+                .search(&SearchQuery {
+                    language: language.map(str::to_owned),
+                    ..SearchQuery::new(text)
+                })
+                .expect("search")
+        };
+        let titles = |hits: &[crate::search::SearchHit]| {
+            hits.iter().map(|hit| hit.title.clone()).collect::<Vec<_>>()
+        };
+
+        let hits = search("Feline", None);
+        assert_eq!(titles(&hits), vec!["cat", "lion"]);
+        assert!(hits[0].score > hits[1].score && hits[1].score > 0.0);
+        assert!(hits.iter().all(|hit| hit.score < 1.0));
+        assert!(
+            hits[0].snippet.contains("<mark>feline</mark>"),
+            "{}",
+            hits[0].snippet
+        );
+
+        assert_eq!(
+            titles(&search("\"domesticated feline\"", None)),
+            vec!["cat"]
+        );
+        assert!(search("\"feline domesticated\"", None).is_empty());
+        let mut prefixed = titles(&search("cat*", None));
+        prefixed.sort();
+        assert_eq!(prefixed, vec!["cat", "catalog"]);
+        assert_eq!(titles(&search("feline", Some("de"))), vec!["lion"]);
+        assert!(search("feline OR list", None).is_empty());
+        assert!(search(" -- ", None).is_empty());
+    }
+
+    fn query(term: &str, language: Option<&str>) -> LookupQuery {
+        LookupQuery {
+            language: language.map(str::to_owned),
+            ..LookupQuery::new(term)
+        }
+    }
+
+    fn urls(dictionary: &Dictionary, query: &LookupQuery) -> Vec<String> {
+        dictionary
+            .lookup(query)
+            .expect("lookup")
+            .pages
+            .into_iter()
+            .map(|page| format!("{}/{}", page.edition, page.url))
+            .collect()
+    }
+
+    fn drop_schema(config: &Config) {
+        crate::db::build_pg_config(&config.postgres)
+            .expect("pg config")
+            .connect(postgres::NoTls)
+            .expect("connect to test postgres")
+            .batch_execute(&format!("DROP SCHEMA {} CASCADE;", config.postgres.schema))
+            .expect("drop schema");
+    }
+
+    fn sqlite_config(path: &Path) -> Config {
+        let mut config = Config {
+            backend: StorageBackend::Sqlite,
+            ..Config::default()
+        };
+        config.input.sqlite_path = path.to_path_buf();
+        config
+    }
+
+    #[test]
+    fn sqlite_lookups_read_the_canonical_tables() {
+        let workdir = tempdir().expect("tempdir");
+        let config = sqlite_config(&workdir.path().join("query.sqlite"));
+        let db = Database::open(&config).expect("open db");
+        db.init_schema().expect("init schema");
+        seed(&db);
+
+        let dictionary = Dictionary::open(&config).expect("open dictionary");
+        let result = dictionary
+            .lookup(&query(" SALVE ", Some("en")))
+            .expect("lookup");
+        assert_eq!(result.source, LookupSource::Canonical);
+        assert_eq!(
+            result
+                .pages
+                .iter()
+                .map(|page| page.url.as_str())
+                .collect::<Vec<_>>(),
+            vec!["salve", "salves"]
+        );
+        assert_eq!(result.pages[0].definitions.len(), 2);
+        assert_eq!(result.pages[0].aliases.len(), 2);
+        assert_eq!(result.pages[0].raw_html, None);
+
+        assert_eq!(
+            urls(&dictionary, &query("salve", None)),
+            vec!["en/salve", "en/salves", "fr/salve"]
+        );
+        assert_eq!(
+            urls(
+                &dictionary,
+                &LookupQuery {
+                    edition: Some("fr".to_owned()),
+                    ..query("salve", None)
+                }
+            ),
+            vec!["fr/salve"]
+        );
+        assert_eq!(
+            urls(
+                &dictionary,
+                &LookupQuery {
+                    by: LookupBy::Headword,
+                    ..query("salve", Some("Latin"))
+                }
+            ),
+            vec!["en/salve"]
+        );
+        assert!(urls(&dictionary, &query("salbe", Some("English"))).is_empty());
+        assert_suggestions(&dictionary);
+
+        let page = dictionary
+            .page(result.pages[0].id, true)
+            .expect("page")
+            .expect("page exists");
+        assert_eq!(page.raw_html.as_deref(), Some("<p>raw</p>"));
+
+        rusqlite::Connection::open(&config.input.sqlite_path)
+            .expect("open writer")
+            .execute(
+                "UPDATE pages SET deleted_at = 'now' WHERE url = 'salves'",
+                [],
+            )
+            .expect("delete page");
+        assert_eq!(
+            urls(&dictionary, &query("salve", Some("English"))),
+            vec!["en/salve"]
+        );
+
+        // Triggers keep the trigram index in step with rewritten aliases.
+        db.upsert_page(
+            &ExtractedPage {
+                edition: "fr".to_owned(),
+                ..self::page("salve", &[("French", "volley")], &[("French", "salvo")])
+            },
+            &PageLineage::default(),
+        )
+        .expect("upsert page");
+        let french = dictionary
+            .fuzzy_matches(&SuggestQuery {
+                language: Some("fr".to_owned()),
+                ..SuggestQuery::new("salvo")
+            })
+            .expect("fuzzy");
+        assert_eq!(
+            french
+                .iter()
+                .map(|hit| (hit.normalized_alias.as_str(), hit.distance))
+                .collect::<Vec<_>>(),
+            vec![("salvo", 0)]
+        );
+    }
+
+    #[test]
+    fn sqlite_search_ranks_and_highlights() {
+        let workdir = tempdir().expect("tempdir");
+        let config = sqlite_config(&workdir.path().join("search.sqlite"));
+        let db = Database::open(&config).expect("open db");
+        db.init_schema().expect("init schema");
+        seed_search(&db);
+
+        let dictionary = Dictionary::open(&config).expect("open dictionary");
+        assert_search(&dictionary);
+    }
+
+    #[test]
+    fn sqlite_reverse_lookup_ranks_headwords_by_best_sense() {
+        let workdir = tempdir().expect("tempdir");
+        let config = sqlite_config(&workdir.path().join("reverse.sqlite"));
+        let db = Database::open(&config).expect("open db");
+        db.init_schema().expect("init schema");
+        seed_search(&db);
+        seed_reverse(&db);
+
+        let dictionary = Dictionary::open(&config).expect("open dictionary");
+        assert_reverse_lookup(&dictionary);
+        assert_reverse_lookup_follows_rewrites(&db, &dictionary);
+
+        rusqlite::Connection::open(&config.input.sqlite_path)
+            .expect("open writer")
+            .execute("UPDATE pages SET deleted_at = 'now' WHERE url = 'lynx'", [])
+            .expect("delete page");
+        assert!(
+            dictionary
+                .reverse_lookup(&SearchQuery::new("tufted ears"))
+                .expect("reverse lookup")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn sqlite_pool_recovers_from_a_poisoned_connection() {
+        let pool = super::SqlitePool {
+            connections: vec![std::sync::Mutex::new(
+                rusqlite::Connection::open_in_memory().expect("open sqlite"),
+            )],
+            next: std::sync::atomic::AtomicUsize::new(0),
+        };
+        std::thread::scope(|scope| {
+            let poisoned = scope.spawn(|| {
+                let _guard = pool.get();
+                panic!("reader panicked");
+            });
+            assert!(poisoned.join().is_err());
+        });
+
+        let one: i64 = pool
+            .get()
+            .query_row("SELECT 1", [], |row| row.get(0))
+            .expect("query after poison");
+        assert_eq!(one, 1);
+    }
+
+    #[test]
+    fn renders_senses_grouped_by_language() {
+        let workdir = tempdir().expect("tempdir");
+        let config = sqlite_config(&workdir.path().join("render.sqlite"));
+        let db = Database::open(&config).expect("open db");
+        db.init_schema().expect("init schema");
+        seed(&db);
+
+        let dictionary = Dictionary::open(&config).expect("open dictionary");
+        let mut page = dictionary
+            .lookup(&query("salve", Some("Latin")))
+            .expect("lookup")
+            .pages
+            .remove(0);
+        page.relations.push(ExportRelation {
+            language: "English".to_owned(),
+            relation_type: "synonyms".to_owned(),
+            order: 0,
+            source_text: "unguent".to_owned(),
+            target_term: "unguent".to_owned(),
+            normalized_target: "unguent".to_owned(),
+            confidence: 0.75,
+        });
+
+        let rendered = render_entry(&page, None, true);
+        assert_eq!(
+            rendered,
+            format!(
+                "salve  [en A/salve, page {}]\n\n  English\n     1. an ointment  (1.00)\n\n  Latin\n     1. hello  (1.00)\n\n  synonyms\n    unguent (English, 0.75)\n\n  aliases: salve (English title), salve (Latin title)\n",
+                page.id
+            )
+        );
+
+        let latin = render_entry(&page, Some("Latin"), true);
+        assert!(latin.contains("  Latin\n     1. hello"));
+        assert!(!latin.contains("English\n"));
+        assert!(latin.contains("no relations"));
+        assert!(!render_entry(&page, None, false).contains("synonyms"));
+    }
+
+    #[test]
+    #[ignore = "needs ZIMRS_TEST_PG_HOST"]
+    fn hot_and_canonical_paths_return_identical_results() {
+        let schema = format!("zimrs_query_{}", std::process::id());
+        let mut config = test_postgres_config(&schema);
+        let db = Database::open(&config).expect("open db");
+        db.init_schema().expect("init schema");
+        seed(&db);
+
+        let hot = Dictionary::open(&config).expect("open hot dictionary");
+        config.hot_lookup.hot_first = false;
+        let canonical = Dictionary::open(&config).expect("open canonical dictionary");
+
+        for (term, language, expected) in [
+            ("salve", Some("English"), LookupSource::Hot),
+            ("Salve", Some("en"), LookupSource::Hot),
+            ("salbe", Some("German"), LookupSource::Hot),
+            ("salve", Some("French"), LookupSource::Hot),
+            ("salve", Some("Latin"), LookupSource::Canonical),
+            ("salve", None, LookupSource::Canonical),
+            ("missing", Some("English"), LookupSource::Fallback),
+        ] {
+            let query = LookupQuery {
+                include_raw_html: true,
+                ..query(term, language)
+            };
+            let from_hot = hot.lookup(&query).expect("hot lookup");
+            let from_canonical = canonical.lookup(&query).expect("canonical lookup");
+            assert_eq!(from_hot.source, expected, "{term} {language:?}");
+            assert_eq!(from_canonical.source, LookupSource::Canonical);
+            assert_eq!(from_hot.pages, from_canonical.pages, "{term} {language:?}");
+        }
+        assert_eq!(
+            urls(&hot, &query("salve", Some("English"))),
+            vec!["en/salve", "en/salves"]
+        );
+        assert_eq!(
+            hot.complete("SAL", None, 1, 10)
+                .expect("complete")
+                .into_iter()
+                .map(|completion| (completion.normalized_alias, completion.page_count))
+                .collect::<Vec<_>>(),
+            vec![("salve".to_owned(), 3), ("salves".to_owned(), 1)]
+        );
+        assert!(
+            hot.relation_neighbours("salve", None, None, 0, 10)
+                .expect("relations")
+                .is_empty()
+        );
+
+        config.sqlite.overwrite = false;
+        config.hot_lookup.hot_first = true;
+        config.hot_lookup.maintenance = HotLookupMaintenance::Off;
+        let db = Database::open(&config).expect("reopen db");
+        db.upsert_page(
+            &page(
+                "unguent",
+                &[("English", "an ointment")],
+                &[("English", "unguent")],
+            ),
+            &PageLineage::default(),
+        )
+        .expect("upsert without projection");
+        let unguent = query("unguent", Some("English"));
+        let result = hot.lookup(&unguent).expect("lookup");
+        assert_eq!(result.source, LookupSource::Fallback);
+        assert_eq!(
+            result.pages,
+            canonical.lookup(&unguent).expect("lookup").pages
+        );
+
+        db.rebuild_hot_lookup().expect("rebuild projection");
+        assert_eq!(
+            hot.lookup(&unguent).expect("lookup").source,
+            LookupSource::Hot
+        );
+
+        drop_schema(&config);
+    }
+
+    #[test]
+    #[ignore = "needs ZIMRS_TEST_PG_HOST"]
+    fn postgres_suggestions_rank_completions_and_typos() {
+        let schema = format!("zimrs_suggest_{}", std::process::id());
+        let config = test_postgres_config(&schema);
+        let db = Database::open(&config).expect("open db");
+        db.init_schema().expect("init schema");
+        seed(&db);
+
+        assert_suggestions(&Dictionary::open(&config).expect("open dictionary"));
+
+        drop_schema(&config);
+    }
+
+    #[test]
+    #[ignore = "needs ZIMRS_TEST_PG_HOST"]
+    fn postgres_search_ranks_highlights_and_stems() {
+        let schema = format!("zimrs_search_{}", std::process::id());
+        let config = test_postgres_config(&schema);
+        let db = Database::open(&config).expect("open db");
+        db.init_schema().expect("init schema");
+        seed(&db);
+        seed_search(&db);
+
+        let dictionary = Dictionary::open(&config).expect("open dictionary");
+        assert_search(&dictionary);
+        // Only the per-language vectors stem, so inflections match when a language is given.
+        assert!(
+            dictionary
+                .search(&SearchQuery::new("systematic lists"))
+                .expect("search")
+                .is_empty()
+        );
+        let stemmed = dictionary
+            .search(&SearchQuery {
+                language: Some("en".to_owned()),
+                ..SearchQuery::new("systematic lists")
+            })
+            .expect("search");
+        assert_eq!(stemmed.len(), 1);
+        assert_eq!(stemmed[0].title, "catalog");
+        assert!(
+            stemmed[0].snippet.contains("<mark>list</mark>"),
+            "{}",
+            stemmed[0].snippet
+        );
+        let hits = dictionary
+            .search(&SearchQuery {
+                language: Some("en".to_owned()),
+                ..SearchQuery::new("salves")
+            })
+            .expect("search");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].title, "salves");
+
+        drop_schema(&config);
+    }
+
+    #[test]
+    #[ignore = "needs ZIMRS_TEST_PG_HOST"]
+    fn postgres_reverse_lookup_ranks_headwords_by_best_sense() {
+        let schema = format!("zimrs_reverse_{}", std::process::id());
+        let config = test_postgres_config(&schema);
+        let db = Database::open(&config).expect("open db");
+        db.init_schema().expect("init schema");
+        seed_search(&db);
+        seed_reverse(&db);
+
+        let dictionary = Dictionary::open(&config).expect("open dictionary");
+        assert_reverse_lookup(&dictionary);
+        // Senses are stemmed, so an inflected description still reaches them.
+        let stemmed = dictionary
+            .reverse_lookup(&SearchQuery {
+                language: Some("en".to_owned()),
+                ..SearchQuery::new("tufts")
+            })
+            .expect("reverse lookup");
+        assert_eq!(stemmed.len(), 1);
+        assert_eq!(stemmed[0].title, "lynx");
+        assert_reverse_lookup_follows_rewrites(&db, &dictionary);
+
+        drop_schema(&config);
+    }
+
+    #[test]
+    #[ignore = "needs ZIMRS_TEST_PG_HOST"]
+    fn postgres_reverse_lookup_is_maintained_with_fts_disabled() {
+        let schema = format!("zimrs_reverse_nofts_{}", std::process::id());
+        let mut config = test_postgres_config(&schema);
+        let db = Database::open(&config).expect("open db");
+        db.init_schema().expect("init schema");
+        seed_reverse(&db);
+
+        // Rewrites with search indexing off keep the sense index and drop the search rows.
+        config.sqlite.enable_fts = false;
+        let db = Database::open(&config).expect("open db without fts");
+        let dictionary = Dictionary::open(&config).expect("open dictionary");
+        assert_reverse_lookup_follows_rewrites(&db, &dictionary);
+
+        let mut client = crate::db::build_pg_config(&config.postgres)
+            .expect("pg config")
+            .connect(postgres::NoTls)
+            .expect("connect to test postgres");
+        let search_rows: i64 = client
+            .query_one(
+                &format!(
+                    "SELECT (SELECT COUNT(*) FROM {schema}.page_fts f \
+                             JOIN {schema}.pages p ON p.id = f.page_id WHERE p.url = 'tabby') \
+                          + (SELECT COUNT(*) FROM {schema}.page_language_fts f \
+                             JOIN {schema}.pages p ON p.id = f.page_id WHERE p.url = 'tabby')"
+                ),
+                &[],
+            )
+            .expect("count search rows")
+            .get(0);
+        assert_eq!(search_rows, 0);
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE;"))
+            .expect("drop schema");
+    }
+
+    /// The plan for `sql` with sequential scans disabled, so any usable index shows up.
+    fn explain(
+        client: &mut postgres::Client,
+        sql: &str,
+        params: &[&(dyn postgres::types::ToSql + Sync)],
+    ) -> String {
+        let mut tx = client.transaction().expect("begin");
+        tx.batch_execute("SET LOCAL enable_seqscan = off")
+            .expect("disable seqscan");
+        let plan = tx
+            .query(&format!("EXPLAIN (COSTS OFF) {sql}"), params)
+            .expect("explain")
+            .iter()
+            .map(|row| row.get::<_, String>(0))
+            .collect::<Vec<_>>()
+            .join("\n");
+        tx.rollback().expect("rollback");
+        plan
+    }
+
+    #[test]
+    #[ignore = "needs ZIMRS_TEST_PG_HOST"]
+    fn postgres_prefix_queries_use_the_prefix_index() {
+        let schema = format!("zimrs_prefix_plan_{}", std::process::id());
+        let config = test_postgres_config(&schema);
+        let db = Database::open(&config).expect("open db");
+        db.init_schema().expect("init schema");
+        seed(&db);
+
+        let mut client = crate::db::build_pg_config(&config.postgres)
+            .expect("pg config")
+            .connect(postgres::NoTls)
+            .expect("connect to test postgres");
+        let upper = format!("salv{}", char::MAX);
+        let plan = explain(
+            &mut client,
+            &super::pg_prefix_matches_sql(&schema),
+            &[&"salv", &upper, &None::<String>, &5_i64],
+        );
+        assert!(plan.contains("idx_aliases_norm_prefix"), "{plan}");
+        let plan = explain(
+            &mut client,
+            &super::pg_completions_sql(&schema),
+            &[&"salv", &upper, &None::<String>, &20_i64, &0_i64],
+        );
+        assert!(plan.contains("idx_aliases_norm_prefix"), "{plan}");
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE;"))
+            .expect("drop schema");
+    }
+
+    #[test]
+    #[ignore = "needs ZIMRS_TEST_PG_HOST"]
+    fn postgres_suggestions_fall_back_to_prefixes_without_pg_trgm() {
+        let schema = format!("zimrs_no_trgm_{}", std::process::id());
+        let mut config = test_postgres_config(&schema);
+        let db = Database::open(&config).expect("open db");
+        db.init_schema().expect("init schema");
+        seed(&db);
+        let mut client = crate::db::build_pg_config(&config.postgres)
+            .expect("pg config")
+            .connect(postgres::NoTls)
+            .expect("connect to test postgres");
+        client
+            .batch_execute(&format!("DROP INDEX {schema}.idx_aliases_norm_trgm;"))
+            .expect("drop trigram index");
+
+        let salvo = |dictionary: &Dictionary| {
+            dictionary
+                .suggest(&SuggestQuery::new("salvo"))
+                .expect("suggest")
+                .into_iter()
+                .map(|hit| (hit.normalized_alias, hit.kind))
+                .collect::<Vec<_>>()
+        };
+        let prefix_only = Dictionary::open(&config).expect("open dictionary");
+        assert!(salvo(&prefix_only).is_empty());
+        let completions = prefix_only
+            .suggest(&SuggestQuery::new("salv"))
+            .expect("suggest");
+        assert!(!completions.is_empty());
+        assert!(completions.iter().all(|hit| hit.kind == MatchKind::Prefix));
+
+        // With the extension present, opening the database restores the index.
+        config.sqlite.overwrite = false;
+        Database::open(&config)
+            .expect("reopen db")
+            .init_schema()
+            .expect("init schema");
+        let fuzzy = Dictionary::open(&config).expect("open dictionary");
+        assert!(salvo(&fuzzy).contains(&("salve".to_owned(), MatchKind::Fuzzy)));
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE;"))
+            .expect("drop schema");
+    }
+}
@@ -27,7 +27,7 @@ pub fn create_sample_database(path: &Path) -> Result<()> {
 
     let conn = Connection::open(path)
         .with_context(|| format!("failed to open sample db {}", path.display()))?;
-    migrate_connection(&conn, true)?;
+    migrate_connection(&conn)?;
 
     conn.execute(
         r#"
@@ -0,0 +1,377 @@
+use std::collections::{BTreeMap, HashMap};
+use std::panic::{self, AssertUnwindSafe};
+
+use anyhow::Result;
+use tracing::{debug, info};
+use zim::{Target, Zim};
+
+use crate::config::{Config, GuardAction, SampleStratify};
+use crate::extractor::{extract_from_html, namespace_code};
+use crate::pipeline::{should_select_entry, skipped_before_parsing};
+use crate::selection::SelectionPatterns;
+
+/// Entries picked for a `sample.size` run, sorted by entry index.
+#[derive(Debug, Clone, Default)]
+pub struct SamplePlan {
+    pub entry_indexes: Vec<u32>,
+    /// Content entries in the window that pass the selection filters.
+    pub selectable_entries: u64,
+    pub strata: Vec<SampleStratum>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SampleStratum {
+    pub key: String,
+    /// Selectable entries in the stratum. For language strata this counts the oversampled
+    /// candidates, not the whole archive.
+    pub population: u64,
+    pub sampled: usize,
+}
+
+/// Draws a reproducible sample of `sample.size` selectable content entries from `[start, end)`
+/// in a single pass over the directory. The same archive, selection and seed always yield the
+/// same entries.
+pub fn plan_sample(
+    zim: &Zim,
+    config: &Config,
+    patterns: &SelectionPatterns,
+    start: u32,
+    end: u32,
+) -> Result<SamplePlan> {
+    let sample = &config.sample;
+    let Some(size) = sample.size else {
+        anyhow::bail!("sample.size is not set");
+    };
+    if size == 0 {
+        anyhow::bail!("sample.size must be at least 1");
+    }
+
+    let mut rng = SplitMix64::new(sample.seed);
+    let capacity = match sample.stratify {
+        SampleStratify::Language => size.saturating_mul(sample.language_oversample.max(1)),
+        SampleStratify::None | SampleStratify::Namespace => size,
+    };
+
+    let mut reservoirs: BTreeMap<String, Reservoir> = BTreeMap::new();
+    let mut selectable_entries = 0_u64;
+    for entry_index in start..end {
+        let entry = match zim.get_by_url_index(entry_index) {
+            Ok(entry) => entry,
+            Err(error) => {
+                debug!(entry_index, %error, "skipping unreadable entry while sampling");
+                continue;
+            }
+        };
+        if !matches!(entry.target, Some(Target::Cluster(..)))
+            || !should_select_entry(&entry, config, patterns)
+        {
+            continue;
+        }
+
+        selectable_entries += 1;
+        let key = match sample.stratify {
+            SampleStratify::Namespace => namespace_code(entry.namespace).to_owned(),
+            SampleStratify::None | SampleStratify::Language => String::new(),
+        };
+        reservoirs
+            .entry(key)
+            .or_insert_with(|| Reservoir::new(capacity))
+            .offer(entry_index, &mut rng);
+    }
+
+    let mut groups = match sample.stratify {
+        SampleStratify::Language => {
+            let candidates = reservoirs
+                .into_values()
+                .flat_map(|reservoir| reservoir.items)
+                .collect::<Vec<_>>();
+            group_by_language(zim, config, candidates)
+        }
+        SampleStratify::None | SampleStratify::Namespace => reservoirs
+            .into_iter()
+            .map(|(key, reservoir)| (key, reservoir.seen, reservoir.items))
+            .collect(),
+    };
+
+    let populations = groups
+        .iter()
+        .map(|(_, population, items)| (*population, items.len()))
+        .collect::<Vec<_>>();
+    let quotas = allocate(size, &populations);
+
+    let mut plan = SamplePlan {
+        selectable_entries,
+        ..SamplePlan::default()
+    };
+    for ((key, population, items), quota) in groups.iter_mut().zip(quotas) {
+        rng.shuffle(items);
+        plan.entry_indexes.extend_from_slice(&items[..quota]);
+        plan.strata.push(SampleStratum {
+            key: if key.is_empty() {
+                "all".to_owned()
+            } else {
+                key.clone()
+            },
+            population: *population,
+            sampled: quota,
+        });
+    }
+    plan.entry_indexes.sort_unstable();
+
+    info!(
+        requested = size,
+        sampled = plan.entry_indexes.len(),
+        selectable_entries,
+        seed = sample.seed,
+        stratify = ?sample.stratify,
+        strata = plan.strata.len(),
+        "planned sample"
+    );
+    Ok(plan)
+}
+
+/// Keys each candidate by the language with the most extracted definitions, `unknown` when the
+/// entry cannot be read, has none, or would be skipped by `extraction.guards`.
+fn group_by_language(
+    zim: &Zim,
+    config: &Config,
+    candidates: Vec<u32>,
+) -> Vec<(String, u64, Vec<u32>)> {
+    let mut groups: BTreeMap<String, Vec<u32>> = BTreeMap::new();
+    for entry_index in candidates {
+        let language =
+            primary_language(zim, config, entry_index).unwrap_or_else(|| "unknown".to_owned());
+        groups.entry(language).or_default().push(entry_index);
+    }
+
+    groups
+        .into_iter()
+        .map(|(language, items)| (language, items.len() as u64, items))
+        .collect()
+}
+
+fn primary_language(zim: &Zim, config: &Config, entry_index: u32) -> Option<String> {
+    let entry = zim.get_by_url_index(entry_index).ok()?;
+    let Some(Target::Cluster(cluster_idx, blob_idx)) = entry.target else {
+        return None;
+    };
+    let cluster = zim.get_cluster(cluster_idx).ok()?;
+    let blob = cluster.get_blob(blob_idx).ok()?;
+    let html = String::from_utf8_lossy(blob.as_ref());
+    // Same guards as convert: pages it would skip, or that panic the extractor, have no language.
+    let guards = &config.extraction.guards;
+    if skipped_before_parsing(guards, &html).is_some() {
+        return None;
+    }
+    let extraction = panic::catch_unwind(AssertUnwindSafe(|| {
+        extract_from_html(&entry.title, &html, &config.extraction)
+    }))
+    .ok()?;
+    if !extraction.guard_trips.is_empty() && guards.on_exceed == GuardAction::Skip {
+        return None;
+    }
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for definition in &extraction.definitions {
+        *counts.entry(definition.language.as_str()).or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+        .map(|(language, _)| language.to_owned())
+}
+
+/// Splits `size` across strata given as `(population, available)`. Every non-empty stratum gets
+/// one entry while the budget lasts, largest first; the rest goes out by the D'Hondt rule, which
+/// keeps quotas proportional to population and never exceeds what a stratum holds.
+fn allocate(size: usize, strata: &[(u64, usize)]) -> Vec<usize> {
+    let mut quotas = vec![0; strata.len()];
+    let total = size.min(strata.iter().map(|(_, available)| available).sum());
+
+    let mut by_population = (0..strata.len())
+        .filter(|&i| strata[i].1 > 0)
+        .collect::<Vec<_>>();
+    by_population.sort_by(|&a, &b| strata[b].0.cmp(&strata[a].0).then(a.cmp(&b)));
+    for &i in by_population.iter().take(total) {
+        quotas[i] = 1;
+    }
+
+    let mut assigned = by_population.len().min(total);
+    while assigned < total {
+        let next = (0..strata.len())
+            .filter(|&i| quotas[i] < strata[i].1)
+            .max_by(|&a, &b| {
+                // Compare population / (quota + 1) without floats; ties go to the earlier stratum.
+                let left = u128::from(strata[a].0) * (quotas[b] as u128 + 1);
+                let right = u128::from(strata[b].0) * (quotas[a] as u128 + 1);
+                left.cmp(&right).then(b.cmp(&a))
+            })
+            .expect("total never exceeds the available entries");
+        quotas[next] += 1;
+        assigned += 1;
+    }
+
+    quotas
+}
+
+/// Uniform sample of up to `capacity` items from a stream of unknown length (Algorithm R).
+struct Reservoir {
+    capacity: usize,
+    seen: u64,
+    items: Vec<u32>,
+}
+
+impl Reservoir {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            seen: 0,
+            items: Vec::new(),
+        }
+    }
+
+    fn offer(&mut self, item: u32, rng: &mut SplitMix64) {
+        self.seen += 1;
+        if self.items.len() < self.capacity {
+            self.items.push(item);
+            return;
+        }
+
+        let slot = rng.below(self.seen) as usize;
+        if slot < self.capacity {
+            self.items[slot] = item;
+        }
+    }
+}
+
+/// Small, fixed PRNG so a seed picks the same sample on every platform and release.
+struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `0..bound` by multiply-high, which is close enough to unbiased for
+    /// sampling purposes.
+    fn below(&mut self, bound: u64) -> u64 {
+        ((u128::from(self.next_u64()) * u128::from(bound)) >> 64) as u64
+    }
+
+    fn shuffle(&mut self, items: &mut [u32]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SampleConfig;
+    use crate::test_zim::{FixtureEntry, write_test_zim};
+    use tempfile::tempdir;
+
+    #[test]
+    fn allocation_is_proportional_and_covers_small_strata() {
+        assert_eq!(allocate(10, &[(80, 80), (20, 20)]), vec![8, 2]);
+        assert_eq!(allocate(10, &[(1000, 1000), (1, 1)]), vec![9, 1]);
+        assert_eq!(allocate(10, &[(50, 3), (50, 50)]), vec![3, 7]);
+        assert_eq!(allocate(1, &[(10, 10), (90, 90)]), vec![0, 1]);
+        assert_eq!(allocate(10, &[(2, 2), (0, 0), (3, 3)]), vec![2, 0, 3]);
+    }
+
+    #[test]
+    fn samples_are_reproducible_per_seed_and_stratified() {
+        let workdir = tempdir().expect("tempdir");
+        let zim_path = workdir.path().join("sample.zim");
+        let mut entries = (0..40)
+            .map(|i| FixtureEntry::article(&format!("word{i:02}"), "<p>word</p>"))
+            .collect::<Vec<_>>();
+        entries.extend((0..10).map(|i| FixtureEntry::Article {
+            namespace: b'C',
+            url: format!("content{i}"),
+            title: format!("content{i}"),
+            html: "<p>content</p>".to_owned(),
+        }));
+        entries.push(FixtureEntry::metadata("Title", "fixture"));
+        write_test_zim(&zim_path, &entries);
+        let zim = Zim::new(&zim_path).expect("open zim");
+
+        let mut config = Config::default();
+        config.selection.include_namespaces = vec!["A".to_owned(), "C".to_owned()];
+        config.sample = SampleConfig {
+            size: Some(10),
+            seed: 7,
+            stratify: SampleStratify::Namespace,
+            ..SampleConfig::default()
+        };
+        let patterns = SelectionPatterns::default();
+        let total = zim.header.article_count;
+
+        let first = plan_sample(&zim, &config, &patterns, 0, total).expect("plan sample");
+        let again = plan_sample(&zim, &config, &patterns, 0, total).expect("plan sample");
+        assert_eq!(first.entry_indexes, again.entry_indexes);
+        assert_eq!(first.selectable_entries, 50);
+        assert_eq!(first.entry_indexes.len(), 10);
+        assert!(first.entry_indexes.is_sorted());
+        assert_eq!(
+            first
+                .strata
+                .iter()
+                .map(|stratum| (stratum.key.as_str(), stratum.sampled))
+                .collect::<Vec<_>>(),
+            vec![("A", 8), ("C", 2)]
+        );
+
+        config.sample.seed = 8;
+        let reseeded = plan_sample(&zim, &config, &patterns, 0, total).expect("plan sample");
+        assert_ne!(first.entry_indexes, reseeded.entry_indexes);
+    }
+
+    #[test]
+    fn language_keys_follow_the_convert_guards() {
+        let workdir = tempdir().expect("tempdir");
+        let zim_path = workdir.path().join("guards.zim");
+        let items = "<li>A sample definition.</li>".repeat(3);
+        write_test_zim(
+            &zim_path,
+            &[
+                FixtureEntry::article(
+                    "plain",
+                    "<h2>English</h2><ol><li>A sample definition.</li></ol>",
+                ),
+                FixtureEntry::article("long", &format!("<h2>English</h2><ol>{items}</ol>")),
+            ],
+        );
+        let zim = Zim::new(&zim_path).expect("open zim");
+        let mut config = Config::default();
+        config.extraction.guards.max_list_items = 2;
+        let language = |config: &Config| {
+            (0..2)
+                .map(|index| primary_language(&zim, config, index))
+                .collect::<Vec<_>>()
+        };
+
+        let english = Some("English".to_owned());
+        assert_eq!(language(&config), vec![english.clone(), english.clone()]);
+
+        config.extraction.guards.on_exceed = GuardAction::Skip;
+        assert_eq!(language(&config), vec![None, english.clone()]);
+
+        config.extraction.guards.max_list_items = 20;
+        config.extraction.guards.max_html_bytes = 60;
+        assert_eq!(language(&config), vec![None, english]);
+    }
+}
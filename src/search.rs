@@ -0,0 +1,285 @@
+use std::fmt::Write as _;
+
+use serde::Serialize;
+
+/// Marks matched words in [`SearchHit::snippet`].
+pub const HIGHLIGHT_START: &str = "<mark>";
+pub const HIGHLIGHT_END: &str = "</mark>";
+
+/// A full-text query over `page_fts`. `text` is a list of terms that must all match: bare
+/// words, `"quoted phrases"` and `prefix*` terms (also `"phrase prefi"*`).
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    pub text: String,
+    /// Language name or ISO code; keeps pages with a definition in that language.
+    pub language: Option<String>,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl SearchQuery {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            language: None,
+            offset: 0,
+            limit: 20,
+        }
+    }
+}
+
+/// A page matched by a [`SearchQuery`], best first.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchHit {
+    pub page_id: i64,
+    pub edition: String,
+    pub title: String,
+    pub url: String,
+    /// Relevance in `[0, 1)`, see [`normalize_score`].
+    pub score: f64,
+    /// Best matching fragment of the page text, matches wrapped in [`HIGHLIGHT_START`] and
+    /// [`HIGHLIGHT_END`].
+    pub snippet: String,
+}
+
+/// A headword found by [`crate::query::Dictionary::reverse_lookup`], with the sense that
+/// matched the description best.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReverseHit {
+    pub page_id: i64,
+    pub edition: String,
+    pub title: String,
+    pub url: String,
+    pub language: String,
+    /// `def_order` of the best sense within `language`.
+    pub sense: i64,
+    pub definition: String,
+    /// Relevance of the best sense in `[0, 1)`, see [`normalize_score`].
+    pub score: f64,
+}
+
+/// One required term: a single word or a phrase, whose last word may be a prefix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SearchTerm {
+    pub words: Vec<String>,
+    pub prefix: bool,
+}
+
+/// Splits query text into terms. Words are lowercased runs of letters and digits, so the
+/// rendered backend queries never contain user-supplied operators.
+pub(crate) fn parse_search_query(text: &str) -> Vec<SearchTerm> {
+    let mut terms = Vec::new();
+    let mut rest = text.trim_start();
+    while !rest.is_empty() {
+        let (raw, after) = if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            let after = quoted.get(end + 1..).unwrap_or_default();
+            match after.strip_prefix('*') {
+                Some(after) => ((&quoted[..end], true), after),
+                None => ((&quoted[..end], false), after),
+            }
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let token = &rest[..end];
+            match token.strip_suffix('*') {
+                Some(token) => ((token, true), &rest[end..]),
+                None => ((token, false), &rest[end..]),
+            }
+        };
+
+        let (raw, prefix) = raw;
+        let words = raw
+            .split(|ch: char| !ch.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>();
+        if !words.is_empty() {
+            terms.push(SearchTerm { words, prefix });
+        }
+        rest = after.trim_start();
+    }
+    terms
+}
+
+/// FTS5 query syntax: every term is a quoted string, `*` after it makes the last word a
+/// prefix, and juxtaposed terms are ANDed.
+pub(crate) fn fts5_query(terms: &[SearchTerm]) -> String {
+    fts5_terms(terms).join(" ")
+}
+
+/// Like [`fts5_query`], but any term may match; reverse lookups describe a word loosely and
+/// rank by how much of the description a sense covers.
+pub(crate) fn fts5_query_any(terms: &[SearchTerm]) -> String {
+    fts5_terms(terms).join(" OR ")
+}
+
+fn fts5_terms(terms: &[SearchTerm]) -> Vec<String> {
+    terms
+        .iter()
+        .map(|term| {
+            let star = if term.prefix { "*" } else { "" };
+            format!("\"{}\"{star}", term.words.join(" "))
+        })
+        .collect()
+}
+
+/// `to_tsquery` syntax: phrases use `<->`, prefixes `:*` and terms are joined with `&`.
+pub(crate) fn tsquery(terms: &[SearchTerm]) -> String {
+    tsquery_terms(terms).join(" & ")
+}
+
+/// Like [`tsquery`], but terms are joined with `|`.
+pub(crate) fn tsquery_any(terms: &[SearchTerm]) -> String {
+    tsquery_terms(terms).join(" | ")
+}
+
+fn tsquery_terms(terms: &[SearchTerm]) -> Vec<String> {
+    terms
+        .iter()
+        .map(|term| {
+            let last = term.words.len() - 1;
+            let words = term
+                .words
+                .iter()
+                .enumerate()
+                .map(|(index, word)| {
+                    if term.prefix && index == last {
+                        format!("{word}:*")
+                    } else {
+                        word.clone()
+                    }
+                })
+                .collect::<Vec<_>>();
+            format!("({})", words.join(" <-> "))
+        })
+        .collect()
+}
+
+/// Maps a non-negative backend rank `r` (negated `bm25` on SQLite, length-normalized
+/// `ts_rank_cd` on Postgres) to `r / (r + 1)`, the form Postgres' rank normalization flag 32
+/// uses, so scores from both backends fall in `[0, 1)` and order the same way.
+pub(crate) fn normalize_score(rank: f64) -> f64 {
+    let rank = rank.max(0.0);
+    rank / (rank + 1.0)
+}
+
+/// Formats hits for `zimrs search`, numbered from `offset + 1`, with highlights shown as
+/// `[match]`.
+pub fn render_search_hits(hits: &[SearchHit], offset: usize) -> String {
+    let mut out = String::new();
+    for (index, hit) in hits.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "{:>3}. {}  [{} {}, page {}]  score {:.3}",
+            offset + index + 1,
+            hit.title,
+            hit.edition,
+            hit.url,
+            hit.page_id,
+            hit.score
+        );
+        let snippet = hit
+            .snippet
+            .replace(HIGHLIGHT_START, "[")
+            .replace(HIGHLIGHT_END, "]");
+        if !snippet.trim().is_empty() {
+            let _ = writeln!(out, "     {}", snippet.trim());
+        }
+    }
+    out
+}
+
+/// Formats hits for `zimrs reverse-lookup`, numbered from `offset + 1`, each with its best
+/// sense.
+pub fn render_reverse_hits(hits: &[ReverseHit], offset: usize) -> String {
+    let mut out = String::new();
+    for (index, hit) in hits.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "{:>3}. {}  [{} {}, page {}]  score {:.3}",
+            offset + index + 1,
+            hit.title,
+            hit.edition,
+            hit.url,
+            hit.page_id,
+            hit.score
+        );
+        let _ = writeln!(
+            out,
+            "     {} {}. {}",
+            hit.language,
+            hit.sense + 1,
+            hit.definition
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(words: &[&str], prefix: bool) -> SearchTerm {
+        SearchTerm {
+            words: words.iter().map(|word| (*word).to_owned()).collect(),
+            prefix,
+        }
+    }
+
+    #[test]
+    fn parses_words_phrases_and_prefixes() {
+        let terms =
+            parse_search_query(r#" Small "domesticated Feline" cat* "house ca"* e-mail "open "#);
+        assert_eq!(
+            terms,
+            vec![
+                term(&["small"], false),
+                term(&["domesticated", "feline"], false),
+                term(&["cat"], true),
+                term(&["house", "ca"], true),
+                term(&["e", "mail"], false),
+                term(&["open"], false),
+            ]
+        );
+        assert!(parse_search_query(r#" "" * -- "#).is_empty());
+        assert_eq!(
+            parse_search_query(r#"OR NEAR("x") ^col:y"#),
+            vec![
+                term(&["or"], false),
+                term(&["near", "x"], false),
+                term(&["col", "y"], false),
+            ]
+        );
+
+        let terms = [term(&["small"], false), term(&["house", "ca"], true)];
+        assert_eq!(fts5_query(&terms), r#""small" "house ca"*"#);
+        assert_eq!(tsquery(&terms), "(small) & (house <-> ca:*)");
+        assert_eq!(fts5_query_any(&terms), r#""small" OR "house ca"*"#);
+        assert_eq!(tsquery_any(&terms), "(small) | (house <-> ca:*)");
+    }
+
+    #[test]
+    fn renders_hits_with_bracketed_highlights() {
+        let hit = SearchHit {
+            page_id: 3,
+            edition: "en".to_owned(),
+            title: "cat".to_owned(),
+            url: "cat".to_owned(),
+            score: 0.5,
+            snippet: "a small <mark>feline</mark>".to_owned(),
+        };
+        assert_eq!(
+            render_search_hits(&[hit], 10),
+            " 11. cat  [en cat, page 3]  score 0.500\n     a small [feline]\n"
+        );
+    }
+
+    #[test]
+    fn normalized_scores_are_bounded_and_monotonic() {
+        assert_eq!(normalize_score(0.0), 0.0);
+        assert_eq!(normalize_score(-3.0), 0.0);
+        assert_eq!(normalize_score(1.0), 0.5);
+        assert!(normalize_score(2.0) < normalize_score(3.0));
+        assert!(normalize_score(1e12) < 1.0);
+    }
+}
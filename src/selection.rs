@@ -0,0 +1,183 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use regex::RegexSet;
+use zim::Zim;
+
+use crate::archive::find_by_title;
+use crate::config::SelectionConfig;
+
+/// Compiled URL/title regexes from `[selection]`. An empty include list admits everything; any
+/// exclude match rejects.
+#[derive(Debug, Clone, Default)]
+pub struct SelectionPatterns {
+    include_url: Option<RegexSet>,
+    exclude_url: Option<RegexSet>,
+    include_title: Option<RegexSet>,
+    exclude_title: Option<RegexSet>,
+}
+
+impl SelectionPatterns {
+    pub fn compile(selection: &SelectionConfig) -> Result<Self> {
+        Ok(Self {
+            include_url: compile_set("include_url_patterns", &selection.include_url_patterns)?,
+            exclude_url: compile_set("exclude_url_patterns", &selection.exclude_url_patterns)?,
+            include_title: compile_set(
+                "include_title_patterns",
+                &selection.include_title_patterns,
+            )?,
+            exclude_title: compile_set(
+                "exclude_title_patterns",
+                &selection.exclude_title_patterns,
+            )?,
+        })
+    }
+
+    pub fn allows_url(&self, url: &str) -> bool {
+        allows(&self.include_url, &self.exclude_url, url)
+    }
+
+    pub fn allows_title(&self, title: &str) -> bool {
+        allows(&self.include_title, &self.exclude_title, title)
+    }
+}
+
+fn compile_set(field: &str, patterns: &[String]) -> Result<Option<RegexSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    RegexSet::new(patterns)
+        .map(Some)
+        .with_context(|| format!("invalid regex in selection.{field}"))
+}
+
+fn allows(include: &Option<RegexSet>, exclude: &Option<RegexSet>, value: &str) -> bool {
+    if let Some(include) = include
+        && !include.is_match(value)
+    {
+        return false;
+    }
+
+    !exclude
+        .as_ref()
+        .is_some_and(|exclude| exclude.is_match(value))
+}
+
+/// Reads a title list: one title per line, blank lines and `#` comments ignored.
+pub fn load_title_list(path: &Path) -> Result<Vec<String>> {
+    let raw = fs::read_to_string(path)
+        .with_context(|| format!("failed to read title list {}", path.display()))?;
+
+    Ok(raw
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(ToOwned::to_owned)
+        .collect())
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TitleListResolution {
+    /// URL indexes of the listed entries, sorted and deduplicated.
+    pub entry_indexes: Vec<u32>,
+    pub missing_titles: Vec<String>,
+}
+
+/// Finds each title in the archive's title pointer list, trying `namespaces` in order.
+pub fn resolve_title_list(
+    zim: &Zim,
+    titles: &[String],
+    namespaces: &[String],
+) -> TitleListResolution {
+    let default_namespaces = ["A".to_owned(), "C".to_owned()];
+    let namespaces = if namespaces.is_empty() {
+        &default_namespaces[..]
+    } else {
+        namespaces
+    };
+
+    let mut resolution = TitleListResolution::default();
+    for title in titles {
+        let found = namespaces
+            .iter()
+            .find_map(|namespace| find_by_title(zim, namespace, title));
+        match found {
+            Some((entry_index, _)) => resolution.entry_indexes.push(entry_index),
+            None => resolution.missing_titles.push(title.clone()),
+        }
+    }
+
+    resolution.entry_indexes.sort_unstable();
+    resolution.entry_indexes.dedup();
+    resolution
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_zim::{FixtureEntry, write_test_zim};
+    use tempfile::tempdir;
+
+    #[test]
+    fn patterns_apply_includes_then_excludes() {
+        let selection = SelectionConfig {
+            include_title_patterns: vec!["^[a-z]+$".to_owned()],
+            exclude_title_patterns: vec!["^zz".to_owned()],
+            exclude_url_patterns: vec!["/".to_owned()],
+            ..SelectionConfig::default()
+        };
+        let patterns = SelectionPatterns::compile(&selection).expect("compile");
+
+        assert!(patterns.allows_title("word"));
+        assert!(!patterns.allows_title("Word"));
+        assert!(!patterns.allows_title("zzz"));
+        assert!(patterns.allows_url("word"));
+        assert!(!patterns.allows_url("word/translations"));
+
+        let invalid = SelectionConfig {
+            include_url_patterns: vec!["(".to_owned()],
+            ..SelectionConfig::default()
+        };
+        assert!(SelectionPatterns::compile(&invalid).is_err());
+    }
+
+    fn titled_article(url: &str, title: &str) -> FixtureEntry {
+        FixtureEntry::Article {
+            namespace: b'A',
+            url: url.to_owned(),
+            title: title.to_owned(),
+            html: format!("<p>{title}</p>"),
+        }
+    }
+
+    #[test]
+    fn title_list_resolves_through_title_index() {
+        let workdir = tempdir().expect("tempdir");
+        let zim_path = workdir.path().join("titles.zim");
+        write_test_zim(
+            &zim_path,
+            &[
+                titled_article("w1", "cherry"),
+                titled_article("w2", "banana"),
+                titled_article("w3", "apple"),
+                FixtureEntry::metadata("Title", "fixture"),
+            ],
+        );
+        let zim = Zim::new(&zim_path).expect("open zim");
+
+        let list_path = workdir.path().join("words.txt");
+        fs::write(&list_path, "# wanted\ncherry\n\napple\nmissing\ncherry\n").expect("write list");
+        let titles = load_title_list(&list_path).expect("load list");
+        assert_eq!(titles, vec!["cherry", "apple", "missing", "cherry"]);
+
+        let resolution = resolve_title_list(&zim, &titles, &["A".to_owned()]);
+        assert_eq!(resolution.entry_indexes, vec![0, 2]);
+        assert_eq!(
+            find_by_title(&zim, "A", "banana").map(|(entry_index, _)| entry_index),
+            Some(1)
+        );
+        assert_eq!(resolution.missing_titles, vec!["missing"]);
+    }
+}
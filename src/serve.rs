@@ -0,0 +1,622 @@
+use std::io;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::json;
+use tracing::{debug, info, warn};
+
+use crate::config::ServeConfig;
+use crate::export::ExportPage;
+use crate::extractor::sha256_hex;
+use crate::http::{self, RequestHead};
+use crate::query::{Dictionary, LookupBy, LookupQuery};
+use crate::search::SearchQuery;
+use crate::suggest::SuggestQuery;
+
+/// Serves a [`Dictionary`] as a read-only JSON API on `serve.listen`, one request per
+/// connection, on `serve.threads` threads until shut down or dropped.
+///
+/// - `GET /entries/{title}`: pages with this exact title
+/// - `GET /lookup?q=`: pages with this normalized alias (hot-first on Postgres)
+/// - `GET /complete?q=`: aliases starting with the prefix
+/// - `GET /suggest?q=`: prefix completions, then typo-tolerant matches, `per_language` each
+/// - `GET /search?q=`: full-text search over `page_fts`
+/// - `GET /relations/{title}`: relations of the headword and relations targeting it
+///
+/// Every list takes `offset`, `limit` and `lang`; entry and lookup also take `edition`, and
+/// relations `type`. Page responses carry an `ETag` derived from the pages' `content_sha256`.
+pub struct ApiServer {
+    stop: Arc<AtomicBool>,
+    threads: Vec<JoinHandle<()>>,
+    local_addr: SocketAddr,
+}
+
+impl ApiServer {
+    pub fn start(config: &ServeConfig, dictionary: Arc<Dictionary>) -> Result<Self> {
+        let listener = TcpListener::bind(&config.listen)
+            .with_context(|| format!("failed to bind API endpoint {}", config.listen))?;
+        listener.set_nonblocking(true)?;
+        let local_addr = listener.local_addr()?;
+        info!(
+            %local_addr,
+            backend = dictionary.backend_name(),
+            threads = config.threads.max(1),
+            "serving dictionary API"
+        );
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut threads = Vec::new();
+        for _ in 0..config.threads.max(1) {
+            let listener = listener.try_clone()?;
+            let stop = Arc::clone(&stop);
+            let dictionary = Arc::clone(&dictionary);
+            let config = config.clone();
+            threads.push(thread::spawn(move || {
+                http::serve(&listener, &stop, "API", |stream| {
+                    answer_request(stream, &dictionary, &config)
+                });
+            }));
+        }
+
+        Ok(Self {
+            stop,
+            threads,
+            local_addr,
+        })
+    }
+
+    /// Bound address; useful with port 0.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Blocks until the request threads exit, i.e. for the life of the process.
+    pub fn wait(mut self) {
+        for handle in self.threads.drain(..) {
+            if handle.join().is_err() {
+                warn!("API request thread panicked");
+            }
+        }
+    }
+
+    pub fn shutdown(mut self) {
+        self.stop_threads();
+    }
+
+    fn stop_threads(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        for handle in self.threads.drain(..) {
+            if handle.join().is_err() {
+                warn!("API request thread panicked");
+            }
+        }
+    }
+}
+
+impl Drop for ApiServer {
+    fn drop(&mut self) {
+        self.stop_threads();
+    }
+}
+
+struct Request {
+    path: Vec<String>,
+    params: Vec<(String, String)>,
+    if_none_match: Option<String>,
+}
+
+impl Request {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+            .filter(|value| !value.trim().is_empty())
+    }
+
+    fn required(&self, name: &str) -> Result<&str, ApiError> {
+        self.param(name)
+            .ok_or_else(|| ApiError::bad_request(format!("missing query parameter `{name}`")))
+    }
+
+    fn number(&self, name: &str) -> Result<Option<usize>, ApiError> {
+        self.param(name)
+            .map(|value| {
+                value.trim().parse().map_err(|_| {
+                    ApiError::bad_request(format!("`{name}` must be a non-negative integer"))
+                })
+            })
+            .transpose()
+    }
+
+    fn paging(&self, config: &ServeConfig) -> Result<Paging, ApiError> {
+        let limit = self.number("limit")?.unwrap_or(config.default_limit);
+        if limit == 0 || limit > config.max_limit {
+            return Err(ApiError::bad_request(format!(
+                "`limit` must be between 1 and {}",
+                config.max_limit
+            )));
+        }
+        Ok(Paging {
+            offset: self.number("offset")?.unwrap_or(0),
+            limit,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Paging {
+    offset: usize,
+    limit: usize,
+}
+
+impl Paging {
+    /// One more than `limit`, to tell whether there is a next page.
+    fn fetch(self) -> usize {
+        self.limit + 1
+    }
+}
+
+#[derive(Serialize)]
+struct Envelope<T: Serialize> {
+    items: Vec<T>,
+    offset: usize,
+    limit: usize,
+    next_offset: Option<usize>,
+}
+
+impl<T: Serialize> Envelope<T> {
+    fn new(mut items: Vec<T>, paging: Paging) -> Self {
+        let next_offset = (items.len() > paging.limit).then_some(paging.offset + paging.limit);
+        items.truncate(paging.limit);
+        Self {
+            items,
+            offset: paging.offset,
+            limit: paging.limit,
+            next_offset,
+        }
+    }
+}
+
+struct Response {
+    status: &'static str,
+    body: String,
+    etag: Option<String>,
+    headers: Vec<(&'static str, String)>,
+}
+
+impl Response {
+    fn json(body: &impl Serialize) -> Result<Self, ApiError> {
+        Ok(Self {
+            status: "200 OK",
+            body: serde_json::to_string(body).map_err(|error| ApiError::internal(error.into()))?,
+            etag: None,
+            headers: Vec::new(),
+        })
+    }
+}
+
+struct ApiError {
+    status: &'static str,
+    message: String,
+}
+
+impl ApiError {
+    fn bad_request(message: impl Into<String>) -> Self {
+        Self {
+            status: "400 Bad Request",
+            message: message.into(),
+        }
+    }
+
+    fn not_found(message: impl Into<String>) -> Self {
+        Self {
+            status: "404 Not Found",
+            message: message.into(),
+        }
+    }
+
+    fn internal(error: anyhow::Error) -> Self {
+        warn!(error = format!("{error:#}"), "API request failed");
+        Self {
+            status: "500 Internal Server Error",
+            message: "internal error".to_owned(),
+        }
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(error: anyhow::Error) -> Self {
+        Self::internal(error)
+    }
+}
+
+fn answer_request(
+    mut stream: TcpStream,
+    dictionary: &Dictionary,
+    config: &ServeConfig,
+) -> io::Result<()> {
+    let started = Instant::now();
+    let head = RequestHead::read(&mut stream, Duration::from_secs(5))?;
+    let (method, target) = (head.method.as_str(), head.target.as_str());
+
+    let result = match method {
+        "GET" => parse_request(target, &head).and_then(|request| {
+            let mut response = route(dictionary, config, &request)?;
+            if let (Some(etag), Some(condition)) = (&response.etag, &request.if_none_match)
+                && http::if_none_match(condition, etag)
+            {
+                response.status = "304 Not Modified";
+                response.body.clear();
+            }
+            Ok(response)
+        }),
+        _ => Err(ApiError {
+            status: "405 Method Not Allowed",
+            message: "only GET is supported".to_owned(),
+        }),
+    };
+    let response = result.unwrap_or_else(|error| Response {
+        status: error.status,
+        body: json!({ "error": error.message }).to_string(),
+        etag: None,
+        headers: Vec::new(),
+    });
+
+    let mut headers = response.headers.clone();
+    if let Some(etag) = &response.etag {
+        headers.push(("ETag", etag.clone()));
+    }
+    http::write_response(
+        &mut stream,
+        response.status,
+        "application/json",
+        &headers,
+        &response.body,
+    )?;
+
+    debug!(
+        method,
+        target,
+        status = response.status,
+        elapsed_ms = started.elapsed().as_secs_f64() * 1000.0,
+        "API request"
+    );
+    Ok(())
+}
+
+fn parse_request(target: &str, head: &RequestHead) -> Result<Request, ApiError> {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let path = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| percent_decode(segment, false))
+        .collect::<Result<Vec<_>, _>>()?;
+    let params = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            Ok((percent_decode(key, true)?, percent_decode(value, true)?))
+        })
+        .collect::<Result<Vec<_>, ApiError>>()?;
+    let if_none_match = head.header("If-None-Match").map(str::to_owned);
+
+    Ok(Request {
+        path,
+        params,
+        if_none_match,
+    })
+}
+
+fn percent_decode(value: &str, plus_as_space: bool) -> Result<String, ApiError> {
+    let invalid = || ApiError::bad_request(format!("invalid percent-encoding in {value:?}"));
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'%' => {
+                let hex = value.get(index + 1..index + 3).ok_or_else(invalid)?;
+                decoded.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+                index += 3;
+            }
+            b'+' if plus_as_space => {
+                decoded.push(b' ');
+                index += 1;
+            }
+            byte => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| invalid())
+}
+
+fn route(
+    dictionary: &Dictionary,
+    config: &ServeConfig,
+    request: &Request,
+) -> Result<Response, ApiError> {
+    let language = request.param("lang");
+    match request
+        .path
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["health"] => Response::json(&json!({
+            "status": "ok",
+            "backend": dictionary.backend_name(),
+        })),
+        ["entries", title] => {
+            let paging = request.paging(config)?;
+            let query = LookupQuery {
+                by: LookupBy::Headword,
+                ..page_query(request, title, paging)
+            };
+            let pages = dictionary.lookup(&query)?.pages;
+            if pages.is_empty() && paging.offset == 0 {
+                return Err(ApiError::not_found(format!("no entry titled {title:?}")));
+            }
+            pages_response(pages, paging)
+        }
+        ["lookup"] => {
+            let paging = request.paging(config)?;
+            let query = page_query(request, request.required("q")?, paging);
+            let result = dictionary.lookup(&query)?;
+            let mut response = pages_response(result.pages, paging)?;
+            response
+                .headers
+                .push(("X-Lookup-Source", result.source.as_str().to_owned()));
+            Ok(response)
+        }
+        ["complete"] => {
+            let paging = request.paging(config)?;
+            let completions = dictionary.complete(
+                request.required("q")?,
+                language,
+                paging.offset,
+                paging.fetch(),
+            )?;
+            Response::json(&Envelope::new(completions, paging))
+        }
+        ["suggest"] => {
+            let mut query = SuggestQuery::new(request.required("q")?);
+            query.language = language.map(str::to_owned);
+            query.per_language = request
+                .number("per_language")?
+                .unwrap_or(query.per_language);
+            query.max_edits = request.number("max_edits")?.unwrap_or(query.max_edits);
+            if query.per_language == 0 || query.per_language > config.max_limit {
+                return Err(ApiError::bad_request(format!(
+                    "`per_language` must be between 1 and {}",
+                    config.max_limit
+                )));
+            }
+            Response::json(&json!({ "items": dictionary.suggest(&query)? }))
+        }
+        ["search"] => {
+            let paging = request.paging(config)?;
+            let hits = dictionary.search(&SearchQuery {
+                text: request.required("q")?.to_owned(),
+                language: language.map(str::to_owned),
+                offset: paging.offset,
+                limit: paging.fetch(),
+            })?;
+            Response::json(&Envelope::new(hits, paging))
+        }
+        ["relations", title] => {
+            let paging = request.paging(config)?;
+            let neighbours = dictionary.relation_neighbours(
+                title,
+                language,
+                request.param("type"),
+                paging.offset,
+                paging.fetch(),
+            )?;
+            Response::json(&Envelope::new(neighbours, paging))
+        }
+        _ => Err(ApiError::not_found("not found")),
+    }
+}
+
+fn page_query(request: &Request, term: &str, paging: Paging) -> LookupQuery {
+    LookupQuery {
+        language: request.param("lang").map(str::to_owned),
+        edition: request.param("edition").map(str::to_owned),
+        offset: paging.offset,
+        limit: paging.fetch(),
+        ..LookupQuery::new(term)
+    }
+}
+
+fn pages_response(pages: Vec<ExportPage>, paging: Paging) -> Result<Response, ApiError> {
+    let envelope = Envelope::new(pages, paging);
+    let mut response = Response::json(&envelope)?;
+    response.etag = pages_etag(&envelope);
+    Ok(response)
+}
+
+/// A strong validator over the ids and content hashes of the returned pages and the page
+/// boundary; `None` when a page has no content hash.
+fn pages_etag(envelope: &Envelope<ExportPage>) -> Option<String> {
+    let mut material = String::new();
+    for page in &envelope.items {
+        material.push_str(&format!("{}:{}\n", page.id, page.content_sha256.as_ref()?));
+    }
+    material.push_str(&format!("next:{:?}", envelope.next_offset));
+    Some(format!("\"{}\"", sha256_hex(&material)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::sync::Arc;
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::config::{Config, StorageBackend};
+    use crate::db::{Database, PageLineage};
+    use crate::extractor::{ExtractedPage, ExtractedRelation};
+    use crate::normalization::canonicalize_lemma;
+    use crate::test_zim;
+
+    fn page(url: &str, definition: &str, relations: &[(&str, &str)]) -> ExtractedPage {
+        ExtractedPage {
+            content_sha256: Some(format!("sha-{url}")),
+            raw_html: None,
+            plain_text: Some(definition.to_owned()),
+            relations: relations
+                .iter()
+                .enumerate()
+                .map(|(order, (relation_type, target))| ExtractedRelation {
+                    language: "English".to_owned(),
+                    relation_type: (*relation_type).to_owned(),
+                    order_in_type: order as i64,
+                    source_text: (*target).to_owned(),
+                    target_term: (*target).to_owned(),
+                    normalized_target: canonicalize_lemma(target),
+                    confidence: 0.8,
+                })
+                .collect(),
+            ..test_zim::page(url, &[("English", definition)], &[("English", url)])
+        }
+    }
+
+    fn get(addr: SocketAddr, target: &str, if_none_match: Option<&str>) -> (String, String) {
+        let mut stream = TcpStream::connect(addr).expect("connect");
+        let condition = if_none_match
+            .map(|etag| format!("If-None-Match: {etag}\r\n"))
+            .unwrap_or_default();
+        write!(
+            stream,
+            "GET {target} HTTP/1.1\r\nHost: localhost\r\n{condition}\r\n"
+        )
+        .expect("send");
+        let mut response = String::new();
+        stream.read_to_string(&mut response).expect("read");
+        let (head, body) = response.split_once("\r\n\r\n").expect("response head");
+        (head.to_owned(), body.to_owned())
+    }
+
+    fn items(body: &str) -> Vec<serde_json::Value> {
+        let value: serde_json::Value = serde_json::from_str(body).expect("json body");
+        value["items"].as_array().expect("items").clone()
+    }
+
+    #[test]
+    fn serves_entries_lookups_and_lists_from_sqlite() {
+        let workdir = tempdir().expect("tempdir");
+        let mut config = Config {
+            backend: StorageBackend::Sqlite,
+            ..Config::default()
+        };
+        config.input.sqlite_path = workdir.path().join("serve.sqlite");
+        config.serve.listen = "127.0.0.1:0".to_owned();
+        config.serve.threads = 2;
+
+        let db = Database::open(&config).expect("open db");
+        db.init_schema().expect("init schema");
+        for page in [
+            page(
+                "cat",
+                "a small domesticated feline",
+                &[("synonyms", "feline")],
+            ),
+            page("catalog", "a list of items", &[]),
+            page("kitten", "a young cat", &[("hypernyms", "cat")]),
+        ] {
+            db.upsert_page(&page, &PageLineage::default())
+                .expect("upsert page");
+        }
+
+        let dictionary = Arc::new(Dictionary::open(&config).expect("open dictionary"));
+        let server = ApiServer::start(&config.serve, dictionary).expect("start server");
+        let addr = server.local_addr();
+
+        let (head, body) = get(addr, "/entries/cat", None);
+        assert!(head.starts_with("HTTP/1.1 200 OK\r\n"), "{head}");
+        let etag = head
+            .lines()
+            .find_map(|line| line.strip_prefix("ETag: "))
+            .expect("etag")
+            .to_owned();
+        assert_eq!(
+            items(&body)[0]["definitions"][0]["text"],
+            "a small domesticated feline"
+        );
+        let (head, body) = get(addr, "/entries/cat", Some(&etag));
+        assert!(head.starts_with("HTTP/1.1 304 Not Modified\r\n"), "{head}");
+        assert!(body.is_empty());
+        for condition in [format!("\"stale\", W/{etag}"), "*".to_owned()] {
+            let (head, _) = get(addr, "/entries/cat", Some(&condition));
+            assert!(head.starts_with("HTTP/1.1 304"), "{condition}: {head}");
+        }
+        let (head, _) = get(addr, "/entries/cat", Some("\"stale\""));
+        assert!(head.starts_with("HTTP/1.1 200 OK\r\n"), "{head}");
+        assert!(
+            get(addr, "/entries/dog", None)
+                .0
+                .starts_with("HTTP/1.1 404")
+        );
+
+        let (head, body) = get(addr, "/lookup?q=Kitten&lang=en", None);
+        assert!(head.contains("X-Lookup-Source: canonical"), "{head}");
+        assert_eq!(items(&body)[0]["title"], "kitten");
+
+        let (_, body) = get(addr, "/complete?q=cat&limit=1", None);
+        let value: serde_json::Value = serde_json::from_str(&body).expect("json body");
+        assert_eq!(value["items"][0]["normalized_alias"], "cat");
+        assert_eq!(value["next_offset"], 1);
+        let (_, body) = get(addr, "/complete?q=cat&offset=1", None);
+        assert_eq!(items(&body)[0]["normalized_alias"], "catalog");
+
+        let (_, body) = get(addr, "/suggest?q=catt&lang=en", None);
+        let suggestions = items(&body);
+        assert_eq!(suggestions[0]["kind"], "fuzzy");
+        assert_eq!(suggestions[0]["normalized_alias"], "cat");
+        assert_eq!(suggestions[0]["distance"], 1);
+        assert!(
+            get(addr, "/suggest?q=cat&per_language=0", None)
+                .0
+                .starts_with("HTTP/1.1 400")
+        );
+
+        let (_, body) = get(addr, "/search?q=domesticated+feline", None);
+        assert_eq!(items(&body)[0]["title"], "cat");
+
+        let (_, body) = get(addr, "/relations/cat", None);
+        let neighbours = items(&body);
+        assert_eq!(neighbours.len(), 2);
+        assert_eq!(neighbours[0]["direction"], "outgoing");
+        assert_eq!(neighbours[0]["term"], "feline");
+        assert_eq!(neighbours[1]["direction"], "incoming");
+        assert_eq!(neighbours[1]["term"], "kitten");
+        let (_, body) = get(addr, "/relations/cat?type=hypernyms", None);
+        assert_eq!(items(&body).len(), 1);
+
+        assert!(get(addr, "/lookup", None).0.starts_with("HTTP/1.1 400"));
+        assert!(
+            get(addr, "/search?q=cat&limit=1000", None)
+                .0
+                .starts_with("HTTP/1.1 400")
+        );
+        assert!(
+            get(addr, "/entries/%ZZ", None)
+                .0
+                .starts_with("HTTP/1.1 400")
+        );
+        assert!(get(addr, "/nope", None).0.starts_with("HTTP/1.1 404"));
+
+        server.shutdown();
+    }
+}
@@ -0,0 +1,271 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use zim::Zim;
+
+use crate::config::Config;
+use crate::db::Database;
+
+/// One slice of an archive's entry-index space, written `index/count` with a 1-based index.
+/// Shards split the index range into contiguous, disjoint windows of near-equal size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct ShardSpec {
+    pub index: u32,
+    pub count: u32,
+}
+
+impl ShardSpec {
+    pub fn new(index: u32, count: u32) -> Result<Self> {
+        if count == 0 {
+            anyhow::bail!("shard count must be at least 1");
+        }
+        if index == 0 || index > count {
+            anyhow::bail!("shard index {index} is outside 1..={count}");
+        }
+        Ok(Self { index, count })
+    }
+
+    /// Every shard of a `count`-way split, in index order.
+    pub fn all(count: u32) -> impl Iterator<Item = Self> {
+        (1..=count).map(move |index| Self { index, count })
+    }
+
+    /// Half-open entry-index window `[start, end)` this shard owns. The first
+    /// `total_entries % count` shards take one extra entry.
+    pub fn index_range(self, total_entries: u32) -> (u32, u32) {
+        let count = u64::from(self.count);
+        let position = u64::from(self.index - 1);
+        let total = u64::from(total_entries);
+        let base = total / count;
+        let remainder = total % count;
+
+        let start = position * base + position.min(remainder);
+        let len = base + u64::from(position < remainder);
+        (start as u32, (start + len) as u32)
+    }
+
+    pub fn checkpoint_name(self, base: &str) -> String {
+        format!("{base}:shard-{}-of-{}", self.index, self.count)
+    }
+
+    /// Per-shard copy of a file path: `status.json` becomes `status.shard-3-of-8.json`.
+    pub fn file_path(self, path: &Path) -> PathBuf {
+        let suffix = format!("shard-{}-of-{}", self.index, self.count);
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let name = match path.extension() {
+            Some(extension) => format!("{stem}.{suffix}.{}", extension.to_string_lossy()),
+            None => format!("{stem}.{suffix}"),
+        };
+        path.with_file_name(name)
+    }
+
+    /// Per-shard `HOST:PORT` listen address: the port plus `index - 1`, so shards on one host
+    /// bind consecutive ports. Port 0 (any free port) is kept.
+    pub fn listen_address(self, listen: &str) -> Result<String> {
+        let (host, port) = listen
+            .rsplit_once(':')
+            .with_context(|| format!("listen address '{listen}' must look like HOST:PORT"))?;
+        let port: u16 = port
+            .parse()
+            .with_context(|| format!("invalid port in listen address '{listen}'"))?;
+        if port == 0 {
+            return Ok(listen.to_owned());
+        }
+        let port = u16::try_from(u32::from(port) + self.index - 1)
+            .ok()
+            .with_context(|| format!("listen port {port} has no room for shard {self}"))?;
+        Ok(format!("{host}:{port}"))
+    }
+}
+
+/// Gives a `selection.shard` process its own status file, metrics port and metrics textfile,
+/// so shards sharing one config can run side by side.
+pub fn separate_shard_outputs(config: &mut Config) -> Result<()> {
+    let Some(shard) = config.selection.shard else {
+        return Ok(());
+    };
+    if let Some(path) = &mut config.logging.status_file {
+        *path = shard.file_path(path);
+    }
+    if let Some(path) = &mut config.metrics.textfile_path {
+        *path = shard.file_path(path);
+    }
+    if let Some(listen) = &mut config.metrics.listen {
+        *listen = shard.listen_address(listen)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for ShardSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
+impl FromStr for ShardSpec {
+    type Err = anyhow::Error;
+
+    fn from_str(raw: &str) -> Result<Self> {
+        let (index, count) = raw
+            .split_once('/')
+            .with_context(|| format!("shard spec '{raw}' must look like INDEX/COUNT"))?;
+        let index = index
+            .trim()
+            .parse()
+            .with_context(|| format!("invalid shard index in '{raw}'"))?;
+        let count = count
+            .trim()
+            .parse()
+            .with_context(|| format!("invalid shard count in '{raw}'"))?;
+        Self::new(index, count)
+    }
+}
+
+impl TryFrom<String> for ShardSpec {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: String) -> Result<Self> {
+        raw.parse()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ShardStatus {
+    pub edition: String,
+    pub shard: ShardSpec,
+    pub checkpoint_name: String,
+    pub start_index: u32,
+    pub end_index: u32,
+    pub processed_entries: u32,
+    pub ingested_pages: u64,
+    pub run_id: Option<i64>,
+    pub started: bool,
+}
+
+impl ShardStatus {
+    pub fn total_entries(&self) -> u32 {
+        self.end_index - self.start_index
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.processed_entries >= self.total_entries()
+    }
+}
+
+/// Progress of every shard in a `count`-way split, read from each shard's checkpoint.
+pub fn shard_statuses(config: &Config, db: &Database, count: u32) -> Result<Vec<ShardStatus>> {
+    let zim = Zim::new(&config.input.zim_path)
+        .with_context(|| format!("failed to open {}", config.input.zim_path.display()))?;
+    collect_shard_statuses(config, db, count, zim.header.article_count)
+}
+
+pub(crate) fn collect_shard_statuses(
+    config: &Config,
+    db: &Database,
+    count: u32,
+    total_entries: u32,
+) -> Result<Vec<ShardStatus>> {
+    let mut statuses = Vec::with_capacity(count as usize);
+    for shard in ShardSpec::all(count) {
+        let (start_index, end_index) = shard.index_range(total_entries);
+        let checkpoint_name = shard.checkpoint_name(&config.checkpoint.name);
+        let checkpoint = db.load_checkpoint(&checkpoint_name)?;
+
+        let processed_entries = checkpoint.as_ref().map_or(0, |checkpoint| {
+            checkpoint
+                .last_processed_index
+                .saturating_add(1)
+                .clamp(start_index, end_index)
+                - start_index
+        });
+
+        statuses.push(ShardStatus {
+            edition: config.input.edition.clone(),
+            shard,
+            checkpoint_name,
+            start_index,
+            end_index,
+            processed_entries,
+            ingested_pages: checkpoint
+                .as_ref()
+                .map_or(0, |checkpoint| checkpoint.ingested_pages),
+            run_id: checkpoint.as_ref().and_then(|checkpoint| checkpoint.run_id),
+            started: checkpoint.is_some(),
+        });
+    }
+
+    Ok(statuses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shard_ranges_cover_the_index_space_without_overlap() {
+        let ranges = ShardSpec::all(3)
+            .map(|shard| shard.index_range(10))
+            .collect::<Vec<_>>();
+        assert_eq!(ranges, vec![(0, 4), (4, 7), (7, 10)]);
+
+        let tiny = ShardSpec::all(4)
+            .map(|shard| shard.index_range(2))
+            .collect::<Vec<_>>();
+        assert_eq!(tiny, vec![(0, 1), (1, 2), (2, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn parses_shard_specs() {
+        let shard: ShardSpec = "3/8".parse().expect("valid spec");
+        assert_eq!(shard, ShardSpec { index: 3, count: 8 });
+        assert_eq!(shard.to_string(), "3/8");
+        assert_eq!(
+            shard.checkpoint_name("wiktionary-en"),
+            "wiktionary-en:shard-3-of-8"
+        );
+
+        assert!("0/8".parse::<ShardSpec>().is_err());
+        assert!("9/8".parse::<ShardSpec>().is_err());
+        assert!("3".parse::<ShardSpec>().is_err());
+    }
+
+    #[test]
+    fn shards_get_their_own_status_files_and_metrics_ports() {
+        let mut config = Config::default();
+        config.logging.status_file = Some(PathBuf::from("run/status.json"));
+        config.metrics.textfile_path = Some(PathBuf::from("zimrs.prom"));
+        config.metrics.listen = Some("127.0.0.1:9464".to_owned());
+        let unsharded = config.clone();
+        separate_shard_outputs(&mut config).expect("no shard");
+        assert_eq!(config.logging.status_file, unsharded.logging.status_file);
+        assert_eq!(config.metrics.listen, unsharded.metrics.listen);
+
+        config.selection.shard = Some(ShardSpec::new(3, 8).expect("shard"));
+        separate_shard_outputs(&mut config).expect("separate outputs");
+        assert_eq!(
+            config.logging.status_file,
+            Some(PathBuf::from("run/status.shard-3-of-8.json"))
+        );
+        assert_eq!(
+            config.metrics.textfile_path,
+            Some(PathBuf::from("zimrs.shard-3-of-8.prom"))
+        );
+        assert_eq!(config.metrics.listen.as_deref(), Some("127.0.0.1:9466"));
+
+        let shard = ShardSpec::new(2, 2).expect("shard");
+        assert_eq!(
+            shard.file_path(Path::new("status")),
+            PathBuf::from("status.shard-2-of-2")
+        );
+        assert_eq!(
+            shard.listen_address("[::1]:0").expect("any port"),
+            "[::1]:0"
+        );
+        assert!(shard.listen_address("0.0.0.0:65535").is_err());
+        assert!(shard.listen_address("localhost").is_err());
+    }
+}
@@ -0,0 +1,381 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::db::{Database, PageLineage};
+use crate::extractor::ExtractedPage;
+
+/// Destination for extracted pages. Conversion bookkeeping (runs, checkpoints, quarantine,
+/// redirect resolution) lives in the database, not in sinks, so a sink only has to store pages.
+pub trait PageSink {
+    fn name(&self) -> &str;
+
+    fn write_page(&mut self, page: &ExtractedPage, lineage: &PageLineage) -> Result<()>;
+
+    /// Makes everything written so far durable. Called before each checkpoint and at the end of
+    /// every archive.
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Where `convert` sends pages, written `db`, `null` or `jsonl:<path>`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum SinkSpec {
+    Database,
+    Jsonl(PathBuf),
+    Null,
+}
+
+impl fmt::Display for SinkSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Database => f.write_str("db"),
+            Self::Jsonl(path) => write!(f, "jsonl:{}", path.display()),
+            Self::Null => f.write_str("null"),
+        }
+    }
+}
+
+impl FromStr for SinkSpec {
+    type Err = anyhow::Error;
+
+    fn from_str(raw: &str) -> Result<Self> {
+        let (kind, target) = match raw.split_once(':') {
+            Some((kind, target)) => (kind, Some(target)),
+            None => (raw, None),
+        };
+
+        match (kind.trim(), target) {
+            ("db" | "database", None) => Ok(Self::Database),
+            ("null", None) => Ok(Self::Null),
+            ("jsonl", Some(path)) if !path.trim().is_empty() => {
+                Ok(Self::Jsonl(PathBuf::from(path.trim())))
+            }
+            ("jsonl", _) => anyhow::bail!("jsonl sink needs a path, e.g. jsonl:out/pages.jsonl"),
+            _ => anyhow::bail!("unknown sink '{raw}' (expected db, null or jsonl:<path>)"),
+        }
+    }
+}
+
+impl TryFrom<String> for SinkSpec {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: String) -> Result<Self> {
+        raw.parse()
+    }
+}
+
+/// Builds the sink for `specs`, teeing when there is more than one. `db` must be set when any
+/// spec is [`SinkSpec::Database`]. The db sink is written first, so a page that reaches any
+/// sink is in the dictionary and a [`PartialWrite`] never needs a retry.
+pub fn open_sink<'a>(
+    specs: &[SinkSpec],
+    db: Option<&'a Database>,
+) -> Result<Box<dyn PageSink + 'a>> {
+    let mut specs = specs.iter().collect::<Vec<_>>();
+    specs.sort_by_key(|spec| **spec != SinkSpec::Database);
+
+    let mut sinks = Vec::with_capacity(specs.len());
+    for spec in specs {
+        let sink: Box<dyn PageSink + 'a> = match spec {
+            SinkSpec::Database => Box::new(DatabaseSink::new(
+                db.context("db sink requested without an open database")?,
+            )),
+            SinkSpec::Jsonl(path) => Box::new(JsonlSink::create(path)?),
+            SinkSpec::Null => Box::new(NullSink::default()),
+        };
+        sinks.push(sink);
+    }
+
+    match sinks.len() {
+        0 => anyhow::bail!("no output sink configured"),
+        1 => Ok(sinks.remove(0)),
+        _ => Ok(Box::new(TeeSink::new(sinks))),
+    }
+}
+
+/// Upserts pages into the dictionary schema, as `convert` always has.
+pub struct DatabaseSink<'a> {
+    db: &'a Database,
+}
+
+impl<'a> DatabaseSink<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+}
+
+impl PageSink for DatabaseSink<'_> {
+    fn name(&self) -> &str {
+        self.db.backend_name()
+    }
+
+    fn write_page(&mut self, page: &ExtractedPage, lineage: &PageLineage) -> Result<()> {
+        self.db.upsert_page(page, lineage)
+    }
+}
+
+/// Writes one JSON object per page. The file is truncated when the sink is created, so a
+/// convert that writes one cannot resume from a checkpoint or skip unchanged pages.
+pub struct JsonlSink {
+    path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+impl JsonlSink {
+    pub fn create(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+
+        let file =
+            File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer: BufWriter::new(file),
+        })
+    }
+}
+
+impl PageSink for JsonlSink {
+    fn name(&self) -> &str {
+        "jsonl"
+    }
+
+    fn write_page(&mut self, page: &ExtractedPage, lineage: &PageLineage) -> Result<()> {
+        serde_json::to_writer(&mut self.writer, &JsonlPage::new(page, lineage))?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.writer
+            .flush()
+            .with_context(|| format!("failed to flush {}", self.path.display()))
+    }
+}
+
+/// Discards pages; useful for timing extraction without storage costs.
+#[derive(Debug, Default)]
+pub struct NullSink {
+    pub pages: u64,
+}
+
+impl PageSink for NullSink {
+    fn name(&self) -> &str {
+        "null"
+    }
+
+    fn write_page(&mut self, _page: &ExtractedPage, _lineage: &PageLineage) -> Result<()> {
+        self.pages += 1;
+        Ok(())
+    }
+}
+
+/// Returned by [`TeeSink`] when a page reached some sinks but not all of them. The page is
+/// already stored, so callers should report it rather than treat the page as failed.
+#[derive(Debug)]
+pub struct PartialWrite {
+    pub stored_in: Vec<String>,
+    pub failed: Vec<(String, anyhow::Error)>,
+}
+
+impl fmt::Display for PartialWrite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let failed = self
+            .failed
+            .iter()
+            .map(|(name, error)| format!("{name} sink failed: {error:#}"))
+            .collect::<Vec<_>>();
+        write!(
+            f,
+            "page stored in {} but {}",
+            self.stored_in.join(", "),
+            failed.join("; ")
+        )
+    }
+}
+
+impl std::error::Error for PartialWrite {}
+
+/// Forwards every page to each inner sink in order. An error from the first sink is returned
+/// as is; once a sink has stored the page, the remaining sinks are still written and their
+/// errors come back together as a [`PartialWrite`].
+pub struct TeeSink<'a> {
+    sinks: Vec<Box<dyn PageSink + 'a>>,
+}
+
+impl<'a> TeeSink<'a> {
+    pub fn new(sinks: Vec<Box<dyn PageSink + 'a>>) -> Self {
+        Self { sinks }
+    }
+}
+
+impl PageSink for TeeSink<'_> {
+    fn name(&self) -> &str {
+        "tee"
+    }
+
+    fn write_page(&mut self, page: &ExtractedPage, lineage: &PageLineage) -> Result<()> {
+        let mut stored_in = Vec::new();
+        let mut failed = Vec::new();
+        for sink in &mut self.sinks {
+            match sink.write_page(page, lineage) {
+                Ok(()) => stored_in.push(sink.name().to_owned()),
+                Err(error) if stored_in.is_empty() => {
+                    return Err(error.context(format!("{} sink failed", sink.name())));
+                }
+                Err(error) => failed.push((sink.name().to_owned(), error)),
+            }
+        }
+
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(PartialWrite { stored_in, failed }.into())
+        }
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        for sink in &mut self.sinks {
+            sink.flush()?;
+        }
+        Ok(())
+    }
+}
+
+// Field names follow `export-json` so both outputs can be read by the same consumer.
+#[derive(Serialize)]
+struct JsonlPage<'a> {
+    edition: &'a str,
+    url: &'a str,
+    title: &'a str,
+    namespace: &'a str,
+    mime_type: &'a str,
+    redirect_url: Option<&'a str>,
+    content_sha256: Option<&'a str>,
+    extraction_confidence: f64,
+    plain_text: Option<&'a str>,
+    raw_html: Option<&'a str>,
+    zim_entry_index: Option<u32>,
+    run_id: Option<i64>,
+    definitions: Vec<JsonlDefinition<'a>>,
+    relations: Vec<JsonlRelation<'a>>,
+    aliases: Vec<JsonlAlias<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonlDefinition<'a> {
+    language: &'a str,
+    order: i64,
+    text: &'a str,
+    normalized_text: &'a str,
+    confidence: f64,
+}
+
+#[derive(Serialize)]
+struct JsonlRelation<'a> {
+    language: &'a str,
+    relation_type: &'a str,
+    order: i64,
+    source_text: &'a str,
+    target_term: &'a str,
+    normalized_target: &'a str,
+    confidence: f64,
+}
+
+#[derive(Serialize)]
+struct JsonlAlias<'a> {
+    language: Option<&'a str>,
+    alias: &'a str,
+    normalized_alias: &'a str,
+    source: &'a str,
+}
+
+impl<'a> JsonlPage<'a> {
+    fn new(page: &'a ExtractedPage, lineage: &PageLineage) -> Self {
+        Self {
+            edition: &page.edition,
+            url: &page.url,
+            title: &page.title,
+            namespace: &page.namespace,
+            mime_type: &page.mime_type,
+            redirect_url: page.redirect_url.as_deref(),
+            content_sha256: page.content_sha256.as_deref(),
+            extraction_confidence: page.extraction_confidence,
+            plain_text: page.plain_text.as_deref(),
+            raw_html: page.raw_html.as_deref(),
+            zim_entry_index: lineage.entry_index,
+            run_id: lineage.run_id,
+            definitions: page
+                .definitions
+                .iter()
+                .map(|definition| JsonlDefinition {
+                    language: &definition.language,
+                    order: definition.order_in_language,
+                    text: &definition.text,
+                    normalized_text: &definition.normalized_text,
+                    confidence: definition.confidence,
+                })
+                .collect(),
+            relations: page
+                .relations
+                .iter()
+                .map(|relation| JsonlRelation {
+                    language: &relation.language,
+                    relation_type: &relation.relation_type,
+                    order: relation.order_in_type,
+                    source_text: &relation.source_text,
+                    target_term: &relation.target_term,
+                    normalized_target: &relation.normalized_target,
+                    confidence: relation.confidence,
+                })
+                .collect(),
+            aliases: page
+                .aliases
+                .iter()
+                .map(|alias| JsonlAlias {
+                    language: alias.language.as_deref(),
+                    alias: &alias.alias,
+                    normalized_alias: &alias.normalized_alias,
+                    source: &alias.source,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sink_specs() {
+        assert_eq!(
+            "db".parse::<SinkSpec>().expect("valid sink"),
+            SinkSpec::Database
+        );
+        assert_eq!(
+            "null".parse::<SinkSpec>().expect("valid sink"),
+            SinkSpec::Null
+        );
+        assert_eq!(
+            "jsonl:out/pages.jsonl"
+                .parse::<SinkSpec>()
+                .expect("valid sink"),
+            SinkSpec::Jsonl(PathBuf::from("out/pages.jsonl"))
+        );
+        assert!("jsonl".parse::<SinkSpec>().is_err());
+        assert!("csv:out.csv".parse::<SinkSpec>().is_err());
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+/// Fuzzy lookups rank this many trigram-nearest aliases by edit distance.
+pub(crate) const FUZZY_CANDIDATES: i64 = 200;
+
+/// Alias suggestions for a search box: prefix completions or typo-tolerant matches of `term`,
+/// at most `per_language` per language.
+#[derive(Debug, Clone)]
+pub struct SuggestQuery {
+    pub term: String,
+    /// Language name or ISO code; only aliases in that language are suggested.
+    pub language: Option<String>,
+    pub per_language: usize,
+    /// Fuzzy matches only: the largest edit distance from the normalized term.
+    pub max_edits: usize,
+}
+
+impl SuggestQuery {
+    pub fn new(term: impl Into<String>) -> Self {
+        Self {
+            term: term.into(),
+            language: None,
+            per_language: 5,
+            max_edits: 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchKind {
+    Prefix,
+    Fuzzy,
+}
+
+/// A normalized alias suggested for a term, with the pages it names.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AliasMatch {
+    pub kind: MatchKind,
+    pub language: Option<String>,
+    pub normalized_alias: String,
+    /// Smallest stored spelling of the alias.
+    pub alias: String,
+    pub page_count: i64,
+    /// Levenshtein distance in characters from the normalized term.
+    pub distance: usize,
+    /// `1 - distance / longer length`, in `[0, 1]`.
+    pub similarity: f64,
+}
+
+/// Levenshtein distance over characters.
+pub fn edit_distance(left: &str, right: &str) -> usize {
+    let right = right.chars().collect::<Vec<_>>();
+    let mut previous = (0..=right.len()).collect::<Vec<_>>();
+    let mut current = vec![0; right.len() + 1];
+    for (i, left_char) in left.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, right_char) in right.iter().enumerate() {
+            let substitution = previous[j] + usize::from(left_char != *right_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[right.len()]
+}
+
+/// Distinct character trigrams of `term`, as FTS5 strings ORed together; `None` when the term
+/// is shorter than three characters, which the trigram tokenizer cannot match.
+pub(crate) fn trigram_query(term: &str) -> Option<String> {
+    let chars = term.chars().collect::<Vec<_>>();
+    let mut trigrams = chars
+        .windows(3)
+        .map(|window| {
+            format!(
+                "\"{}\"",
+                window.iter().collect::<String>().replace('"', "\"\"")
+            )
+        })
+        .collect::<Vec<_>>();
+    trigrams.sort();
+    trigrams.dedup();
+    (!trigrams.is_empty()).then(|| trigrams.join(" OR "))
+}
+
+/// Scores `(language, normalized_alias, alias, page_count)` rows against `term`, drops those
+/// further than `max_edits`, orders by distance, then page count, then alias, and keeps the
+/// first `per_language` of each language.
+pub(crate) fn rank_matches(
+    term: &str,
+    kind: MatchKind,
+    rows: Vec<(Option<String>, String, String, i64)>,
+    max_edits: usize,
+    per_language: usize,
+) -> Vec<AliasMatch> {
+    let mut matches = rows
+        .into_iter()
+        .filter_map(|(language, normalized_alias, alias, page_count)| {
+            let distance = edit_distance(term, &normalized_alias);
+            let longer = term
+                .chars()
+                .count()
+                .max(normalized_alias.chars().count())
+                .max(1);
+            (distance <= max_edits).then(|| AliasMatch {
+                kind,
+                language,
+                similarity: 1.0 - distance as f64 / longer as f64,
+                normalized_alias,
+                alias,
+                page_count,
+                distance,
+            })
+        })
+        .collect::<Vec<_>>();
+    matches.sort_by(|left, right| {
+        left.distance
+            .cmp(&right.distance)
+            .then(right.page_count.cmp(&left.page_count))
+            .then_with(|| left.normalized_alias.cmp(&right.normalized_alias))
+            .then_with(|| left.language.cmp(&right.language))
+    });
+
+    let mut kept = HashMap::<Option<String>, usize>::new();
+    matches.retain(|candidate| {
+        let count = kept.entry(candidate.language.clone()).or_default();
+        *count += 1;
+        *count <= per_language
+    });
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distance_counts_character_edits() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("straße", "strasse"), 2);
+        assert_eq!(edit_distance("cat", "cat"), 0);
+    }
+
+    #[test]
+    fn trigram_queries_quote_distinct_trigrams() {
+        assert_eq!(
+            trigram_query("aaaa").as_deref(),
+            Some(r#""aaa""#),
+            "repeated trigrams collapse"
+        );
+        assert_eq!(
+            trigram_query("a\"bc").as_deref(),
+            Some(r#""""bc" OR "a""b""#)
+        );
+        assert_eq!(trigram_query("ab"), None);
+    }
+
+    #[test]
+    fn ranks_by_distance_and_limits_per_language() {
+        let row = |language: &str, alias: &str, pages: i64| {
+            (
+                Some(language.to_owned()),
+                alias.to_owned(),
+                alias.to_owned(),
+                pages,
+            )
+        };
+        let matches = rank_matches(
+            "cat",
+            MatchKind::Fuzzy,
+            vec![
+                row("English", "cut", 1),
+                row("English", "cat", 1),
+                row("English", "car", 5),
+                row("English", "catalog", 9),
+                row("German", "kat", 1),
+            ],
+            2,
+            2,
+        );
+        let ranked = matches
+            .iter()
+            .map(|hit| (hit.language.as_deref().unwrap_or(""), hit.alias.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ranked,
+            vec![("English", "cat"), ("English", "car"), ("German", "kat")]
+        );
+        assert_eq!(matches[0].similarity, 1.0);
+        assert!((matches[1].similarity - 2.0 / 3.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,462 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use tracing::{info, warn};
+
+use crate::config::MetricsConfig;
+use crate::http::{self, RequestHead};
+use crate::pipeline::RunMetrics;
+use crate::progress::write_atomically;
+
+const PREFIX: &str = "zimrs";
+/// Upper bounds, in seconds, of the DB write latency histogram buckets.
+const WRITE_BUCKETS: [f64; 14] = [
+    0.000_5, 0.001, 0.002_5, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// `RunMetrics` counters exported per edition, as `(name, help)`. Keep in step with
+/// [`run_counter_values`].
+const RUN_COUNTERS: [(&str, &str); 17] = [
+    ("scanned_entries", "Entries visited in the index window."),
+    ("filtered_entries", "Entries dropped by the selection."),
+    ("ingested_pages", "Pages written to the sinks."),
+    (
+        "unchanged_pages",
+        "Pages skipped because their content hash is unchanged.",
+    ),
+    (
+        "extracted_definitions",
+        "Definitions extracted from written pages.",
+    ),
+    (
+        "extracted_relations",
+        "Relations extracted from written pages.",
+    ),
+    (
+        "extraction_errors",
+        "Entries that failed to decode, extract or persist.",
+    ),
+    ("checkpoint_updates", "Checkpoints saved."),
+    (
+        "guard_truncated_pages",
+        "Pages truncated by extraction guards.",
+    ),
+    ("guard_skipped_pages", "Pages skipped by extraction guards."),
+    ("extraction_panics", "Panics caught during extraction."),
+    ("decompressed_bytes", "Blob bytes read out of clusters."),
+    ("queued_redirects", "Redirects queued for alias resolution."),
+    ("redirect_aliases", "Redirects stored as lemma aliases."),
+    (
+        "unresolved_redirects",
+        "Queued redirects whose target is not stored.",
+    ),
+    ("swept_pages", "Pages marked or deleted by the sweep."),
+    (
+        "missing_list_titles",
+        "Title-list entries not found in the archive.",
+    ),
+];
+
+fn run_counter_values(metrics: &RunMetrics) -> [u64; RUN_COUNTERS.len()] {
+    [
+        metrics.scanned_entries,
+        metrics.filtered_entries,
+        metrics.ingested_pages,
+        metrics.unchanged_pages,
+        metrics.extracted_definitions,
+        metrics.extracted_relations,
+        metrics.extraction_errors,
+        metrics.checkpoint_updates,
+        metrics.guard_truncated_pages,
+        metrics.guard_skipped_pages,
+        metrics.extraction_panics,
+        metrics.decompressed_bytes,
+        metrics.queued_redirects,
+        metrics.redirect_aliases,
+        metrics.unresolved_redirects,
+        metrics.swept_pages,
+        metrics.missing_list_titles,
+    ]
+}
+
+static GLOBAL: Lazy<MetricsRegistry> = Lazy::new(MetricsRegistry::default);
+
+/// The process-wide registry that `convert` and `reindex` report into.
+pub fn global() -> &'static MetricsRegistry {
+    &GLOBAL
+}
+
+/// Current values of every exported metric. Updates are cheap enough to make per entry.
+#[derive(Debug, Default)]
+pub struct MetricsRegistry {
+    state: Mutex<RegistryState>,
+}
+
+#[derive(Debug, Default)]
+struct RegistryState {
+    runs: BTreeMap<String, [u64; RUN_COUNTERS.len()]>,
+    queue_depths: BTreeMap<&'static str, u64>,
+    db_writes: BTreeMap<&'static str, Histogram>,
+    retries: BTreeMap<&'static str, u64>,
+    checkpoints: BTreeMap<String, SystemTime>,
+    reindexed_pages: u64,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Histogram {
+    buckets: [u64; WRITE_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl MetricsRegistry {
+    fn lock(&self) -> MutexGuard<'_, RegistryState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Replaces the counters of `metrics.edition` with the run's current values.
+    pub fn record_run(&self, metrics: &RunMetrics) {
+        let values = run_counter_values(metrics);
+        let mut state = self.lock();
+        match state.runs.get_mut(&metrics.edition) {
+            Some(current) => *current = values,
+            None => {
+                state.runs.insert(metrics.edition.clone(), values);
+            }
+        }
+    }
+
+    pub fn set_queue_depth(&self, queue: &'static str, depth: u64) {
+        self.lock().queue_depths.insert(queue, depth);
+    }
+
+    pub fn observe_db_write(&self, operation: &'static str, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        let mut state = self.lock();
+        let histogram = state.db_writes.entry(operation).or_default();
+        for (bucket, bound) in histogram.buckets.iter_mut().zip(WRITE_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        histogram.count += 1;
+        histogram.sum += seconds;
+    }
+
+    pub fn inc_retry(&self, operation: &'static str) {
+        *self.lock().retries.entry(operation).or_default() += 1;
+    }
+
+    pub fn checkpoint_saved(&self, checkpoint_name: &str) {
+        self.lock()
+            .checkpoints
+            .insert(checkpoint_name.to_owned(), SystemTime::now());
+    }
+
+    pub fn add_reindexed_pages(&self, pages: u64) {
+        self.lock().reindexed_pages += pages;
+    }
+
+    /// Renders every metric in the Prometheus text exposition format (version 0.0.4).
+    pub fn render(&self) -> String {
+        let state = self.lock();
+        let mut out = String::new();
+
+        for (position, (name, help)) in RUN_COUNTERS.iter().enumerate() {
+            write_header(&mut out, &format!("{name}_total"), help, "counter");
+            for (edition, values) in &state.runs {
+                let _ = writeln!(
+                    out,
+                    "{PREFIX}_{name}_total{{edition=\"{}\"}} {}",
+                    escape_label(edition),
+                    values[position]
+                );
+            }
+        }
+
+        write_header(
+            &mut out,
+            "queue_depth",
+            "Items waiting in an in-process queue.",
+            "gauge",
+        );
+        for (queue, depth) in &state.queue_depths {
+            let _ = writeln!(out, "{PREFIX}_queue_depth{{queue=\"{queue}\"}} {depth}");
+        }
+
+        write_header(
+            &mut out,
+            "db_write_seconds",
+            "Latency of database writes.",
+            "histogram",
+        );
+        for (operation, histogram) in &state.db_writes {
+            for (bound, count) in WRITE_BUCKETS.iter().zip(histogram.buckets) {
+                let _ = writeln!(
+                    out,
+                    "{PREFIX}_db_write_seconds_bucket{{op=\"{operation}\",le=\"{bound}\"}} {count}"
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{PREFIX}_db_write_seconds_bucket{{op=\"{operation}\",le=\"+Inf\"}} {}",
+                histogram.count
+            );
+            let _ = writeln!(
+                out,
+                "{PREFIX}_db_write_seconds_sum{{op=\"{operation}\"}} {}",
+                histogram.sum
+            );
+            let _ = writeln!(
+                out,
+                "{PREFIX}_db_write_seconds_count{{op=\"{operation}\"}} {}",
+                histogram.count
+            );
+        }
+
+        write_header(
+            &mut out,
+            "retries_total",
+            "Operations retried after a transient failure.",
+            "counter",
+        );
+        for (operation, retries) in &state.retries {
+            let _ = writeln!(
+                out,
+                "{PREFIX}_retries_total{{operation=\"{operation}\"}} {retries}"
+            );
+        }
+
+        write_header(
+            &mut out,
+            "checkpoint_age_seconds",
+            "Seconds since the checkpoint was last saved.",
+            "gauge",
+        );
+        let now = SystemTime::now();
+        for (checkpoint, saved_at) in &state.checkpoints {
+            let age = now.duration_since(*saved_at).unwrap_or_default();
+            let _ = writeln!(
+                out,
+                "{PREFIX}_checkpoint_age_seconds{{checkpoint=\"{}\"}} {}",
+                escape_label(checkpoint),
+                age.as_secs_f64()
+            );
+        }
+
+        write_header(
+            &mut out,
+            "reindexed_pages_total",
+            "Pages refreshed by incremental reindex.",
+            "counter",
+        );
+        let _ = writeln!(
+            out,
+            "{PREFIX}_reindexed_pages_total {}",
+            state.reindexed_pages
+        );
+
+        out
+    }
+}
+
+fn write_header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {PREFIX}_{name} {help}");
+    let _ = writeln!(out, "# TYPE {PREFIX}_{name} {kind}");
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Serves [`global`] on `metrics.listen` and rewrites `metrics.textfile_path`, each on its own
+/// thread, until shut down or dropped.
+pub struct MetricsExporter {
+    stop: Arc<AtomicBool>,
+    threads: Vec<JoinHandle<()>>,
+    local_addr: Option<SocketAddr>,
+    textfile_path: Option<PathBuf>,
+}
+
+impl MetricsExporter {
+    /// Starts the configured outputs, or returns `None` when `[metrics]` configures neither.
+    pub fn start(config: &MetricsConfig) -> Result<Option<Self>> {
+        if config.listen.is_none() && config.textfile_path.is_none() {
+            return Ok(None);
+        }
+
+        let mut exporter = Self {
+            stop: Arc::new(AtomicBool::new(false)),
+            threads: Vec::new(),
+            local_addr: None,
+            textfile_path: config.textfile_path.clone(),
+        };
+
+        if let Some(listen) = &config.listen {
+            let listener = TcpListener::bind(listen)
+                .with_context(|| format!("failed to bind metrics endpoint {listen}"))?;
+            listener.set_nonblocking(true)?;
+            let local_addr = listener.local_addr()?;
+            info!(%local_addr, "serving metrics on /metrics");
+
+            let stop = Arc::clone(&exporter.stop);
+            exporter.local_addr = Some(local_addr);
+            exporter.threads.push(thread::spawn(move || {
+                http::serve(&listener, &stop, "metrics", answer_request);
+            }));
+        }
+
+        if let Some(path) = config.textfile_path.clone() {
+            let stop = Arc::clone(&exporter.stop);
+            let interval = Duration::from_millis(config.textfile_interval_ms.max(1));
+            exporter.threads.push(thread::spawn(move || {
+                loop {
+                    if let Err(error) = write_atomically(&path, global().render().as_bytes()) {
+                        warn!(path = %path.display(), error = %error, "failed to write metrics textfile");
+                    }
+                    let written = Instant::now();
+                    while written.elapsed() < interval {
+                        if stop.load(Ordering::Relaxed) {
+                            return;
+                        }
+                        thread::sleep(POLL_INTERVAL.min(interval));
+                    }
+                }
+            }));
+        }
+
+        Ok(Some(exporter))
+    }
+
+    /// Bound address of the `/metrics` endpoint; useful with port 0.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    /// Stops the exporter threads and writes the textfile one last time, so it holds the
+    /// final counters.
+    pub fn shutdown(mut self) -> Result<()> {
+        self.stop_threads();
+        match &self.textfile_path {
+            Some(path) => write_atomically(path, global().render().as_bytes()),
+            None => Ok(()),
+        }
+    }
+
+    fn stop_threads(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        for handle in self.threads.drain(..) {
+            if handle.join().is_err() {
+                warn!("metrics exporter thread panicked");
+            }
+        }
+    }
+}
+
+impl Drop for MetricsExporter {
+    fn drop(&mut self) {
+        self.stop_threads();
+    }
+}
+
+fn answer_request(mut stream: TcpStream) -> io::Result<()> {
+    let request = RequestHead::read(&mut stream, Duration::from_secs(2))?;
+    let (status, content_type, body) = match (request.method.as_str(), request.target.as_str()) {
+        ("GET", "/metrics") => (
+            "200 OK",
+            "text/plain; version=0.0.4; charset=utf-8",
+            global().render(),
+        ),
+        ("GET", _) => ("404 Not Found", "text/plain", "not found\n".to_owned()),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "method not allowed\n".to_owned(),
+        ),
+    };
+    http::write_response(&mut stream, status, content_type, &[], &body)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+
+    use super::*;
+
+    #[test]
+    fn registry_renders_prometheus_text() {
+        let registry = MetricsRegistry::default();
+        registry.record_run(&RunMetrics {
+            edition: "en".to_owned(),
+            scanned_entries: 12,
+            ingested_pages: 9,
+            ..RunMetrics::default()
+        });
+        registry.set_queue_depth("extraction", 3);
+        registry.observe_db_write("page", Duration::from_millis(3));
+        registry.observe_db_write("page", Duration::from_secs(20));
+        registry.inc_retry("postgres_connect");
+        registry.checkpoint_saved("default");
+
+        let text = registry.render();
+        assert!(text.contains("# TYPE zimrs_scanned_entries_total counter\n"));
+        assert!(text.contains("zimrs_scanned_entries_total{edition=\"en\"} 12\n"));
+        assert!(text.contains("zimrs_ingested_pages_total{edition=\"en\"} 9\n"));
+        assert!(text.contains("zimrs_queue_depth{queue=\"extraction\"} 3\n"));
+        assert!(text.contains("zimrs_db_write_seconds_bucket{op=\"page\",le=\"0.001\"} 0\n"));
+        assert!(text.contains("zimrs_db_write_seconds_bucket{op=\"page\",le=\"0.005\"} 1\n"));
+        assert!(text.contains("zimrs_db_write_seconds_bucket{op=\"page\",le=\"+Inf\"} 2\n"));
+        assert!(text.contains("zimrs_db_write_seconds_count{op=\"page\"} 2\n"));
+        assert!(text.contains("zimrs_retries_total{operation=\"postgres_connect\"} 1\n"));
+        assert!(text.contains("zimrs_checkpoint_age_seconds{checkpoint=\"default\"} "));
+        assert_eq!(escape_label("a\"b\\c"), "a\\\"b\\\\c");
+    }
+
+    #[test]
+    fn exporter_serves_metrics_and_writes_the_textfile() {
+        let workdir = tempfile::tempdir().expect("tempdir");
+        let textfile_path = workdir.path().join("zimrs.prom");
+        let config = MetricsConfig {
+            listen: Some("127.0.0.1:0".to_owned()),
+            textfile_path: Some(textfile_path.clone()),
+            textfile_interval_ms: 60_000,
+        };
+        let exporter = MetricsExporter::start(&config)
+            .expect("start exporter")
+            .expect("exporter configured");
+        global().add_reindexed_pages(0);
+
+        let addr = exporter.local_addr().expect("listening");
+        let scrape = |path: &str| {
+            let mut stream = TcpStream::connect(addr).expect("connect");
+            write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").expect("send");
+            let mut response = String::new();
+            stream.read_to_string(&mut response).expect("read");
+            response
+        };
+        let response = scrape("/metrics");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("text/plain; version=0.0.4"));
+        assert!(response.contains("# TYPE zimrs_reindexed_pages_total counter"));
+        assert!(scrape("/").starts_with("HTTP/1.1 404"));
+
+        exporter.shutdown().expect("shutdown");
+        let textfile = std::fs::read_to_string(&textfile_path).expect("read textfile");
+        assert!(textfile.contains("zimrs_reindexed_pages_total "));
+    }
+}
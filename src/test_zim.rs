@@ -0,0 +1,252 @@
+//! Test fixtures: tiny uncompressed ZIM archives, and extracted pages for tests that write
+//! straight to a database. The ZIM checksum is zeroed, so fixtures open fine but fail
+//! `verify_checksum`.
+
+use std::fs;
+use std::path::Path;
+
+use crate::extractor::{ExtractedAlias, ExtractedDefinition, ExtractedPage};
+use crate::normalization::canonicalize_lemma;
+
+const MIME_HTML: u16 = 0;
+const MIME_TEXT: u16 = 1;
+const MIME_REDIRECT: u16 = 0xffff;
+
+#[derive(Debug, Clone)]
+pub enum FixtureEntry {
+    Article {
+        namespace: u8,
+        url: String,
+        title: String,
+        html: String,
+    },
+    Metadata {
+        key: String,
+        value: String,
+    },
+    Redirect {
+        namespace: u8,
+        url: String,
+        title: String,
+        target_url: String,
+    },
+}
+
+impl FixtureEntry {
+    pub fn article(url: &str, html: &str) -> Self {
+        Self::Article {
+            namespace: b'A',
+            url: url.to_owned(),
+            title: url.to_owned(),
+            html: html.to_owned(),
+        }
+    }
+
+    pub fn metadata(key: &str, value: &str) -> Self {
+        Self::Metadata {
+            key: key.to_owned(),
+            value: value.to_owned(),
+        }
+    }
+
+    pub fn redirect(url: &str, target_url: &str) -> Self {
+        Self::Redirect {
+            namespace: b'A',
+            url: url.to_owned(),
+            title: url.to_owned(),
+            target_url: target_url.to_owned(),
+        }
+    }
+
+    fn key(&self) -> (u8, &str) {
+        match self {
+            Self::Article { namespace, url, .. } | Self::Redirect { namespace, url, .. } => {
+                (*namespace, url)
+            }
+            Self::Metadata { key, .. } => (b'M', key),
+        }
+    }
+
+    fn title(&self) -> &str {
+        match self {
+            Self::Article { title, .. } | Self::Redirect { title, .. } => title,
+            Self::Metadata { key, .. } => key,
+        }
+    }
+}
+
+/// An `en` page titled `url` with `(language, text)` senses, numbered per language in order, and
+/// `(language, alias)` title aliases. Override other fields with struct update syntax.
+pub fn page(url: &str, definitions: &[(&str, &str)], aliases: &[(&str, &str)]) -> ExtractedPage {
+    let mut senses = Vec::<ExtractedDefinition>::new();
+    for (language, text) in definitions {
+        let order_in_language = senses
+            .iter()
+            .filter(|sense| sense.language == *language)
+            .count() as i64;
+        senses.push(ExtractedDefinition {
+            language: (*language).to_owned(),
+            order_in_language,
+            text: (*text).to_owned(),
+            normalized_text: text.to_lowercase(),
+            confidence: 1.0,
+        });
+    }
+
+    ExtractedPage {
+        edition: "en".to_owned(),
+        url: url.to_owned(),
+        title: url.to_owned(),
+        namespace: "A".to_owned(),
+        mime_type: "text/html".to_owned(),
+        cluster_idx: Some(0),
+        blob_idx: Some(0),
+        redirect_url: None,
+        content_sha256: None,
+        raw_html: Some("<p>raw</p>".to_owned()),
+        plain_text: Some(String::new()),
+        extraction_confidence: 0.5,
+        definitions: senses,
+        relations: Vec::new(),
+        aliases: aliases
+            .iter()
+            .map(|(language, alias)| ExtractedAlias {
+                language: Some((*language).to_owned()),
+                alias: (*alias).to_owned(),
+                normalized_alias: canonicalize_lemma(alias),
+                source: "title".to_owned(),
+            })
+            .collect(),
+    }
+}
+
+/// Writes `entries` as a ZIM v5 file with one uncompressed cluster. Entries are sorted into URL
+/// order, so callers can list them in any order; redirect targets are resolved by URL within the
+/// same namespace.
+pub fn write_test_zim(path: &Path, entries: &[FixtureEntry]) {
+    let mut entries = entries.to_vec();
+    entries.sort_by(|a, b| a.key().cmp(&b.key()));
+
+    let url_index = |namespace: u8, url: &str| -> u32 {
+        entries
+            .iter()
+            .position(|entry| entry.key() == (namespace, url))
+            .unwrap_or_else(|| panic!("redirect target {url} missing from fixture")) as u32
+    };
+
+    let mut blobs: Vec<&[u8]> = Vec::new();
+    let mut dirents = Vec::with_capacity(entries.len());
+    for entry in &entries {
+        let mut dirent = Vec::new();
+        match entry {
+            FixtureEntry::Article {
+                namespace,
+                url,
+                title,
+                html,
+            } => {
+                push_content_dirent(&mut dirent, MIME_HTML, *namespace, blobs.len() as u32);
+                blobs.push(html.as_bytes());
+                push_names(&mut dirent, url, title);
+            }
+            FixtureEntry::Metadata { key, value } => {
+                push_content_dirent(&mut dirent, MIME_TEXT, b'M', blobs.len() as u32);
+                blobs.push(value.as_bytes());
+                push_names(&mut dirent, key, "");
+            }
+            FixtureEntry::Redirect {
+                namespace,
+                url,
+                title,
+                target_url,
+            } => {
+                dirent.extend_from_slice(&MIME_REDIRECT.to_le_bytes());
+                dirent.push(0);
+                dirent.push(*namespace);
+                dirent.extend_from_slice(&0_u32.to_le_bytes());
+                dirent.extend_from_slice(&url_index(*namespace, target_url).to_le_bytes());
+                push_names(&mut dirent, url, title);
+            }
+        }
+        dirents.push(dirent);
+    }
+
+    let mut title_order: Vec<u32> = (0..entries.len() as u32).collect();
+    title_order.sort_by(|a, b| {
+        let a = &entries[*a as usize];
+        let b = &entries[*b as usize];
+        (a.key().0, a.title()).cmp(&(b.key().0, b.title()))
+    });
+
+    let mut out = vec![0_u8; 80];
+    out.extend_from_slice(b"text/html\0text/plain\0\0");
+
+    let mut dirent_offsets = Vec::with_capacity(dirents.len());
+    for dirent in &dirents {
+        dirent_offsets.push(out.len() as u64);
+        out.extend_from_slice(dirent);
+    }
+
+    let url_ptr_pos = out.len() as u64;
+    for offset in &dirent_offsets {
+        out.extend_from_slice(&offset.to_le_bytes());
+    }
+
+    let title_ptr_pos = out.len() as u64;
+    for idx in &title_order {
+        out.extend_from_slice(&idx.to_le_bytes());
+    }
+
+    let cluster_ptr_pos = out.len() as u64;
+    let cluster_pos = cluster_ptr_pos + 8;
+    out.extend_from_slice(&cluster_pos.to_le_bytes());
+
+    // Uncompressed cluster: one info byte, then n + 1 blob offsets relative to the offset table.
+    out.push(0x01);
+    let mut blob_offset = ((blobs.len() + 1) * 4) as u32;
+    for blob in &blobs {
+        out.extend_from_slice(&blob_offset.to_le_bytes());
+        blob_offset += blob.len() as u32;
+    }
+    out.extend_from_slice(&blob_offset.to_le_bytes());
+    for blob in &blobs {
+        out.extend_from_slice(blob);
+    }
+
+    let checksum_pos = out.len() as u64;
+    out.extend_from_slice(&[0_u8; 16]);
+
+    let mut header = Vec::with_capacity(80);
+    header.extend_from_slice(&72_173_914_u32.to_le_bytes());
+    header.extend_from_slice(&5_u16.to_le_bytes());
+    header.extend_from_slice(&0_u16.to_le_bytes());
+    header.extend_from_slice(&[0x5a; 16]);
+    header.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+    header.extend_from_slice(&1_u32.to_le_bytes());
+    header.extend_from_slice(&url_ptr_pos.to_le_bytes());
+    header.extend_from_slice(&title_ptr_pos.to_le_bytes());
+    header.extend_from_slice(&cluster_ptr_pos.to_le_bytes());
+    header.extend_from_slice(&80_u64.to_le_bytes());
+    header.extend_from_slice(&u32::MAX.to_le_bytes());
+    header.extend_from_slice(&u32::MAX.to_le_bytes());
+    header.extend_from_slice(&checksum_pos.to_le_bytes());
+    out[..80].copy_from_slice(&header);
+
+    fs::write(path, out).expect("write test zim");
+}
+
+fn push_content_dirent(dirent: &mut Vec<u8>, mime: u16, namespace: u8, blob_idx: u32) {
+    dirent.extend_from_slice(&mime.to_le_bytes());
+    dirent.push(0);
+    dirent.push(namespace);
+    dirent.extend_from_slice(&0_u32.to_le_bytes());
+    dirent.extend_from_slice(&0_u32.to_le_bytes());
+    dirent.extend_from_slice(&blob_idx.to_le_bytes());
+}
+
+fn push_names(dirent: &mut Vec<u8>, url: &str, title: &str) {
+    dirent.extend_from_slice(url.as_bytes());
+    dirent.push(0);
+    dirent.extend_from_slice(title.as_bytes());
+    dirent.push(0);
+}